@@ -1,2 +1,70 @@
 // implement instructions here
 // reference: qemu/target/i386/tcg/decode-new.c.inc
+
+use crate::{CPU, GPRName, FLAGSName, IPName, MemoryError, Utilities};
+use crate::registers::{CF, PF, AF, ZF, SF, DF};
+
+/// The flags LAHF and SAHF transfer between AH and RFLAGS.
+const AH_FLAGS: u64 = SF | ZF | AF | PF | CF;
+/// Bit 1 of RFLAGS is reserved and always reads as 1.
+const RESERVED_FLAG: u64 = 1 << 1;
+
+/// An enumeration of the instructions `CPU::step` can execute.
+///
+/// Only instructions without explicit operands are supported; each one acts on the registers,
+/// flags or stack implicitly, as its name describes.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub enum Instruction {
+    Nop, Clc, Stc, Cmc, Cld, Std, Cdqe, Cqo, Lahf, Sahf, Pushfq, Popfq
+}
+
+impl Instruction {
+    /// Returns the length in bytes of the instruction's encoding in 64-bit mode.
+    pub fn length(self) -> u64 {
+        match self {
+            Instruction::Cdqe | Instruction::Cqo => 2,
+            _ => 1,
+        }
+    }
+}
+
+/// Executes a single instruction and advances RIP past it.
+///
+/// # Arguments
+/// * `cpu` - The CPU to execute the instruction on.
+/// * `instruction` - The instruction to execute.
+///
+/// # Returns
+/// `Ok(())` on success, or the error met while accessing the stack, in which case the CPU is unchanged.
+pub(crate) fn execute(cpu: &mut CPU, instruction: Instruction) -> Result<(), MemoryError> {
+    let flags = cpu.registers.get_flags_value(FLAGSName::RFLAGS);
+    match instruction {
+        Instruction::Nop => {}
+        Instruction::Clc => cpu.registers.set_flags_value(FLAGSName::RFLAGS, flags & !CF),
+        Instruction::Stc => cpu.registers.set_flags_value(FLAGSName::RFLAGS, flags | CF),
+        Instruction::Cmc => cpu.registers.set_flags_value(FLAGSName::RFLAGS, flags ^ CF),
+        Instruction::Cld => cpu.registers.set_flags_value(FLAGSName::RFLAGS, flags & !DF),
+        Instruction::Std => cpu.registers.set_flags_value(FLAGSName::RFLAGS, flags | DF),
+        Instruction::Cdqe => {
+            let eax = cpu.registers.get_gpr_value(GPRName::EAX);
+            cpu.registers.set_gpr_value(GPRName::RAX, Utilities::sext32_64(eax as u32));
+        }
+        Instruction::Cqo => {
+            let rax = cpu.registers.get_gpr_as_i64(GPRName::RAX);
+            cpu.registers.set_gpr_i64(GPRName::RDX, rax >> 63);
+        }
+        Instruction::Lahf => cpu.registers.set_gpr_value(GPRName::AH, flags & AH_FLAGS | RESERVED_FLAG),
+        Instruction::Sahf => {
+            let ah = cpu.registers.get_gpr_value(GPRName::AH);
+            cpu.registers.set_flags_value(FLAGSName::RFLAGS, flags & !AH_FLAGS | ah & AH_FLAGS);
+        }
+        Instruction::Pushfq => cpu.push(flags | RESERVED_FLAG)?,
+        Instruction::Popfq => {
+            let value = cpu.pop()?;
+            cpu.registers.set_flags_value(FLAGSName::RFLAGS, value | RESERVED_FLAG);
+        }
+    }
+    let rip = cpu.registers.get_ip_value(IPName::RIP);
+    cpu.registers.set_ip_value(IPName::RIP, rip.wrapping_add(instruction.length()));
+    Ok(())
+}
@@ -16,6 +16,14 @@ extern crate primitive_types;
 pub use primitive_types::U256 as u256;
 pub use primitive_types::U512 as u512;
 
+/// Emits a `tracing::debug!` event when the `tracing` feature is enabled, and nothing otherwise.
+macro_rules! trace_event {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::debug!($($arg)*);
+    };
+}
+
 mod registers;
 mod memory;
 mod utilities;
@@ -26,13 +34,70 @@ pub use registers::VecRegName;
 pub use registers::GPRName;
 pub use registers::FLAGSName;
 pub use registers::IPName;
+pub use registers::CPUMode;
+pub use registers::KMaskName;
+pub use registers::DeserializeError;
+pub use registers::REGISTERS_BYTES_LEN;
 
 pub use memory::Memory;
+pub use memory::MemoryError;
+pub use memory::AlignmentError;
+pub use memory::Protection;
+pub use memory::MmioHandler;
+pub use memory::ClearOptions;
+pub use memory::Region;
+pub use memory::RegionKind;
+pub use memory::FragReport;
+pub use memory::AccessKind;
+pub use memory::Translator;
+pub use memory::UninitHandler;
+pub use memory::PageTable;
+pub use memory::PagePerms;
+pub use memory::PageFaultError;
+pub use memory::MemoryIO;
+pub use memory::Endianness;
+
+pub use instructions::Instruction;
 
 pub use utilities::Utilities;
+pub use utilities::LengthMismatch;
+pub use utilities::ByteLengthError;
+pub use utilities::FlagsDelta;
+pub use utilities::RegisterRef;
+pub use utilities::ParseHexError;
+pub use utilities::Narrowing;
+pub use utilities::RoundingMode;
+pub use utilities::RandomizeOptions;
 
 pub use registers::SectionCompatible;
 
+use std::collections::HashMap;
+
+/// The size of the guard page `CPU::setup_stack` places below the stack.
+const STACK_GUARD_SIZE: u64 = 4096;
+/// The number of cycles `CPU::step` charges for an instruction without a configured latency.
+const DEFAULT_LATENCY: u64 = 1;
+/// The vendor string `CPU::cpuid` reports in leaf 0 unless reconfigured.
+const DEFAULT_CPUID_VENDOR: &[u8; 12] = b"GenuineIntel";
+/// The ECX feature bits of CPUID leaf 1 unless reconfigured: SSE3, PCLMULQDQ, SSSE3, SSE4.1,
+/// SSE4.2 and POPCNT.
+const DEFAULT_CPUID_FEATURES_ECX: u32 = 1 << 0 | 1 << 1 | 1 << 9 | 1 << 19 | 1 << 20 | 1 << 23;
+/// The EDX feature bits of CPUID leaf 1 unless reconfigured: TSC, CMOV, SSE and SSE2.
+const DEFAULT_CPUID_FEATURES_EDX: u32 = 1 << 4 | 1 << 15 | 1 << 25 | 1 << 26;
+
+/// The registers captured by `CPU::syscall`, following the Linux x86-64 system call convention.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct SyscallInfo {
+    /// The system call number, from RAX.
+    pub number: u64,
+    /// The arguments, from RDI, RSI, RDX, R10, R8 and R9 in that order.
+    pub args: [u64; 6],
+}
+
+/// The signature of a handler installed with `CPU::set_syscall_handler`. It receives the captured
+/// registers and the CPU, and returns the value placed in RAX.
+pub type SyscallHandler = dyn Fn(SyscallInfo, &mut CPU) -> u64;
+
 /// Represents the CPU context in the emulator.
 ///
 /// Contains registers and memory components necessary for CPU operations.
@@ -44,6 +109,11 @@ pub use registers::SectionCompatible;
 pub struct CPU {
     pub registers: Registers,
     pub memory: Memory,
+    aligned_simd: bool,
+    cycle_count: u64,
+    latencies: HashMap<Instruction, u64>,
+    cpuid_leaves: HashMap<(u32, Option<u32>), [u32; 4]>,
+    syscall_handler: Option<Box<SyscallHandler>>,
 }
 
 impl CPU {
@@ -53,18 +123,679 @@ impl CPU {
     /// This allows for a customizable memory layout.
     ///
     /// # Arguments
-    /// * `base` - A `usize` representing the base address for the CPU's memory.
+    /// * `base` - A `u64` representing the base address for the CPU's memory.
     ///
     /// # Returns
     /// Returns a new `CPU` instance with initialized registers and memory.
-    pub fn new(base: usize) -> Self {
+    pub fn new(base: u64) -> Self {
         CPU {
             registers: Registers::new(),
-            memory: Memory::new(base)
+            memory: Memory::new(base),
+            aligned_simd: false,
+            cycle_count: 0,
+            latencies: HashMap::new(),
+            cpuid_leaves: default_cpuid_leaves(),
+            syscall_handler: None,
+        }
+    }
+
+    /// Executes a single instruction, advances RIP past it and charges its latency to the cycle counter.
+    ///
+    /// # Arguments
+    /// * `instruction` - The instruction to execute.
+    ///
+    /// # Returns
+    /// `Ok(())` on success, or the error met while executing the instruction, in which case neither
+    /// the CPU state nor the cycle counter changes.
+    pub fn step(&mut self, instruction: Instruction) -> Result<(), MemoryError> {
+        trace_event!(function = "CPU::step", instruction = ?instruction, rip = self.registers.get_ip_value(IPName::RIP));
+        instructions::execute(self, instruction)?;
+        self.tick(self.instruction_latency(instruction));
+        Ok(())
+    }
+
+    /// Advances the cycle counter.
+    ///
+    /// # Arguments
+    /// * `cycles` - The number of cycles to add. The counter wraps around on overflow.
+    pub fn tick(&mut self, cycles: u64) {
+        self.cycle_count = self.cycle_count.wrapping_add(cycles);
+    }
+
+    /// Returns the number of cycles elapsed since the CPU was created or the counter was last reset.
+    pub fn get_cycle_count(&self) -> u64 {
+        self.cycle_count
+    }
+
+    /// Resets the cycle counter to 0.
+    pub fn reset_cycle_count(&mut self) {
+        self.cycle_count = 0;
+    }
+
+    /// Reads the cycle counter into EDX:EAX, as `RDTSC` does.
+    ///
+    /// The low 32 bits go to EAX and the high 32 bits to EDX; the upper halves of RAX and RDX are
+    /// cleared. No flag is affected.
+    pub fn rdtsc(&mut self) {
+        self.registers.set_gpr_value(GPRName::EAX, self.cycle_count & 0xFFFF_FFFF);
+        self.registers.set_gpr_value(GPRName::EDX, self.cycle_count >> 32);
+    }
+
+    /// Sets the number of cycles `step` charges for an instruction.
+    ///
+    /// Instructions without a configured latency take 1 cycle.
+    ///
+    /// # Arguments
+    /// * `instr` - The instruction to configure.
+    /// * `latency` - The number of cycles the instruction takes.
+    pub fn set_instruction_latency(&mut self, instr: Instruction, latency: u64) {
+        self.latencies.insert(instr, latency);
+    }
+
+    /// Returns the number of cycles `step` charges for an instruction.
+    pub fn instruction_latency(&self, instr: Instruction) -> u64 {
+        self.latencies.get(&instr).copied().unwrap_or(DEFAULT_LATENCY)
+    }
+
+    /// Executes `CPUID`, reading the leaf from EAX and the subleaf from ECX.
+    ///
+    /// The result is written to EAX, EBX, ECX and EDX, clearing their upper halves. A subleaf
+    /// configured with `set_cpuid_subleaf` takes precedence over the leaf's own data; leaves that
+    /// were never configured return all zeros.
+    ///
+    /// By default leaf 0 reports 1 as the highest leaf and `GenuineIntel` as the vendor, and leaf 1
+    /// reports SSE through SSE4.2, POPCNT and PCLMULQDQ among its feature bits.
+    pub fn cpuid(&mut self) {
+        let leaf = self.registers.get_gpr_value(GPRName::EAX) as u32;
+        let subleaf = self.registers.get_gpr_value(GPRName::ECX) as u32;
+        let result = self.cpuid_leaves.get(&(leaf, Some(subleaf)))
+            .or_else(|| self.cpuid_leaves.get(&(leaf, None)))
+            .copied()
+            .unwrap_or_default();
+        for (reg, value) in [GPRName::EAX, GPRName::EBX, GPRName::ECX, GPRName::EDX].into_iter().zip(result) {
+            self.registers.set_gpr_value(reg, value as u64);
+        }
+    }
+
+    /// Sets the data `cpuid` returns for a leaf, whatever the subleaf.
+    ///
+    /// Leaf 1 holds the feature bits in ECX and EDX. Raise the highest leaf reported in EAX of
+    /// leaf 0 when configuring leaves above it.
+    ///
+    /// # Arguments
+    /// * `leaf` - The leaf number, as read from EAX.
+    /// * `eax`, `ebx`, `ecx`, `edx` - The values to return.
+    pub fn set_cpuid_leaf(&mut self, leaf: u32, eax: u32, ebx: u32, ecx: u32, edx: u32) {
+        self.cpuid_leaves.insert((leaf, None), [eax, ebx, ecx, edx]);
+    }
+
+    /// Sets the data `cpuid` returns for one subleaf of a leaf, such as leaf 7 subleaf 0.
+    ///
+    /// # Arguments
+    /// * `leaf` - The leaf number, as read from EAX.
+    /// * `subleaf` - The subleaf number, as read from ECX.
+    /// * `values` - The values to return in EAX, EBX, ECX and EDX, in that order.
+    pub fn set_cpuid_subleaf(&mut self, leaf: u32, subleaf: u32, values: [u32; 4]) {
+        self.cpuid_leaves.insert((leaf, Some(subleaf)), values);
+    }
+
+    /// Executes `SYSCALL`, handing the call to the installed handler in place of a kernel.
+    ///
+    /// The address of the next instruction (RIP + 2) is saved in RCX and RFLAGS in R11, as the
+    /// instruction does. The handler, if any, is then called and its return value stored in RAX;
+    /// without a handler RAX is left unchanged. Finally RIP advances past the instruction, as if the
+    /// kernel had returned with `SYSRET`.
+    ///
+    /// # Returns
+    /// The system call number and arguments as they were at the point of the call.
+    pub fn syscall(&mut self) -> SyscallInfo {
+        let info = SyscallInfo {
+            number: self.registers.get_gpr_value(GPRName::RAX),
+            args: [GPRName::RDI, GPRName::RSI, GPRName::RDX, GPRName::R10, GPRName::R8, GPRName::R9]
+                .map(|reg| self.registers.get_gpr_value(reg)),
+        };
+        let next = self.registers.get_ip_value(IPName::RIP).wrapping_add(2);
+        self.registers.set_gpr_value(GPRName::RCX, next);
+        self.registers.set_gpr_value(GPRName::R11, self.registers.get_flags_value(FLAGSName::RFLAGS));
+        // the handler is taken out while it runs so that it can borrow the CPU mutably
+        if let Some(handler) = self.syscall_handler.take() {
+            let result = handler(info, self);
+            self.registers.set_gpr_value(GPRName::RAX, result);
+            self.syscall_handler.get_or_insert(handler);
+        }
+        self.registers.set_ip_value(IPName::RIP, next);
+        info
+    }
+
+    /// Executes `SYSRET` to 64-bit code, returning from a system call.
+    ///
+    /// RIP is loaded from RCX and RFLAGS from R11, with the reserved bit 1 set.
+    pub fn sysret(&mut self) {
+        self.registers.set_ip_value(IPName::RIP, self.registers.get_gpr_value(GPRName::RCX));
+        self.registers.set_flags_value(FLAGSName::RFLAGS, self.registers.get_gpr_value(GPRName::R11) | 1 << 1);
+    }
+
+    /// Installs the handler `syscall` calls, replacing any previous one.
+    ///
+    /// # Arguments
+    /// * `handler` - The function servicing system calls.
+    pub fn set_syscall_handler(&mut self, handler: Box<SyscallHandler>) {
+        self.syscall_handler = Some(handler);
+    }
+
+    /// Removes the handler installed with `set_syscall_handler`.
+    pub fn clear_syscall_handler(&mut self) {
+        self.syscall_handler = None;
+    }
+
+    /// Sets the operating mode of the CPU.
+    ///
+    /// In 32-bit mode only SIMD registers 0 to 7 are accessible; 64-bit mode exposes all 32.
+    pub fn set_mode(&mut self, mode: CPUMode) {
+        self.registers.set_mode(mode);
+    }
+
+    /// Returns the operating mode of the CPU.
+    pub fn mode(&self) -> CPUMode {
+        self.registers.mode()
+    }
+
+    /// Enables or disables alignment checking for `simd_load` and `simd_store`.
+    ///
+    /// When enabled, the address of a SIMD transfer must be a multiple of the register width in bytes,
+    /// as for `MOVAPS`; otherwise unaligned transfers are allowed, as for `MOVUPS`.
+    pub fn set_aligned_simd(&mut self, aligned: bool) {
+        self.aligned_simd = aligned;
+    }
+
+    /// Returns whether SIMD transfers must be aligned.
+    pub fn is_aligned_simd(&self) -> bool {
+        self.aligned_simd
+    }
+
+    /// Loads a SIMD register from memory.
+    ///
+    /// Reads 16, 32 or 64 bytes depending on `reg_type` and stores them in the register in little endian order.
+    /// Loading an XMM or YMM register clears the upper bits of the underlying ZMM register.
+    ///
+    /// # Arguments
+    /// * `reg_type` - The type of SIMD register to load.
+    /// * `reg_index` - The index of the register.
+    /// * `address` - The address of the first byte to load.
+    ///
+    /// # Returns
+    /// `Ok(())` on success, or the error met while reading; the register is unchanged on error.
+    pub fn simd_load(&mut self, reg_type: VecRegName, reg_index: usize, address: u64) -> Result<(), MemoryError> {
+        let width = reg_type.width_bytes();
+        self.check_simd_alignment(address, width)?;
+        let mut bytes = vec![0u8; width];
+        self.memory.read_exact(address, &mut bytes)?;
+        self.registers.set_by_sections::<u8>(reg_type, reg_index, bytes);
+        Ok(())
+    }
+
+    /// Stores a SIMD register to memory.
+    ///
+    /// Writes 16, 32 or 64 bytes depending on `reg_type` in little endian order.
+    ///
+    /// # Arguments
+    /// * `reg_type` - The type of SIMD register to store.
+    /// * `reg_index` - The index of the register.
+    /// * `address` - The address of the first byte to store.
+    ///
+    /// # Returns
+    /// `Ok(())` on success, or the error met while writing, in which case nothing is written.
+    pub fn simd_store(&mut self, reg_type: VecRegName, reg_index: usize, address: u64) -> Result<(), MemoryError> {
+        let width = reg_type.width_bytes();
+        self.check_simd_alignment(address, width)?;
+        let bytes = self.registers.get_by_sections::<u8>(reg_type, reg_index).unwrap_or_default();
+        self.memory.write_bytes(address, &bytes)
+    }
+
+    /// Fills every lane of a SIMD register with a scalar read from memory.
+    ///
+    /// # Type Parameters
+    /// `T` - The type of the lanes and of the scalar in memory.
+    ///
+    /// # Arguments
+    /// * `reg_type` - The type of SIMD register to load.
+    /// * `dst` - The index of the register.
+    /// * `address` - The address of the scalar.
+    ///
+    /// # Returns
+    /// `Ok(())` on success, or the error met while reading; the register is unchanged on error.
+    pub fn broadcast_from_memory<T: SectionCompatible + MemoryIO>(&mut self, reg_type: VecRegName, dst: usize, address: u64) -> Result<(), MemoryError> {
+        let value = self.memory.try_read::<T>(address)?;
+        self.registers.broadcast_scalar_to_simd(reg_type, dst, value);
+        Ok(())
+    }
+
+    /// Gathers lanes of a SIMD register from memory, as `VGATHERDPS` and `VPGATHERDD` do.
+    ///
+    /// Lane `i` of `dst` is loaded from `base_address + index[i] * scale`, where `index[i]` is lane `i` of the index
+    /// register read as a signed 32-bit integer. Lanes whose bit in `mask_reg` is clear are zeroed. As on hardware,
+    /// the mask register is cleared once the gather completes.
+    ///
+    /// # Type Parameters
+    /// `T` - The type of the lanes being gathered.
+    ///
+    /// # Arguments
+    /// * `dst_type` - The type of the destination register.
+    /// * `dst` - The index of the destination register.
+    /// * `base_address` - The base address the scaled indices are added to.
+    /// * `index_type` - The type of the index register.
+    /// * `index_reg` - The index of the index register.
+    /// * `scale` - The factor each index is multiplied by.
+    /// * `mask_reg` - The opmask register selecting the lanes to load.
+    ///
+    /// # Returns
+    /// `true` on success, or `false` if a register is not accessible, the index register has fewer lanes
+    /// than the destination, or a read fails; the registers are unchanged on failure.
+    #[allow(clippy::too_many_arguments)]
+    pub fn simd_gather<T: SectionCompatible + MemoryIO>(&mut self, dst_type: VecRegName, dst: usize, base_address: u64,
+                                                         index_type: VecRegName, index_reg: usize, scale: usize, mask_reg: KMaskName) -> bool {
+        let Some(addresses) = self.vsib_addresses::<T>(dst_type, base_address, index_type, index_reg, scale) else {
+            return false;
+        };
+        let mask = self.registers.get_kmask_value(mask_reg);
+        let mut lanes = Vec::with_capacity(addresses.len());
+        for (lane, address) in addresses.into_iter().enumerate() {
+            if mask >> lane & 1 == 0 {
+                lanes.push(T::from(0u8));
+                continue;
+            }
+            match self.memory.try_read::<T>(address) {
+                Ok(value) => lanes.push(value),
+                Err(_) => return false,
+            }
+        }
+        if !self.registers.set_by_sections(dst_type, dst, lanes) {
+            return false;
+        }
+        self.registers.set_kmask_value(mask_reg, 0);
+        true
+    }
+
+    /// Scatters lanes of a SIMD register to memory, as `VSCATTERDPS` and `VPSCATTERDD` do.
+    ///
+    /// Lane `i` of `src` is stored to `base_address + index[i] * scale`, where `index[i]` is lane `i` of the index
+    /// register read as a signed 32-bit integer. Only lanes whose bit in `mask_reg` is set are stored, in lane order,
+    /// so when two lanes target overlapping addresses the highest-numbered lane wins. Each mask bit is cleared once
+    /// its lane has been written.
+    ///
+    /// # Type Parameters
+    /// `T` - The type of the lanes being scattered.
+    ///
+    /// # Arguments
+    /// * `src_type` - The type of the source register.
+    /// * `src` - The index of the source register.
+    /// * `base_address` - The base address the scaled indices are added to.
+    /// * `index_type` - The type of the index register.
+    /// * `index_reg` - The index of the index register.
+    /// * `scale` - The factor each index is multiplied by.
+    /// * `mask_reg` - The opmask register selecting the lanes to store.
+    ///
+    /// # Returns
+    /// `true` on success, or `false` if a register is not accessible, the index register has fewer lanes
+    /// than the source, or a write fails; lanes stored before a failing write stay stored and their mask bits
+    /// stay cleared.
+    #[allow(clippy::too_many_arguments)]
+    pub fn simd_scatter<T: SectionCompatible + MemoryIO>(&mut self, src_type: VecRegName, src: usize, base_address: u64,
+                                                          index_type: VecRegName, index_reg: usize, scale: usize, mask_reg: KMaskName) -> bool {
+        let Some(addresses) = self.vsib_addresses::<T>(src_type, base_address, index_type, index_reg, scale) else {
+            return false;
+        };
+        let Some(lanes) = self.registers.get_by_sections::<T>(src_type, src) else {
+            return false;
+        };
+        for (lane, (address, value)) in addresses.into_iter().zip(lanes).enumerate() {
+            let mask = self.registers.get_kmask_value(mask_reg);
+            if mask >> lane & 1 == 0 {
+                continue;
+            }
+            if self.memory.write::<T>(address, value).is_err() {
+                return false;
+            }
+            self.registers.set_kmask_value(mask_reg, mask & !(1 << lane));
+        }
+        true
+    }
+
+    /// Computes the lane addresses of a VSIB memory operand with 32-bit indices.
+    ///
+    /// # Returns
+    /// One address per `T` lane of `data_type`, or `None` if the index register is not accessible or too narrow.
+    fn vsib_addresses<T: SectionCompatible>(&self, data_type: VecRegName, base_address: u64, index_type: VecRegName, index_reg: usize, scale: usize) -> Option<Vec<u64>> {
+        let lanes = data_type.lane_count::<T>();
+        let indices = self.registers.get_by_sections::<u32>(index_type, index_reg)?;
+        if indices.len() < lanes {
+            return None;
+        }
+        Some(indices[..lanes].iter()
+            .map(|index| base_address.wrapping_add_signed((*index as i32 as i64).wrapping_mul(scale as i64)))
+            .collect())
+    }
+
+    /// Maps a stack region and points the stack registers at it.
+    ///
+    /// Maps `[top - size, top)`, maps a guard page below it so that running off the end of the stack
+    /// faults with `MemoryError::GuardHit`, sets RSP to `top` aligned down to 16 bytes and zeroes RBP.
+    ///
+    /// # Arguments
+    /// * `top` - The address one past the highest byte of the stack.
+    /// * `size` - The size of the stack in bytes.
+    ///
+    /// # Returns
+    /// `Ok(())` on success, `MemoryError::AddressOverflow` if the stack or its guard page would extend
+    /// below address 0, or `MemoryError::Overlap` if the stack region or its guard page is already in use;
+    /// on error nothing is mapped.
+    pub fn setup_stack(&mut self, top: u64, size: u64) -> Result<(), MemoryError> {
+        let bottom = top.checked_sub(size).ok_or(MemoryError::AddressOverflow { address: top })?;
+        let guard = bottom.checked_sub(STACK_GUARD_SIZE).ok_or(MemoryError::AddressOverflow { address: bottom })?;
+        self.memory.map(bottom..top)?;
+        if let Err(error) = self.memory.map_guard(guard..bottom) {
+            self.memory.unmap_range(bottom..top);
+            return Err(error);
+        }
+        self.registers.set_gpr_value(GPRName::RSP, top & !0xF);
+        self.registers.set_gpr_value(GPRName::RBP, 0);
+        Ok(())
+    }
+
+    /// Pushes a quadword onto the stack.
+    ///
+    /// # Arguments
+    /// * `value` - The value to push.
+    ///
+    /// # Returns
+    /// `Ok(())` on success, or the error met while writing, in which case RSP is unchanged.
+    pub fn push(&mut self, value: u64) -> Result<(), MemoryError> {
+        let rsp = self.registers.get_gpr_value(GPRName::RSP).wrapping_sub(8);
+        self.memory.write::<u64>(rsp, value)?;
+        self.registers.set_gpr_value(GPRName::RSP, rsp);
+        Ok(())
+    }
+
+    /// Pops a quadword from the stack.
+    ///
+    /// # Returns
+    /// The value popped, or the error met while reading, in which case RSP is unchanged.
+    pub fn pop(&mut self) -> Result<u64, MemoryError> {
+        let rsp = self.registers.get_gpr_value(GPRName::RSP);
+        let value = self.memory.try_read::<u64>(rsp)?;
+        self.registers.set_gpr_value(GPRName::RSP, rsp.wrapping_add(8));
+        Ok(value)
+    }
+
+    /// Multiplies RAX by a register as unsigned integers, as the one-operand `MUL r/m64` does.
+    ///
+    /// The low 64 bits of the product are stored in RAX and the high 64 bits in RDX. CF and OF are
+    /// set if the high half is not zero and cleared otherwise; the other flags are left unchanged.
+    ///
+    /// # Arguments
+    /// * `multiplier` - The register holding the multiplier, read as by `get_gpr_value`.
+    ///
+    /// # Returns
+    /// The full 128-bit product.
+    pub fn mulq_full(&mut self, multiplier: GPRName) -> u128 {
+        let product = self.registers.get_gpr_value(GPRName::RAX) as u128 * self.registers.get_gpr_value(multiplier) as u128;
+        self.store_product(product as u64, (product >> 64) as u64, product >> 64 != 0);
+        product
+    }
+
+    /// Multiplies RAX by a register as signed integers, as the one-operand `IMUL r/m64` does.
+    ///
+    /// The low 64 bits of the product are stored in RAX and the high 64 bits in RDX. CF and OF are
+    /// set if the product does not fit in 64 signed bits and cleared otherwise; the other flags are
+    /// left unchanged.
+    ///
+    /// # Arguments
+    /// * `multiplier` - The register holding the multiplier, read as by `get_gpr_as_i64`.
+    ///
+    /// # Returns
+    /// The full 128-bit product.
+    pub fn imulq_full(&mut self, multiplier: GPRName) -> i128 {
+        let product = self.registers.get_gpr_as_i64(GPRName::RAX) as i128 * self.registers.get_gpr_as_i64(multiplier) as i128;
+        self.store_product(product as u64, (product >> 64) as u64, product != product as i64 as i128);
+        product
+    }
+
+    /// Rotates a register left, as `ROL` does.
+    ///
+    /// The count is masked to 6 bits for 64-bit registers and 5 bits otherwise, and nothing happens
+    /// if the masked count is 0. CF receives the last bit rotated out, the new least significant bit. For a masked count of 1, OF is set to the most significant bit of the result XOR the new CF;
+    /// it is left unchanged for other counts. No other flag is affected.
+    ///
+    /// # Arguments
+    /// * `dst` - The register to rotate; its width decides the rotation width.
+    /// * `count` - The number of bits to rotate by.
+    pub fn rol_gpr(&mut self, dst: GPRName, count: u8) {
+        self.rotate_gpr(dst, count, true, false);
+    }
+
+    /// Rotates a register right, as `ROR` does.
+    ///
+    /// The count is masked to 6 bits for 64-bit registers and 5 bits otherwise, and nothing happens
+    /// if the masked count is 0. CF receives the last bit rotated out, the new most significant bit. For a masked count of 1, OF is set to the XOR of the two most significant bits of the result;
+    /// it is left unchanged for other counts. No other flag is affected.
+    ///
+    /// # Arguments
+    /// * `dst` - The register to rotate; its width decides the rotation width.
+    /// * `count` - The number of bits to rotate by.
+    pub fn ror_gpr(&mut self, dst: GPRName, count: u8) {
+        self.rotate_gpr(dst, count, false, false);
+    }
+
+    /// Rotates a register left through CF, as `RCL` does.
+    ///
+    /// The count is masked to 6 bits for 64-bit registers and 5 bits otherwise, and nothing happens
+    /// if the masked count is 0. The register and CF rotate together as a single value one bit wider than the register, so CF receives the last bit rotated out and its old value is rotated in. For a masked count of 1, OF is set to the most significant bit of the result XOR the new CF;
+    /// it is left unchanged for other counts. No other flag is affected.
+    ///
+    /// # Arguments
+    /// * `dst` - The register to rotate; its width decides the rotation width.
+    /// * `count` - The number of bits to rotate by.
+    pub fn rcl_gpr(&mut self, dst: GPRName, count: u8) {
+        self.rotate_gpr(dst, count, true, true);
+    }
+
+    /// Rotates a register right through CF, as `RCR` does.
+    ///
+    /// The count is masked to 6 bits for 64-bit registers and 5 bits otherwise, and nothing happens
+    /// if the masked count is 0. The register and CF rotate together as a single value one bit wider than the register, so CF receives the last bit rotated out and its old value is rotated in. For a masked count of 1, OF is set to the XOR of the two most significant bits of the result;
+    /// it is left unchanged for other counts. No other flag is affected.
+    ///
+    /// # Arguments
+    /// * `dst` - The register to rotate; its width decides the rotation width.
+    /// * `count` - The number of bits to rotate by.
+    pub fn rcr_gpr(&mut self, dst: GPRName, count: u8) {
+        self.rotate_gpr(dst, count, false, true);
+    }
+
+    /// Rotates a register left or right, optionally through CF, and updates CF and OF.
+    fn rotate_gpr(&mut self, dst: GPRName, count: u8, left: bool, through_carry: bool) {
+        let width = Utilities::get_gpr_size(&dst) as u32;
+        let count = count as u32 & if width == 64 { 0x3F } else { 0x1F };
+        if count == 0 {
+            return;
+        }
+        let flags = self.registers.get_flags_value(FLAGSName::RFLAGS);
+        // the rotated value, with CF above the register's most significant bit when rotating through carry
+        let span = width + through_carry as u32;
+        let span_mask = u128::MAX >> (128 - span);
+        let carry_in = if through_carry { ((flags & registers::CF) as u128) << width } else { 0 };
+        let value = self.registers.get_gpr_value(dst) as u128 | carry_in;
+        let shift = count % span;
+        let rotated = if shift == 0 {
+            value
+        } else if left {
+            (value << shift | value >> (span - shift)) & span_mask
+        } else {
+            (value >> shift | value << (span - shift)) & span_mask
+        };
+        let result = rotated as u64 & (u64::MAX >> (64 - width));
+        let bit = |position: u32| result >> position & 1 != 0;
+        let carry = if through_carry {
+            rotated >> width & 1 != 0
+        } else if left {
+            bit(0)
+        } else {
+            bit(width - 1)
+        };
+        let mut flags = flags & !registers::CF | if carry { registers::CF } else { 0 };
+        if count == 1 {
+            let overflow = if left { bit(width - 1) ^ carry } else { bit(width - 1) ^ bit(width - 2) };
+            flags = flags & !registers::OF | if overflow { registers::OF } else { 0 };
+        }
+        self.registers.set_gpr_value(dst, result);
+        self.registers.set_flags_value(FLAGSName::RFLAGS, flags);
+    }
+
+    /// Finds the lowest set bit of a register, as `BSF` does.
+    ///
+    /// If `src` is zero, ZF is set and `dst` is left unchanged; otherwise ZF is cleared and the
+    /// index of the lowest set bit is written to `dst`. No other flag is affected.
+    ///
+    /// # Arguments
+    /// * `dst` - The register receiving the bit index.
+    /// * `src` - The register to scan; its width decides the scan width.
+    ///
+    /// # Returns
+    /// `true` if a set bit was found, `false` if `src` is zero.
+    pub fn bsf(&mut self, dst: GPRName, src: GPRName) -> bool {
+        let index = Utilities::bsf(self.registers.get_gpr_value(src), Utilities::get_gpr_size(&src));
+        self.bit_scan(dst, index)
+    }
+
+    /// Finds the highest set bit of a register, as `BSR` does.
+    ///
+    /// If `src` is zero, ZF is set and `dst` is left unchanged; otherwise ZF is cleared and the
+    /// index of the highest set bit is written to `dst`. No other flag is affected.
+    ///
+    /// # Arguments
+    /// * `dst` - The register receiving the bit index.
+    /// * `src` - The register to scan; its width decides the scan width.
+    ///
+    /// # Returns
+    /// `true` if a set bit was found, `false` if `src` is zero.
+    pub fn bsr(&mut self, dst: GPRName, src: GPRName) -> bool {
+        let index = Utilities::bsr(self.registers.get_gpr_value(src), Utilities::get_gpr_size(&src));
+        self.bit_scan(dst, index)
+    }
+
+    /// Counts the leading zero bits of a register, as `LZCNT` does.
+    ///
+    /// The count is taken over the width of `src`, so a zero source yields that width. CF is set if
+    /// `src` is zero, ZF is set if the count is zero, and the other arithmetic flags are cleared.
+    ///
+    /// # Arguments
+    /// * `dst` - The register receiving the count.
+    /// * `src` - The register to count in.
+    ///
+    /// # Returns
+    /// The number of leading zero bits.
+    pub fn lzcnt_gpr(&mut self, dst: GPRName, src: GPRName) -> u32 {
+        let (count, flags) = Utilities::lzcnt(self.registers.get_gpr_value(src), Utilities::get_gpr_size(&src));
+        self.store_count(dst, count, flags)
+    }
+
+    /// Counts the trailing zero bits of a register, as `TZCNT` does.
+    ///
+    /// The count is taken over the width of `src`, so a zero source yields that width. CF is set if
+    /// `src` is zero, ZF is set if the count is zero, and the other arithmetic flags are cleared.
+    ///
+    /// # Arguments
+    /// * `dst` - The register receiving the count.
+    /// * `src` - The register to count in.
+    ///
+    /// # Returns
+    /// The number of trailing zero bits.
+    pub fn tzcnt_gpr(&mut self, dst: GPRName, src: GPRName) -> u32 {
+        let (count, flags) = Utilities::tzcnt(self.registers.get_gpr_value(src), Utilities::get_gpr_size(&src));
+        self.store_count(dst, count, flags)
+    }
+
+    /// Counts the set bits of a register, as `POPCNT` does.
+    ///
+    /// ZF is set if `src` is zero, and CF, OF, SF, AF and PF are cleared.
+    ///
+    /// # Arguments
+    /// * `dst` - The register receiving the count.
+    /// * `src` - The register to count in; its width decides the count width.
+    ///
+    /// # Returns
+    /// The number of set bits.
+    pub fn popcnt_gpr(&mut self, dst: GPRName, src: GPRName) -> u32 {
+        let (count, flags) = Utilities::popcnt(self.registers.get_gpr_value(src), Utilities::get_gpr_size(&src));
+        self.store_count(dst, count, flags)
+    }
+
+    /// Reverses the byte order of a register, as `BSWAP` does.
+    ///
+    /// 64-bit and 32-bit registers have their 8 or 4 bytes reversed; writing a 32-bit register
+    /// clears the upper half of its 64-bit register as usual. `BSWAP` on a 16-bit register is
+    /// undefined and the register is set to 0, as most processors do; 8-bit registers, which the
+    /// instruction cannot encode, are treated the same way. No flag is affected.
+    ///
+    /// # Arguments
+    /// * `reg` - The register to byte-swap.
+    pub fn bswap_gpr(&mut self, reg: GPRName) {
+        let value = self.registers.get_gpr_value(reg);
+        let swapped = match Utilities::get_gpr_size(&reg) {
+            64 => Utilities::bswap64(value),
+            32 => Utilities::bswap32(value as u32) as u64,
+            _ => 0,
+        };
+        self.registers.set_gpr_value(reg, swapped);
+    }
+
+    /// Writes a found bit index to `dst`, or sets ZF if the source had no set bit.
+    fn bit_scan(&mut self, dst: GPRName, index: Option<u32>) -> bool {
+        let flags = self.registers.get_flags_value(FLAGSName::RFLAGS) & !registers::ZF;
+        let Some(index) = index else {
+            self.registers.set_flags_value(FLAGSName::RFLAGS, flags | registers::ZF);
+            return false;
+        };
+        self.registers.set_gpr_value(dst, index as u64);
+        self.registers.set_flags_value(FLAGSName::RFLAGS, flags);
+        true
+    }
+
+    /// Writes a bit count to `dst`, applies the flags computed with it and returns it.
+    fn store_count(&mut self, dst: GPRName, count: u32, flags: FlagsDelta) -> u32 {
+        self.registers.set_gpr_value(dst, count as u64);
+        flags.apply_to(&mut self.registers);
+        count
+    }
+
+    /// Stores a 128-bit product in RDX:RAX and sets CF and OF to `overflow`.
+    fn store_product(&mut self, low: u64, high: u64, overflow: bool) {
+        self.registers.set_gpr_value(GPRName::RAX, low);
+        self.registers.set_gpr_value(GPRName::RDX, high);
+        let flags = self.registers.get_flags_value(FLAGSName::RFLAGS) & !(registers::CF | registers::OF);
+        let overflow_flags = if overflow { registers::CF | registers::OF } else { 0 };
+        self.registers.set_flags_value(FLAGSName::RFLAGS, flags | overflow_flags);
+    }
+
+    /// Checks a SIMD transfer address against the alignment setting.
+    fn check_simd_alignment(&self, address: u64, width: usize) -> Result<(), MemoryError> {
+        if self.aligned_simd && !address.is_multiple_of(width as u64) {
+            return Err(AlignmentError { address, required_alignment: width }.into());
         }
+        Ok(())
     }
 }
 
+/// Builds the CPUID data a new CPU starts with.
+fn default_cpuid_leaves() -> HashMap<(u32, Option<u32>), [u32; 4]> {
+    // the vendor string is returned in EBX, EDX, ECX order
+    let vendor = |i: usize| u32::from_le_bytes(DEFAULT_CPUID_VENDOR[i * 4..i * 4 + 4].try_into().unwrap());
+    HashMap::from([
+        ((0, None), [1, vendor(0), vendor(2), vendor(1)]),
+        ((1, None), [0, 0, DEFAULT_CPUID_FEATURES_ECX, DEFAULT_CPUID_FEATURES_EDX]),
+    ])
+}
+
 impl Default for CPU {
     /// Creates a new CPU context with a default memory base address.
     ///
@@ -74,7 +805,7 @@ impl Default for CPU {
     /// # Returns
     /// Returns a new `CPU` instance with default settings.
     fn default() -> Self {
-        CPU::new(0x00400000usize)
+        CPU::new(0x00400000u64)
     }
 }
 
@@ -86,8 +817,11 @@ impl Default for CPU {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::cell::{Cell, RefCell};
+    use std::rc::Rc;
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn test() {
         let mut cpu = CPU::default();
         // test set/get bit
@@ -182,23 +916,23 @@ mod tests {
         }
         // test memory
         assert_eq!(cpu.memory.read::<u8>(0x00400000), 0);
-        cpu.memory.write::<u8>(0x00400000, 0x12);
+        cpu.memory.write::<u8>(0x00400000, 0x12).unwrap();
         assert_eq!(cpu.memory.read::<u8>(0x00400000), 0x12);
-        cpu.memory.write::<u16>(0x00400000, 0x1234);
+        cpu.memory.write::<u16>(0x00400000, 0x1234).unwrap();
         assert_eq!(cpu.memory.read::<u16>(0x00400000), 0x1234);
-        cpu.memory.write::<u32>(0x00400000, 0x12345678);
+        cpu.memory.write::<u32>(0x00400000, 0x12345678).unwrap();
         assert_eq!(cpu.memory.read::<u32>(0x00400000), 0x12345678);
-        cpu.memory.write::<u64>(0x00400000, 0x1234567887654321);
+        cpu.memory.write::<u64>(0x00400000, 0x1234567887654321).unwrap();
         assert_eq!(cpu.memory.read::<u64>(0x00400000), 0x1234567887654321);
-        cpu.memory.write::<u128>(0x00400000, 0x12345678876543211234567887654321);
+        cpu.memory.write::<u128>(0x00400000, 0x12345678876543211234567887654321).unwrap();
         assert_eq!(cpu.memory.read::<u128>(0x00400000), 0x12345678876543211234567887654321);
-        cpu.memory.write::<u256>(0x00400000, u256::from(0x12345678876543211234567887654321u128));
+        cpu.memory.write::<u256>(0x00400000, u256::from(0x12345678876543211234567887654321u128)).unwrap();
         assert_eq!(cpu.memory.read::<u256>(0x00400000), u256::from(0x12345678876543211234567887654321u128));
-        cpu.memory.write::<u512>(0x00400000, u512::from(0x12345678876543211234567887654321u128));
+        cpu.memory.write::<u512>(0x00400000, u512::from(0x12345678876543211234567887654321u128)).unwrap();
         assert_eq!(cpu.memory.read::<u512>(0x00400000), u512::from(0x12345678876543211234567887654321u128));
         cpu.memory.write_vec::<u64>(0x00400000, vec![
             0, 1, 2, 3, 4, 5, 6, 7,
-        ]);
+        ]).unwrap();
         let result = cpu.memory.read_vec::<u32>(0x00400000, 16);
         assert_eq!(result[0], 0);
         assert_eq!(result[1], 0);
@@ -217,4 +951,3025 @@ mod tests {
         assert_eq!(result[14], 7);
         assert_eq!(result[15], 0);
     }
+
+    #[test]
+    fn test_count_zeros() {
+        // leading zeros
+        assert_eq!(Utilities::clz_u8(0), 8);
+        assert_eq!(Utilities::clz_u8(1), 7);
+        assert_eq!(Utilities::clz_u8(u8::MAX), 0);
+        assert_eq!(Utilities::clz_u16(0), 16);
+        assert_eq!(Utilities::clz_u16(1), 15);
+        assert_eq!(Utilities::clz_u16(u16::MAX), 0);
+        assert_eq!(Utilities::clz_u32(0), 32);
+        assert_eq!(Utilities::clz_u32(1), 31);
+        assert_eq!(Utilities::clz_u32(u32::MAX), 0);
+        assert_eq!(Utilities::clz_u64(0), 64);
+        assert_eq!(Utilities::clz_u64(1), 63);
+        assert_eq!(Utilities::clz_u64(u64::MAX), 0);
+        assert_eq!(Utilities::clz_u128(0), 128);
+        assert_eq!(Utilities::clz_u128(1), 127);
+        assert_eq!(Utilities::clz_u128(u128::MAX), 0);
+        // trailing zeros
+        assert_eq!(Utilities::ctz_u8(0), 8);
+        assert_eq!(Utilities::ctz_u8(1), 0);
+        assert_eq!(Utilities::ctz_u8(u8::MAX), 0);
+        assert_eq!(Utilities::ctz_u8(0x80), 7);
+        assert_eq!(Utilities::ctz_u16(0), 16);
+        assert_eq!(Utilities::ctz_u16(1), 0);
+        assert_eq!(Utilities::ctz_u16(u16::MAX), 0);
+        assert_eq!(Utilities::ctz_u16(0x8000), 15);
+        assert_eq!(Utilities::ctz_u32(0), 32);
+        assert_eq!(Utilities::ctz_u32(1), 0);
+        assert_eq!(Utilities::ctz_u32(u32::MAX), 0);
+        assert_eq!(Utilities::ctz_u32(0x8000_0000), 31);
+        assert_eq!(Utilities::ctz_u64(0), 64);
+        assert_eq!(Utilities::ctz_u64(1), 0);
+        assert_eq!(Utilities::ctz_u64(u64::MAX), 0);
+        assert_eq!(Utilities::ctz_u64(1 << 63), 63);
+        assert_eq!(Utilities::ctz_u128(0), 128);
+        assert_eq!(Utilities::ctz_u128(1), 0);
+        assert_eq!(Utilities::ctz_u128(u128::MAX), 0);
+        assert_eq!(Utilities::ctz_u128(1 << 127), 127);
+    }
+
+    #[test]
+    fn test_memory_protection() {
+        let mut memory = Memory::new(0x1000);
+        memory.write_vec::<u8>(0x1000, vec![0x11; 32]).unwrap();
+        memory.protect(0x1008..0x1010, Protection::ReadOnly);
+        assert_eq!(memory.protection(0x1007), Protection::ReadWrite);
+        assert_eq!(memory.protection(0x1008), Protection::ReadOnly);
+        assert_eq!(memory.protection(0x100F), Protection::ReadOnly);
+        assert_eq!(memory.protection(0x1010), Protection::ReadWrite);
+        // writes straddling the start and the end of the protected range
+        assert_eq!(memory.write::<u32>(0x1006, 0xAABBCCDD), Err(MemoryError::WriteProtected { address: 0x1008 }));
+        assert_eq!(memory.write::<u64>(0x100C, u64::MAX), Err(MemoryError::WriteProtected { address: 0x100C }));
+        assert_eq!(memory.write_vec::<u16>(0x1000, vec![0; 16]), Err(MemoryError::WriteProtected { address: 0x1008 }));
+        assert_eq!(memory.fill(0x100F, 4, 0), Err(MemoryError::WriteProtected { address: 0x100F }));
+        // neither protected nor adjacent bytes are modified on failure
+        for i in 0..32 {
+            assert_eq!(memory.read::<u8>(0x1000 + i), 0x11);
+        }
+        // writes beside the range and reads inside it are unaffected
+        memory.write::<u64>(0x1000, 0).unwrap();
+        memory.fill(0x1010, 16, 0x22).unwrap();
+        assert_eq!(memory.read::<u64>(0x1008), 0x1111111111111111);
+        // protection covers unmapped memory and survives segment merging
+        memory.protect(0x1400..0x1404, Protection::ReadOnly);
+        memory.write::<u8>(0x1200, 0x33).unwrap();
+        assert_eq!(memory.write::<u8>(0x1402, 0x44), Err(MemoryError::WriteProtected { address: 0x1402 }));
+        memory.write::<u8>(0x1404, 0x44).unwrap();
+        // lifting part of the protection
+        memory.protect(0x100C..0x1010, Protection::ReadWrite);
+        memory.write::<u32>(0x100C, 0).unwrap();
+        assert_eq!(memory.write::<u8>(0x100B, 0), Err(MemoryError::WriteProtected { address: 0x100B }));
+    }
+
+    #[test]
+    fn test_bit_reverse() {
+        assert_eq!(Utilities::bit_reverse_u8(0b0000_0001), 0b1000_0000);
+        assert_eq!(Utilities::bit_reverse_u8(0b1100_1010), 0b0101_0011);
+        assert_eq!(Utilities::bit_reverse_u16(0x0001), 0x8000);
+        assert_eq!(Utilities::bit_reverse_u16(0x1234), 0x2C48);
+        assert_eq!(Utilities::bit_reverse_u32(0x0000_0001), 0x8000_0000);
+        assert_eq!(Utilities::bit_reverse_u32(0x1234_5678), 0x1E6A_2C48);
+        assert_eq!(Utilities::bit_reverse_u64(1), 1 << 63);
+        assert_eq!(Utilities::bit_reverse_u64(0x0000_0000_FFFF_0000), 0x0000_FFFF_0000_0000);
+        assert_eq!(Utilities::bit_reverse_u64(Utilities::bit_reverse_u64(0x1234_5678_9ABC_DEF0)), 0x1234_5678_9ABC_DEF0);
+    }
+
+    #[test]
+    fn test_rotate() {
+        assert_eq!(Utilities::rotate_left_u64(0x8000_0000_0000_0001, 1), 0x0000_0000_0000_0003);
+        assert_eq!(Utilities::rotate_left_u64(0x1234_5678_9ABC_DEF0, 16), 0x5678_9ABC_DEF0_1234);
+        assert_eq!(Utilities::rotate_left_u64(0x1234_5678_9ABC_DEF0, 64), 0x1234_5678_9ABC_DEF0);
+        assert_eq!(Utilities::rotate_right_u64(0x8000_0000_0000_0001, 1), 0xC000_0000_0000_0000);
+        assert_eq!(Utilities::rotate_right_u64(0x1234_5678_9ABC_DEF0, 16), 0xDEF0_1234_5678_9ABC);
+        assert_eq!(Utilities::rotate_right_u64(0x1234_5678_9ABC_DEF0, 0), 0x1234_5678_9ABC_DEF0);
+    }
+
+    /// A device returning a magic constant and counting every access it serves.
+    struct CountingDevice {
+        reads: Rc<Cell<usize>>,
+        writes: Rc<RefCell<Vec<u8>>>,
+    }
+
+    impl MmioHandler for CountingDevice {
+        fn read(&mut self, offset: u64, size: usize) -> u64 {
+            self.reads.set(self.reads.get() + 1);
+            (0xDEADBEEFu64 >> (offset * 8)) & (u64::MAX >> (64 - size * 8))
+        }
+
+        fn write(&mut self, offset: u64, data: &[u8]) {
+            self.writes.borrow_mut().push(offset as u8);
+            self.writes.borrow_mut().extend_from_slice(data);
+        }
+    }
+
+    #[test]
+    fn test_memory_mmio() {
+        let mut memory = Memory::new(0x1000);
+        memory.write::<u8>(0x1000, 0x11).unwrap();
+        let reads = Rc::new(Cell::new(0));
+        let writes = Rc::new(RefCell::new(Vec::new()));
+        let device = || Box::new(CountingDevice { reads: reads.clone(), writes: writes.clone() });
+        // overlapping RAM is rejected
+        assert_eq!(memory.map_mmio(0x1100..0x1104, device()), Err(MemoryError::Overlap { address: 0x1100 }));
+        memory.map_mmio(0x2000..0x2004, device()).unwrap();
+        // overlapping another MMIO region is rejected
+        assert_eq!(memory.map_mmio(0x2002..0x2010, device()), Err(MemoryError::Overlap { address: 0x2002 }));
+        assert_eq!(memory.read::<u32>(0x2000), 0xDEADBEEF);
+        assert_eq!(reads.get(), 1);
+        assert_eq!(memory.read::<u16>(0x2002), 0xDEAD);
+        assert_eq!(reads.get(), 2);
+        memory.write::<u16>(0x2002, 0x1234).unwrap();
+        // the device sees the offset followed by the written bytes
+        assert_eq!(writes.borrow().as_slice(), &[2, 0x34, 0x12]);
+        // no RAM was created behind the device
+        assert_eq!(memory.read::<u8>(0x1000), 0x11);
+        assert_eq!(memory.read::<u32>(0x2000), 0xDEADBEEF);
+    }
+
+    #[test]
+    fn test_sign_extend() {
+        assert_eq!(Utilities::sign_extend(0xFF, 8), -1i64);
+        assert_eq!(Utilities::sign_extend(0x7F, 8), 127i64);
+        assert_eq!(Utilities::sign_extend(0x80, 8), -128i64);
+        assert_eq!(Utilities::sign_extend(0x1FF, 8), -1i64);
+        assert_eq!(Utilities::sign_extend(0x8000, 16), -32768i64);
+        assert_eq!(Utilities::sign_extend(0xFFFF_FFFE, 32), -2i64);
+        assert_eq!(Utilities::sign_extend(0x7FFF_FFFF, 32), 0x7FFF_FFFFi64);
+        assert_eq!(Utilities::sign_extend(u64::MAX, 64), -1i64);
+        assert_eq!(Utilities::sign_extend(1, 1), -1i64);
+        assert_eq!(Utilities::zero_extend(0xFF, 8), 0xFF);
+        assert_eq!(Utilities::zero_extend(0x1234_5678, 16), 0x5678);
+        assert_eq!(Utilities::zero_extend(u64::MAX, 32), 0xFFFF_FFFF);
+        assert_eq!(Utilities::zero_extend(u64::MAX, 64), u64::MAX);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_sign_extend_zero_width() {
+        Utilities::sign_extend(1, 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_zero_extend_too_wide() {
+        Utilities::zero_extend(1, 65);
+    }
+
+    #[test]
+    fn test_memory_clear() {
+        let mut memory = Memory::new(0x1000);
+        memory.write::<u64>(0x1000, u64::MAX).unwrap();
+        memory.write::<u64>(0x3000, u64::MAX).unwrap();
+        memory.protect(0x1000..0x1004, Protection::ReadOnly);
+        memory.set_strict(true);
+        assert_eq!(memory.try_read::<u64>(0x1000), Ok(u64::MAX));
+        memory.clear();
+        assert_eq!(memory.base_address, 0x1000);
+        assert!(!memory.is_mapped(0x1000));
+        assert_eq!(memory.try_read::<u64>(0x1000), Err(MemoryError::Unmapped { address: 0x1000 }));
+        assert_eq!(memory.try_read::<u64>(0x3000), Err(MemoryError::Unmapped { address: 0x3000 }));
+        assert_eq!(memory.read::<u64>(0x1000), 0);
+        memory.set_strict(false);
+        assert_eq!(memory.try_read::<u64>(0x3000), Ok(0));
+        // protection is kept unless requested
+        assert_eq!(memory.protection(0x1000), Protection::ReadOnly);
+        memory.clear_with(ClearOptions { mmio: true, protection: true, ..ClearOptions::default() });
+        assert_eq!(memory.protection(0x1000), Protection::ReadWrite);
+        // outstanding allocations and the program break do not outlive the memory behind them
+        memory.set_arena(0x8000..0x8100).unwrap();
+        let block = memory.alloc(0x100, 1).unwrap();
+        assert_eq!(memory.alloc(1, 1), Err(MemoryError::OutOfMemory { size: 1 }));
+        memory.set_brk(0x9000);
+        memory.sbrk(0x40).unwrap();
+        memory.clear();
+        assert_eq!(memory.free(block), Err(MemoryError::InvalidFree { address: block }));
+        assert_eq!(memory.alloc(0x100, 1), Ok(block));
+        assert_eq!(memory.brk(), 0x9000);
+        assert!(!memory.is_mapped(0x9000));        // guard regions, such as the one below a stack, are kept unless requested
+        memory.map_guard(0xA000..0xB000).unwrap();
+        memory.clear();
+        assert_eq!(memory.map(0xA000..0xA100), Err(MemoryError::Overlap { address: 0xA000 }));
+        memory.clear_with(ClearOptions { guards: true, ..ClearOptions::default() });
+        memory.map(0xA000..0xA100).unwrap();
+    }
+
+    #[test]
+    fn test_memory_unmap_range() {
+        let mut memory = Memory::new(0x1000);
+        memory.write_vec::<u8>(0x1000, (0..32).collect()).unwrap();
+        memory.set_strict(true);
+        memory.unmap_range(0x1008..0x1010);
+        assert_eq!(memory.try_read::<u32>(0x1004), Ok(0x07060504));
+        assert_eq!(memory.try_read::<u32>(0x1006), Err(MemoryError::Unmapped { address: 0x1008 }));
+        assert_eq!(memory.try_read::<u32>(0x1010), Ok(0x13121110));
+        assert_eq!(memory.read::<u64>(0x1008), 0);
+        assert!(memory.is_mapped(0x1007));
+        assert!(!memory.is_mapped(0x100F));
+        assert!(memory.is_mapped(0x1010));
+    }
+
+    #[test]
+    fn test_saturating_vec() {
+        assert_eq!(Utilities::saturating_add_i8_vec(&[100, -100, 1], &[100, -100, 2]), Ok(vec![127, -128, 3]));
+        assert_eq!(Utilities::saturating_add_u8_vec(&[200, 0, 1], &[100, 0, 2]), Ok(vec![255, 0, 3]));
+        assert_eq!(Utilities::saturating_add_i16_vec(&[30000, -30000, 1], &[30000, -30000, 2]), Ok(vec![32767, -32768, 3]));
+        assert_eq!(Utilities::saturating_add_u16_vec(&[60000, 0, 1], &[60000, 0, 2]), Ok(vec![65535, 0, 3]));
+        assert_eq!(Utilities::saturating_sub_i8_vec(&[-100, 100, 3], &[100, -100, 2]), Ok(vec![-128, 127, 1]));
+        assert_eq!(Utilities::saturating_sub_u8_vec(&[0, 255, 3], &[1, 0, 2]), Ok(vec![0, 255, 1]));
+        assert_eq!(Utilities::saturating_sub_i16_vec(&[-30000, 30000, 3], &[30000, -30000, 2]), Ok(vec![-32768, 32767, 1]));
+        assert_eq!(Utilities::saturating_sub_u16_vec(&[0, 65535, 3], &[1, 0, 2]), Ok(vec![0, 65535, 1]));
+        // mismatched lengths
+        assert_eq!(Utilities::saturating_add_u8_vec(&[1, 2], &[1]), Err(LengthMismatch { left: 2, right: 1 }));
+        assert_eq!(Utilities::saturating_sub_i16_vec(&[1], &[1, 2]), Err(LengthMismatch { left: 1, right: 2 }));
+    }
+
+    #[test]
+    fn test_memory_translator() {
+        let mut memory = Memory::new(0);
+        memory.write::<u32>(0x10000, 0x12345678).unwrap();
+        // identity plus offset
+        memory.set_translator(Box::new(|address, _| Ok(address + 0x10000)));
+        assert_eq!(memory.read::<u32>(0), 0x12345678);
+        memory.write::<u16>(0x10, 0xBEEF).unwrap();
+        memory.clear_translator();
+        assert_eq!(memory.read::<u16>(0x10010), 0xBEEF);
+        // page 1 faults on writes and is remapped per page elsewhere
+        memory.set_translator(Box::new(|address, kind| {
+            let page = address >> 12;
+            match (page, kind) {
+                (1, AccessKind::Write) => Err(MemoryError::TranslationFault { address, kind }),
+                (0, _) => Ok(address + 0x20000),
+                _ => Ok(address + 0x40000),
+            }
+        }));
+        assert_eq!(memory.write::<u32>(0x1000, 1), Err(MemoryError::TranslationFault { address: 0x1000, kind: AccessKind::Write }));
+        // a write crossing into the faulting page leaves the first page untouched
+        assert_eq!(memory.write::<u32>(0xFFE, 0xFFFFFFFF), Err(MemoryError::TranslationFault { address: 0x1000, kind: AccessKind::Write }));
+        assert_eq!(memory.read::<u16>(0xFFE), 0);
+        // reads crossing the page boundary translate each page separately
+        memory.clear_translator();
+        memory.write::<u16>(0x20FFE, 0x2211).unwrap();
+        memory.write::<u16>(0x41000, 0x4433).unwrap();
+        memory.set_translator(Box::new(|address, _| Ok(address + if address < 0x1000 { 0x20000 } else { 0x40000 })));
+        assert_eq!(memory.read::<u32>(0xFFE), 0x44332211);
+        assert_eq!(memory.fetch::<u32>(0xFFE), Ok(0x44332211));
+    }
+
+    #[test]
+    fn test_interleave() {
+        let a = [0u8, 1, 2, 3, 4, 5, 6, 7];
+        let b = [10u8, 11, 12, 13, 14, 15, 16, 17];
+        assert_eq!(Utilities::interleave_low(&a, &b), vec![0, 10, 1, 11, 2, 12, 3, 13]);
+        assert_eq!(Utilities::interleave_high(&a, &b), vec![4, 14, 5, 15, 6, 16, 7, 17]);
+        let (low_a, low_b) = Utilities::deinterleave(&Utilities::interleave_low(&a, &b));
+        assert_eq!(low_a, a[..4].to_vec());
+        assert_eq!(low_b, b[..4].to_vec());
+        let (high_a, high_b) = Utilities::deinterleave(&Utilities::interleave_high(&a, &b));
+        assert_eq!(high_a, a[4..].to_vec());
+        assert_eq!(high_b, b[4..].to_vec());
+        // quadword lanes as used by PUNPCKLQDQ
+        assert_eq!(Utilities::interleave_low(&[1u64, 2], &[3u64, 4]), vec![1, 3]);
+    }
+
+    #[test]
+    fn test_horizontal() {
+        assert_eq!(Utilities::hadd_u32_vec(&[1, 2, 3, 4], &[10, 20, 30, 40]), vec![3, 7, 30, 70]);
+        assert_eq!(Utilities::hadd_u32_vec(&[u32::MAX, 1], &[5, 6]), vec![0, 11]);
+        assert_eq!(Utilities::hadd_u16_vec(&[1, 2, 3, 4, 5, 6, 7, 8], &[0xFFFF, 2, 0, 0, 1, 1, 2, 2]), vec![3, 7, 11, 15, 1, 0, 2, 4]);
+        assert_eq!(Utilities::hsub_u32_vec(&[5, 2, 3, 4], &[10, 1, 7, 7]), vec![3, u32::MAX, 9, 0]);
+        assert_eq!(Utilities::hsub_u16_vec(&[5, 2], &[0, 1]), vec![3, 0xFFFF]);
+        assert_eq!(Utilities::hadd_f32_vec(&[1.0, 2.0, 3.5, 4.5], &[-1.0, 1.0, 0.25, 0.5]), vec![3.0, 8.0, 0.0, 0.75]);
+        assert_eq!(Utilities::hsub_f32_vec(&[1.0, 2.0, 3.5, 4.5], &[-1.0, 1.0, 0.25, 0.5]), vec![-1.0, -1.0, -2.0, -0.25]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_horizontal_odd_length() {
+        Utilities::hadd_u32_vec(&[1, 2, 3], &[1, 2]);
+    }
+
+    #[test]
+    fn test_memory_slices() {
+        let mut memory = Memory::new(0x1000);
+        let values = [0x1111u16, 0x2222, 0x3333];
+        memory.write_slice(0x1000, &values).unwrap();
+        assert_eq!(memory.read_vec::<u16>(0x1000, 3), values.to_vec());
+        // raw bytes inside one segment and across a segment boundary
+        memory.write_bytes(0x1010, b"hello").unwrap();
+        memory.write_bytes(0x11FE, &[1, 2, 3, 4]).unwrap();
+        let mut buffer = [0u8; 5];
+        memory.read_exact(0x1010, &mut buffer).unwrap();
+        assert_eq!(&buffer, b"hello");
+        let mut buffer = [0u8; 4];
+        memory.read_exact(0x11FE, &mut buffer).unwrap();
+        assert_eq!(buffer, [1, 2, 3, 4]);
+        // strict mode reports the unmapped part
+        memory.set_strict(true);
+        let mut buffer = [0u8; 8];
+        assert_eq!(memory.read_exact(0x13FC, &mut buffer), Err(MemoryError::Unmapped { address: 0x1400 }));
+        // protection still applies to raw writes
+        memory.protect(0x1012..0x1013, Protection::ReadOnly);
+        assert_eq!(memory.write_bytes(0x1010, b"world"), Err(MemoryError::WriteProtected { address: 0x1012 }));
+        assert_eq!(memory.read_vec::<u8>(0x1010, 5), b"hello".to_vec());
+    }
+
+    #[test]
+    fn test_shuffle() {
+        let src = [10u32, 11, 12, 13];
+        assert_eq!(Utilities::shuffle(&src, &[3, 2, 1, 0]), vec![13, 12, 11, 10]);
+        assert_eq!(Utilities::shuffle(&src, &[0, 0, 9, 2, 2]), vec![10, 10, 0, 12, 12]);
+        assert_eq!(Utilities::shuffle(&[1.5f32, 2.5], &[1, 5]), vec![2.5, 0.0]);
+        // VPERMQ ymm, ymm, 0x1B reverses the quadwords
+        assert_eq!(Utilities::permute64(&[0u64, 1, 2, 3], 0x1B), vec![3, 2, 1, 0]);
+        // broadcast lane 2 within each 256-bit half
+        assert_eq!(Utilities::permute64(&[0u64, 1, 2, 3, 4, 5, 6, 7], 0xAA), vec![2, 2, 2, 2, 6, 6, 6, 6]);
+        assert_eq!(Utilities::permute64(&[0u64, 1, 2, 3], 0xE4), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_compare_vec() {
+        // all equal, none equal, mixed
+        assert_eq!(Utilities::compare_eq_u32_vec(&[1, 2, 3, 4], &[1, 2, 3, 4]), Ok(vec![0xFFFF_FFFF; 4]));
+        assert_eq!(Utilities::compare_eq_u32_vec(&[1, 2, 3, 4], &[5, 6, 7, 8]), Ok(vec![0; 4]));
+        assert_eq!(Utilities::compare_eq_u32_vec(&[1, 2, 3, 4], &[1, 0, 3, 0]), Ok(vec![0xFFFF_FFFF, 0, 0xFFFF_FFFF, 0]));
+        assert_eq!(Utilities::compare_eq_u8_vec(&[1, 2], &[1, 2]), Ok(vec![0xFF, 0xFF]));
+        assert_eq!(Utilities::compare_eq_u8_vec(&[1, 2], &[2, 1]), Ok(vec![0, 0]));
+        assert_eq!(Utilities::compare_eq_u8_vec(&[1, 2], &[1, 1]), Ok(vec![0xFF, 0]));
+        assert_eq!(Utilities::compare_eq_u16_vec(&[7, 8], &[7, 8]), Ok(vec![0xFFFF, 0xFFFF]));
+        assert_eq!(Utilities::compare_eq_u16_vec(&[7, 8], &[8, 7]), Ok(vec![0, 0]));
+        assert_eq!(Utilities::compare_eq_u16_vec(&[7, 8], &[0, 8]), Ok(vec![0, 0xFFFF]));
+        // signed comparison treats negative lanes as smaller
+        assert_eq!(Utilities::compare_gt_i32_vec(&[5, 6], &[1, 2]), Ok(vec![0xFFFF_FFFF; 2]));
+        assert_eq!(Utilities::compare_gt_i32_vec(&[-1, 2], &[1, 2]), Ok(vec![0, 0]));
+        assert_eq!(Utilities::compare_gt_i32_vec(&[1, i32::MIN, i32::MAX, 0], &[-1, 0, i32::MIN, 0]), Ok(vec![0xFFFF_FFFF, 0, 0xFFFF_FFFF, 0]));
+        assert_eq!(Utilities::compare_eq_u32_vec(&[1], &[1, 2]), Err(LengthMismatch { left: 1, right: 2 }));
+        // a mismatch is told apart from a valid empty input
+        assert_eq!(Utilities::compare_gt_i32_vec(&[], &[]), Ok(vec![]));
+        assert_eq!(Utilities::compare_gt_i32_vec(&[1, 2], &[]), Err(LengthMismatch { left: 2, right: 0 }));
+    }
+
+    #[test]
+    fn test_memory_limit() {
+        let mut memory = Memory::new(0);
+        memory.set_limit(Some(1024));
+        assert_eq!(memory.limit(), Some(1024));
+        memory.write::<u8>(0x0, 1).unwrap();
+        memory.write::<u8>(0x1000, 2).unwrap();
+        assert_eq!(memory.mapped_size(), 1024);
+        // writes into mapped memory still succeed
+        memory.write::<u64>(0x1008, u64::MAX).unwrap();
+        // a write needing a third block fails at its first byte in that block
+        assert_eq!(memory.write::<u8>(0x2000, 3), Err(MemoryError::LimitExceeded { address: 0x2000, limit: 1024 }));
+        assert_eq!(memory.write::<u32>(0x11FE, u32::MAX), Err(MemoryError::LimitExceeded { address: 0x1200, limit: 1024 }));
+        assert_eq!(memory.fill(0x100, 0x200, 0xAA), Err(MemoryError::LimitExceeded { address: 0x200, limit: 1024 }));
+        // failed writes leave memory untouched
+        assert_eq!(memory.mapped_size(), 1024);
+        assert_eq!(memory.read::<u16>(0x11FE), 0);
+        assert_eq!(memory.read::<u8>(0x100), 0);
+        memory.set_limit(None);
+        memory.write::<u8>(0x2000, 3).unwrap();
+        assert_eq!(memory.mapped_size(), 1536);
+        // a large write around existing blocks counts each new block once
+        let data = vec![0x5A; 1 << 20];
+        let limit = 1536 + (1 << 20);
+        memory.set_limit(Some(limit - 1));
+        assert_eq!(memory.write_bytes(0x100000, &data), Err(MemoryError::LimitExceeded { address: 0x1FFE00, limit: limit - 1 }));
+        memory.set_limit(Some(limit));
+        memory.write::<u8>(0x100200, 4).unwrap();
+        memory.write::<u8>(0x180000, 5).unwrap();
+        memory.write_bytes(0x100000, &data).unwrap();
+        assert_eq!(memory.mapped_size(), limit);
+    }
+
+    #[test]
+    fn test_min_max_vec() {
+        let a = [i32::MIN, -5, 7, i32::MAX];
+        let b = [0, 5, -7, i32::MIN];
+        assert_eq!(Utilities::min_i32_vec(&a, &b), Ok(vec![i32::MIN, -5, -7, i32::MIN]));
+        assert_eq!(Utilities::max_i32_vec(&a, &b), Ok(vec![0, 5, 7, i32::MAX]));
+        // the same bit patterns compare differently when unsigned
+        let ua: Vec<u32> = a.iter().map(|x| *x as u32).collect();
+        let ub: Vec<u32> = b.iter().map(|x| *x as u32).collect();
+        assert_eq!(Utilities::min_u32_vec(&ua, &ub), Ok(vec![0, 5, 7, 0x7FFF_FFFF]));
+        assert_eq!(Utilities::max_u32_vec(&ua, &ub), Ok(vec![0x8000_0000, 0xFFFF_FFFB, 0xFFFF_FFF9, 0x8000_0000]));
+        assert_eq!(Utilities::min_i8_vec(&[-128, 127], &[0, -1]), Ok(vec![-128, -1]));
+        assert_eq!(Utilities::max_u8_vec(&[0x80, 1], &[0x7F, 2]), Ok(vec![0x80, 2]));
+        assert_eq!(Utilities::min_u8_vec(&[0x80, 1], &[0x7F, 2]), Ok(vec![0x7F, 1]));
+        assert_eq!(Utilities::max_i8_vec(&[-128, 127], &[0, -1]), Ok(vec![0, 127]));
+        assert_eq!(Utilities::min_i16_vec(&[i16::MIN, 3], &[i16::MAX, -3]), Ok(vec![i16::MIN, -3]));
+        assert_eq!(Utilities::max_i16_vec(&[i16::MIN, 3], &[i16::MAX, -3]), Ok(vec![i16::MAX, 3]));
+        assert_eq!(Utilities::min_u16_vec(&[0xFFFF, 3], &[1, 4]), Ok(vec![1, 3]));
+        assert_eq!(Utilities::max_u16_vec(&[0xFFFF, 3], &[1, 4]), Ok(vec![0xFFFF, 4]));
+        assert_eq!(Utilities::min_u32_vec(&[1, 2], &[1]), Err(LengthMismatch { left: 2, right: 1 }));
+    }
+
+    #[test]
+    fn test_memory_iter_nonzero() {
+        let mut memory = Memory::new(0x1000);
+        assert_eq!(memory.iter_nonzero().count(), 0);
+        memory.write_bytes(0x1010, &[1, 0, 0, 2]).unwrap();
+        memory.write::<u8>(0x11FF, 3).unwrap();
+        memory.write::<u8>(0x1200, 4).unwrap();
+        memory.write::<u8>(0x5000, 5).unwrap();
+        let expected = vec![(0x1010, 1), (0x1013, 2), (0x11FF, 3), (0x1200, 4), (0x5000, 5)];
+        assert_eq!(memory.iter_nonzero().collect::<Vec<_>>(), expected);
+        assert_eq!(memory.first_nonzero_after(0x1000), Some(0x1010));
+        assert_eq!(memory.first_nonzero_after(0x1011), Some(0x1013));
+        assert_eq!(memory.first_nonzero_after(0x1201), Some(0x5000));
+        assert_eq!(memory.first_nonzero_after(0x5001), None);
+    }
+
+    #[test]
+    fn test_float_vec_arithmetic() {
+        assert_eq!(Utilities::f32vec_add(&[1.0, 2.5], &[2.0, -0.5]), vec![3.0, 2.0]);
+        assert_eq!(Utilities::f32vec_sub(&[1.0, 2.5], &[2.0, -0.5]), vec![-1.0, 3.0]);
+        assert_eq!(Utilities::f32vec_mul(&[1.5, -2.0], &[2.0, 4.0]), vec![3.0, -8.0]);
+        assert_eq!(Utilities::f32vec_div(&[1.0, -9.0], &[4.0, 3.0]), vec![0.25, -3.0]);
+        assert_eq!(Utilities::f32vec_sqrt(&[4.0, 2.25]), vec![2.0, 1.5]);
+        assert_eq!(Utilities::f64vec_add(&[1.0, 2.5], &[2.0, -0.5]), vec![3.0, 2.0]);
+        assert_eq!(Utilities::f64vec_sub(&[1.0, 2.5], &[2.0, -0.5]), vec![-1.0, 3.0]);
+        assert_eq!(Utilities::f64vec_mul(&[1.5, -2.0], &[2.0, 4.0]), vec![3.0, -8.0]);
+        assert_eq!(Utilities::f64vec_div(&[1.0, -9.0], &[4.0, 3.0]), vec![0.25, -3.0]);
+        assert_eq!(Utilities::f64vec_sqrt(&[4.0, 2.25]), vec![2.0, 1.5]);
+        // subnormals
+        let tiny = f32::from_bits(1);
+        assert_eq!(Utilities::f32vec_add(&[tiny], &[tiny]), vec![f32::from_bits(2)]);
+        assert_eq!(Utilities::f32vec_mul(&[f32::MIN_POSITIVE], &[0.5]), vec![f32::from_bits(0x0040_0000)]);
+        assert_eq!(Utilities::f64vec_div(&[f64::from_bits(2)], &[2.0]), vec![f64::from_bits(1)]);
+        // infinities
+        assert_eq!(Utilities::f32vec_div(&[1.0, -1.0], &[0.0, 0.0]), vec![f32::INFINITY, f32::NEG_INFINITY]);
+        assert!(Utilities::f32vec_sub(&[f32::INFINITY], &[f32::INFINITY])[0].is_nan());
+        assert!(Utilities::f64vec_mul(&[f64::INFINITY], &[0.0])[0].is_nan());
+        assert_eq!(Utilities::f64vec_add(&[f64::INFINITY], &[1.0e308]), vec![f64::INFINITY]);
+        // NaN propagation
+        assert!(Utilities::f32vec_add(&[f32::NAN], &[1.0])[0].is_nan());
+        assert!(Utilities::f64vec_div(&[1.0], &[f64::NAN])[0].is_nan());
+        assert!(Utilities::f32vec_sqrt(&[-1.0])[0].is_nan());
+        // signed zeros
+        let zero = Utilities::f32vec_add(&[-0.0], &[-0.0])[0];
+        assert!(zero == 0.0 && zero.is_sign_negative());
+        let zero = Utilities::f64vec_sub(&[0.0], &[0.0])[0];
+        assert!(zero == 0.0 && zero.is_sign_positive());
+        let zero = Utilities::f32vec_sqrt(&[-0.0])[0];
+        assert!(zero == 0.0 && zero.is_sign_negative());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_float_vec_mismatched() {
+        Utilities::f32vec_add(&[1.0], &[1.0, 2.0]);
+    }
+
+    #[test]
+    fn test_memory_find() {
+        let mut memory = Memory::new(0);
+        memory.write_bytes(0x100, b"\x7fELF").unwrap();
+        // this match crosses the boundary between two allocation blocks
+        memory.write_bytes(0x3FE, b"MAGIC").unwrap();
+        memory.write_bytes(0x2000, b"MAG").unwrap();
+        memory.write_bytes(0x2200, b"IC").unwrap();
+        memory.unmap_range(0x2003..0x2200);
+        assert_eq!(memory.find(b"\x7fELF", None), Some(0x100));
+        assert_eq!(memory.find(b"MAGIC", None), Some(0x3FE));
+        // the pattern interrupted by a gap does not match
+        assert_eq!(memory.find_all(b"MAGIC", None), vec![0x3FE]);
+        assert_eq!(memory.find_all(b"MAG", None), vec![0x3FE, 0x2000]);
+        assert_eq!(memory.find(b"MAGIC", Some(0x3FF..0x1000)), None);
+        assert_eq!(memory.find(b"MAG", Some(0x1000..0x3000)), Some(0x2000));
+        assert_eq!(memory.find(b"nothing", None), None);
+        // wildcards
+        assert_eq!(memory.find_masked(b"\x7f??F", &[true, false, false, true], None), vec![0x100]);
+        assert_eq!(memory.find_masked(b"M?G", &[true, false, true], None), vec![0x3FE, 0x2000]);
+    }
+
+    #[test]
+    fn test_fused_multiply() {
+        assert_eq!(Utilities::f32vec_fmadd(&[2.0, -1.5], &[3.0, 2.0], &[1.0, 0.5]), vec![7.0, -2.5]);
+        assert_eq!(Utilities::f32vec_fmsub(&[2.0, -1.5], &[3.0, 2.0], &[1.0, 0.5]), vec![5.0, -3.5]);
+        assert_eq!(Utilities::f64vec_fmadd(&[2.0, -1.5], &[3.0, 2.0], &[1.0, 0.5]), vec![7.0, -2.5]);
+        assert_eq!(Utilities::f64vec_fmsub(&[2.0, -1.5], &[3.0, 2.0], &[1.0, 0.5]), vec![5.0, -3.5]);
+        // (1 + 2^-12)^2 = 1 + 2^-11 + 2^-24, whose last term is lost when the product is rounded first;
+        // the same happens in f64 with (1 + 2^-27)^2
+        let a = f32::from_bits(0x3F80_0800);
+        let c = f32::from_bits(0x3F80_1000);
+        assert_eq!(a * a - c, 0.0);
+        assert_eq!(Utilities::f32vec_fmsub(&[a], &[a], &[c]), vec![2.0f32.powi(-24)]);
+        let a = f64::from_bits(0x3FF0_0000_0200_0000);
+        let c = f64::from_bits(0x3FF0_0000_0400_0000);
+        assert_eq!(a * a - c, 0.0);
+        assert_eq!(Utilities::f64vec_fmsub(&[a], &[a], &[c]), vec![2.0f64.powi(-54)]);
+    }
+
+    #[test]
+    fn test_memory_read_vec_into() {
+        let mut memory = Memory::new(0x1000);
+        memory.write_vec::<u32>(0x11F8, (1..=8).collect()).unwrap();
+        let mut out = [0u32; 8];
+        memory.read_vec_into(0x11F8, &mut out).unwrap();
+        assert_eq!(out.to_vec(), memory.read_vec::<u32>(0x11F8, 8));
+        let mut wide = [u512::zero(); 2];
+        memory.read_vec_into(0x11F8, &mut wide).unwrap();
+        assert_eq!(wide.to_vec(), memory.read_vec::<u512>(0x11F8, 2));
+        // strict mode stops at the first unmapped element
+        memory.set_strict(true);
+        let mut out = [0xFFu8; 4];
+        assert_eq!(memory.read_vec_into(0x13FE, &mut out), Err(MemoryError::Unmapped { address: 0x1400 }));
+        assert_eq!(out, [0, 0, 0xFF, 0xFF]);
+        // read_vec stays lenient and reads the unmapped elements as 0
+        assert_eq!(memory.read_vec::<u8>(0x13FE, 4), vec![0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_float_vec_reciprocal() {
+        let close = |x: f32, y: f32| (x - y).abs() <= y.abs() * 1e-6;
+        let rcp = Utilities::f32vec_rcp(&[2.0, 4.0, -0.5, 3.0]);
+        for (result, expected) in rcp.iter().zip([0.5, 0.25, -2.0, 0.333_333_34]) {
+            assert!(close(*result, expected));
+        }
+        assert_eq!(Utilities::f32vec_rcp(&[0.0, -0.0]), vec![f32::INFINITY, f32::NEG_INFINITY]);
+        let rsqrt = Utilities::f32vec_rsqrt(&[4.0, 16.0, 2.0, 0.25]);
+        for (result, expected) in rsqrt.iter().zip([0.5, 0.25, std::f32::consts::FRAC_1_SQRT_2, 2.0]) {
+            assert!(close(*result, expected));
+        }
+        assert!(Utilities::f32vec_rsqrt(&[-1.0])[0].is_nan());
+        assert_eq!(Utilities::f32_hadd_vec(&[1.0, 2.0, 3.0, 4.0], &[5.0, 6.0, 7.0, 8.0]), vec![3.0, 7.0, 11.0, 15.0]);
+    }
+
+    #[test]
+    fn test_memory_fill_pattern() {
+        let mut memory = Memory::new(0x1000);
+        // the range spans two allocation blocks and ends with a partial repetition
+        memory.fill_pattern(0x11FD, 7, &[0xDE, 0xAD, 0xBE]).unwrap();
+        assert_eq!(memory.read_vec::<u8>(0x11FC, 9), vec![0, 0xDE, 0xAD, 0xBE, 0xDE, 0xAD, 0xBE, 0xDE, 0]);
+        memory.fill_u32(0x1400, 3, 0xDEADBEEF).unwrap();
+        assert_eq!(memory.read_vec::<u32>(0x1400, 4), vec![0xDEADBEEF, 0xDEADBEEF, 0xDEADBEEF, 0]);
+        memory.fill(0x1500, 3, 0xCC).unwrap();
+        assert_eq!(memory.read::<u32>(0x1500), 0x00CCCCCC);
+        memory.fill_pattern(0x1500, 4, &[]).unwrap();
+        assert_eq!(memory.read::<u32>(0x1500), 0x00CCCCCC);
+    }
+
+    #[test]
+    fn test_half_precision() {
+        // known encodings round-trip exactly
+        let known: [(u16, f32); 10] = [
+            (0x0000, 0.0), (0x3C00, 1.0), (0xC000, -2.0), (0x3555, 0.333_251_95),
+            (0x7BFF, 65504.0), (0x0400, 6.103_515_6e-5), (0x03FF, 6.097_555e-5),
+            (0x0001, 5.960_464_5e-8), (0x7C00, f32::INFINITY), (0xFC00, f32::NEG_INFINITY),
+        ];
+        for (bits, value) in known {
+            assert_eq!(Utilities::f16_to_f32(bits), value);
+            assert_eq!(Utilities::f32_to_f16(value), bits);
+        }
+        let negative_zero = Utilities::f16_to_f32(0x8000);
+        assert!(negative_zero == 0.0 && negative_zero.is_sign_negative());
+        assert_eq!(Utilities::f32_to_f16(-0.0), 0x8000);
+        // NaN stays NaN
+        assert!(Utilities::f16_to_f32(0x7E00).is_nan());
+        assert_eq!(Utilities::f32_to_f16(f32::NAN) & 0x7E00, 0x7E00);
+        // round to nearest even: 1 + 2^-11 is halfway between 1.0 and the next half
+        assert_eq!(Utilities::f32_to_f16(1.0 + 2.0f32.powi(-11)), 0x3C00);
+        assert_eq!(Utilities::f32_to_f16(1.0 + 3.0 * 2.0f32.powi(-11)), 0x3C02);
+        assert_eq!(Utilities::f32_to_f16(1.0 + 2.0f32.powi(-11) + 2.0f32.powi(-20)), 0x3C01);
+        // subnormal ties: 2^-25 rounds to zero, 3 * 2^-25 rounds to 2 * 2^-24
+        assert_eq!(Utilities::f32_to_f16(2.0f32.powi(-25)), 0x0000);
+        assert_eq!(Utilities::f32_to_f16(3.0 * 2.0f32.powi(-25)), 0x0002);
+        // overflow rounds to infinity, the largest finite value does not
+        assert_eq!(Utilities::f32_to_f16(65520.0), 0x7C00);
+        assert_eq!(Utilities::f32_to_f16(65519.0), 0x7BFF);
+        // every half value survives a round trip through f32
+        for bits in 0..=u16::MAX {
+            if Utilities::f16_to_f32(bits).is_nan() {
+                continue;
+            }
+            assert_eq!(Utilities::f32_to_f16(Utilities::f16_to_f32(bits)), bits);
+        }
+        assert_eq!(Utilities::f16vec_to_f32vec(&Utilities::f32vec_to_f16vec(&[1.0, -2.0, 0.5])), vec![1.0, -2.0, 0.5]);
+    }
+
+    #[test]
+    fn test_memory_io_length_mismatch() {
+        let bytes: Vec<u8> = (1..=33).collect();
+        // short inputs are zero-extended, long inputs are truncated
+        assert_eq!(<u256 as MemoryIO>::from_bytes(&[]), u256::zero());
+        assert_eq!(<u256 as MemoryIO>::from_bytes(&bytes[..31]), u256::from_little_endian(&[&bytes[..31], &[0][..]].concat()));
+        assert_eq!(<u256 as MemoryIO>::from_bytes(&bytes[..32]), u256::from_little_endian(&bytes[..32]));
+        assert_eq!(<u256 as MemoryIO>::from_bytes(&bytes), u256::from_little_endian(&bytes[..32]));
+        assert_eq!(<u512 as MemoryIO>::from_bytes(&bytes), u512::from_little_endian(&[&bytes[..], &[0; 31][..]].concat()));
+        assert_eq!(<u512 as MemoryIO>::from_bytes(&[]), u512::zero());
+        assert_eq!(<u32 as MemoryIO>::from_bytes(&[0x01, 0x02]), 0x0201);
+        assert_eq!(<u32 as MemoryIO>::from_bytes(&bytes), 0x0403_0201);
+        assert_eq!(<u8 as MemoryIO>::from_bytes(&[]), 0);
+    }
+
+    #[test]
+    fn test_bfloat16() {
+        assert_eq!(Utilities::f32_to_bf16(1.0), 0x3F80);
+        assert_eq!(Utilities::bf16_to_f32(0x3F80), 1.0);
+        assert_eq!(Utilities::bf16_to_f32(0xC040), -3.0);
+        // ties go to the even neighbour
+        assert_eq!(Utilities::f32_to_bf16(f32::from_bits(0x3F80_8000)), 0x3F80);
+        assert_eq!(Utilities::f32_to_bf16(f32::from_bits(0x3F81_8000)), 0x3F82);
+        // anything past the tie rounds up, anything before it rounds down
+        assert_eq!(Utilities::f32_to_bf16(f32::from_bits(0x3F80_8001)), 0x3F81);
+        assert_eq!(Utilities::f32_to_bf16(f32::from_bits(0x3F81_7FFF)), 0x3F81);
+        // rounding may carry into the exponent and up to infinity
+        assert_eq!(Utilities::f32_to_bf16(f32::from_bits(0x3FFF_8000)), 0x4000);
+        assert_eq!(Utilities::f32_to_bf16(f32::MAX), 0x7F80);
+        assert_eq!(Utilities::f32_to_bf16(f32::NEG_INFINITY), 0xFF80);
+        assert_eq!(Utilities::f32_to_bf16(-0.0), 0x8000);
+        // a NaN whose payload sits only in the low half stays NaN
+        assert!(Utilities::bf16_to_f32(Utilities::f32_to_bf16(f32::from_bits(0x7F80_0001))).is_nan());
+        assert_eq!(Utilities::bf16vec_to_f32vec(&Utilities::f32vec_to_bf16vec(&[0.5, -2.0, 1.0])), vec![0.5, -2.0, 1.0]);
+    }
+
+    #[test]
+    fn test_memory_read_checked() {
+        let mut memory = Memory::new(0x1000);
+        memory.write::<u32>(0x11FC, 0x0000_0000).unwrap();
+        // 0x11FC..0x1200 is mapped (and zero), 0x1200.. is not
+        let bytes = memory.read_bytes_checked(0x11FC, 8);
+        assert_eq!(bytes, vec![Some(0), Some(0), Some(0), Some(0), None, None, None, None]);
+        memory.write::<u16>(0x11FE, 0xBEEF).unwrap();
+        let (value, mask) = memory.read_checked::<u64>(0x11FC);
+        assert_eq!(value, 0xBEEF_0000);
+        assert_eq!(mask.iter().collect::<Vec<bool>>(), vec![true, true, true, true, false, false, false, false]);
+        let (_, mask) = memory.read_checked::<u8>(0x0FFF);
+        assert!(mask.none());
+    }
+
+    #[test]
+    fn test_memory_float() {
+        let mut memory = Memory::new(0x1000);
+        for (i, value) in [0.0f32, -0.0, 1.5, -3.25, f32::MIN_POSITIVE, f32::MAX, f32::INFINITY, f32::NEG_INFINITY].iter().enumerate() {
+            memory.write_f32(0x1000 + i as u64 * 4, *value).unwrap();
+            assert_eq!(memory.read_f32(0x1000 + i as u64 * 4).to_bits(), value.to_bits());
+        }
+        memory.write_f32(0x1100, f32::NAN).unwrap();
+        assert!(memory.read_f32(0x1100).is_nan());
+        assert_eq!(memory.read::<u32>(0x1004), 0x8000_0000);
+        for (i, value) in [0.0f64, -0.0, 0.1, -1e300, f64::INFINITY].iter().enumerate() {
+            memory.write_f64(0x1200 + i as u64 * 8, *value).unwrap();
+            assert_eq!(memory.read_f64(0x1200 + i as u64 * 8).to_bits(), value.to_bits());
+        }
+        memory.write_f64(0x1300, f64::NAN).unwrap();
+        assert!(memory.read_f64(0x1300).is_nan());
+    }
+
+    #[test]
+    fn test_memory_alloc() {
+        let mut memory = Memory::new(0x1000);
+        assert_eq!(memory.alloc(16, 8), Err(MemoryError::OutOfMemory { size: 16 }));
+        memory.write::<u8>(0x1000, 1).unwrap();
+        assert_eq!(memory.set_arena(0x1000..0x3000), Err(MemoryError::Overlap { address: 0x1000 }));
+        memory.set_arena(0x2000..0x3000).unwrap();
+        let mut blocks = Vec::new();
+        for (size, align) in [(3, 1), (16, 16), (100, 64), (1, 8), (40, 32)] {
+            let address = memory.alloc(size, align).unwrap();
+            assert_eq!(address % align as u64, 0);
+            blocks.push((address, size as u64));
+        }
+        memory.free(blocks[1].0).unwrap();
+        memory.free(blocks[3].0).unwrap();
+        assert_eq!(memory.free(blocks[1].0), Err(MemoryError::InvalidFree { address: blocks[1].0 }));
+        blocks.remove(3);
+        blocks.remove(1);
+        for (size, align) in [(8, 8), (200, 256), (12, 4)] {
+            let address = memory.alloc(size, align).unwrap();
+            assert_eq!(address % align as u64, 0);
+            assert!((0x2000..0x3000).contains(&address) && address + size as u64 <= 0x3000);
+            blocks.push((address, size as u64));
+        }
+        for (i, a) in blocks.iter().enumerate() {
+            for b in &blocks[i + 1..] {
+                assert!(a.0 + a.1 <= b.0 || b.0 + b.1 <= a.0, "{:?} overlaps {:?}", a, b);
+            }
+        }
+        assert_eq!(memory.alloc(0x1000, 1), Err(MemoryError::OutOfMemory { size: 0x1000 }));
+    }
+
+    #[test]
+    fn test_simd_load_store() {
+        let mut cpu = CPU::new(0x1000);
+        let bytes: Vec<u8> = (0..64).collect();
+        cpu.memory.write_bytes(0x1000, &bytes).unwrap();
+        cpu.simd_load(VecRegName::ZMM, 1, 0x1000).unwrap();
+        assert_eq!(cpu.registers.get_by_sections::<u8>(VecRegName::ZMM, 1).unwrap(), bytes);
+        assert_eq!(cpu.registers.get_by_sections::<u32>(VecRegName::XMM, 1).unwrap(),
+                   vec![0x0302_0100, 0x0706_0504, 0x0B0A_0908, 0x0F0E_0D0C]);
+        // an XMM load clears the upper lanes
+        cpu.simd_load(VecRegName::XMM, 1, 0x1001).unwrap();
+        let lanes = cpu.registers.get_by_sections::<u8>(VecRegName::ZMM, 1).unwrap();
+        assert_eq!(&lanes[..16], &bytes[1..17]);
+        assert!(lanes[16..].iter().all(|lane| *lane == 0));
+        cpu.simd_load(VecRegName::YMM, 2, 0x1020).unwrap();
+        cpu.simd_store(VecRegName::YMM, 2, 0x1100).unwrap();
+        assert_eq!(cpu.memory.read_vec::<u8>(0x1100, 32), bytes[32..].to_vec());
+        cpu.set_aligned_simd(true);
+        assert_eq!(cpu.simd_load(VecRegName::YMM, 2, 0x1010), Err(MemoryError::Misaligned { address: 0x1010, alignment: 32 }));
+        assert_eq!(cpu.simd_store(VecRegName::XMM, 2, 0x1108), Err(MemoryError::Misaligned { address: 0x1108, alignment: 16 }));
+        cpu.simd_load(VecRegName::XMM, 2, 0x1010).unwrap();
+    }
+
+    #[test]
+    fn test_memory_sbrk() {
+        let mut memory = Memory::new(0x1000);
+        memory.set_brk(0x2000);
+        assert_eq!(memory.sbrk(0x100), Ok(0x2000));
+        assert_eq!(memory.brk(), 0x2100);
+        memory.write::<u64>(0x20F8, 0xDEAD_BEEF).unwrap();
+        assert_eq!(memory.sbrk(-0x80), Ok(0x2100));
+        assert!(memory.is_mapped(0x207F));
+        assert!(!memory.is_mapped(0x2080));
+        assert_eq!(memory.sbrk(0x80), Ok(0x2080));
+        assert_eq!(memory.read::<u64>(0x20F8), 0);
+        // the break can't move below where it started
+        assert_eq!(memory.sbrk(-0x101), Err(MemoryError::InvalidBreak { address: 0x1FFF }));
+        assert_eq!(memory.brk(), 0x2100);
+        // growth is subject to protections and the limit
+        memory.protect(0x2100..0x2200, Protection::ReadOnly);
+        assert_eq!(memory.sbrk(0x10), Err(MemoryError::WriteProtected { address: 0x2100 }));
+        memory.protect(0x2100..0x2200, Protection::ReadWrite);
+        memory.set_limit(Some(memory.mapped_size()));
+        assert!(matches!(memory.sbrk(0x1000), Err(MemoryError::LimitExceeded { .. })));
+        assert_eq!(memory.brk(), 0x2100);
+    }
+
+    #[test]
+    fn test_memory_aligned() {
+        let mut memory = Memory::new(0x1000);
+        memory.write_aligned::<u32>(0x1004, 0x1234_5678).unwrap();
+        assert_eq!(memory.read_aligned::<u32>(0x1004), Ok(0x1234_5678));
+        assert_eq!(memory.read_aligned::<u16>(0x1006), Ok(0x1234));
+        assert_eq!(memory.read_aligned::<u8>(0x1007), Ok(0x12));
+        assert_eq!(memory.read_aligned::<u32>(0x1006), Err(AlignmentError { address: 0x1006, required_alignment: 4 }));
+        assert_eq!(memory.read_aligned::<u128>(0x1008), Err(AlignmentError { address: 0x1008, required_alignment: 16 }));
+        assert_eq!(memory.write_aligned::<u64>(0x1004, 0), Err(MemoryError::Misaligned { address: 0x1004, alignment: 8 }));
+        assert_eq!(memory.read::<u32>(0x1004), 0x1234_5678);
+        memory.protect(0x1000..0x1010, Protection::ReadOnly);
+        assert_eq!(memory.write_aligned::<u64>(0x1008, 0), Err(MemoryError::WriteProtected { address: 0x1008 }));
+    }
+
+    #[test]
+    fn test_setup_stack() {
+        let mut cpu = CPU::new(0x1000);
+        cpu.registers.set_gpr_value(GPRName::RBP, 0x1234);
+        cpu.setup_stack(0x9008, 0x1008).unwrap();
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::RSP), 0x9000);
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::RBP), 0);
+        cpu.push(0x1111).unwrap();
+        cpu.push(0x2222).unwrap();
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::RSP), 0x8FF0);
+        assert_eq!(cpu.pop(), Ok(0x2222));
+        assert_eq!(cpu.pop(), Ok(0x1111));
+        // walking off the bottom of the stack hits the guard page
+        for i in 0..0x1000 / 8 {
+            cpu.push(i).unwrap();
+        }
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::RSP), 0x8000);
+        assert_eq!(cpu.push(0), Err(MemoryError::GuardHit { address: 0x7FF8 }));
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::RSP), 0x8000);
+        assert_eq!(cpu.memory.try_read::<u8>(0x7000), Err(MemoryError::GuardHit { address: 0x7000 }));
+        // a stack whose guard page is taken maps nothing
+        assert_eq!(cpu.setup_stack(0x7100, 0x100), Err(MemoryError::Overlap { address: 0x7000 }));
+        assert!(!cpu.memory.is_mapped(0x7000));
+        // the stack region can't be mapped twice
+        assert_eq!(cpu.setup_stack(0x9000, 0x100), Err(MemoryError::Overlap { address: 0x8F00 }));
+    }
+
+    #[test]
+    fn test_memory_io_arrays() {
+        let mut memory = Memory::new(0x1000);
+        memory.write::<[u32; 4]>(0x1000, [0x1111_1111, 0x2222_2222, 0x3333_3333, 0x4444_4444]).unwrap();
+        assert_eq!(memory.read::<[u32; 4]>(0x1000), [0x1111_1111, 0x2222_2222, 0x3333_3333, 0x4444_4444]);
+        assert_eq!(memory.read::<u32>(0x1004), 0x2222_2222);
+        assert_eq!(memory.read::<u32>(0x100C), 0x4444_4444);
+        assert_eq!(memory.read::<u8>(0x1000), 0x11);
+        let ymm: [u32; 8] = core::array::from_fn(|i| i as u32 * 0x0101_0101);
+        memory.write(0x1100, ymm).unwrap();
+        assert_eq!(memory.read::<[u32; 8]>(0x1100), ymm);
+        assert_eq!(memory.read::<u32>(0x111C), 0x0707_0707);
+        let zmm: [u64; 8] = core::array::from_fn(|i| u64::MAX - i as u64);
+        memory.write(0x1200, zmm).unwrap();
+        assert_eq!(memory.read::<[u64; 8]>(0x1200), zmm);
+        assert_eq!(memory.read::<u64>(0x1238), u64::MAX - 7);
+        assert_eq!(<[u64; 8] as MemoryIO>::size(), 64);
+    }
+
+    #[test]
+    fn test_memory_dirty_pages() {
+        let mut memory = Memory::new(0x1000);
+        memory.write::<u32>(0x1000, 1).unwrap();
+        assert!(memory.dirty_pages().is_empty());
+        memory.enable_dirty_tracking(0x100);
+        memory.write::<u8>(0x1010, 1).unwrap();
+        memory.write::<u32>(0x12FE, 1).unwrap();
+        memory.fill(0x2000, 0x180, 0).unwrap();
+        let _ = memory.read::<u64>(0x1800);
+        assert_eq!(memory.dirty_pages(), vec![0x1000, 0x1200, 0x1300, 0x2000, 0x2100]);
+        // failed writes don't dirty anything
+        memory.protect(0x3000..0x3100, Protection::ReadOnly);
+        assert!(memory.write::<u8>(0x3000, 1).is_err());
+        memory.clear_dirty();
+        assert!(memory.dirty_pages().is_empty());
+        memory.write::<u8>(0x1011, 1).unwrap();
+        assert_eq!(memory.dirty_pages(), vec![0x1000]);
+        memory.disable_dirty_tracking();
+        assert!(memory.dirty_pages().is_empty());
+    }
+
+    #[test]
+    fn test_gpr_signed() {
+        let mut registers = Registers::new();
+        registers.set_gpr_i64(GPRName::RAX, -1);
+        assert_eq!(registers.get_gpr_as_i64(GPRName::RAX), -1);
+        assert_eq!(registers.get_gpr_value(GPRName::RAX), u64::MAX);
+        registers.set_gpr_i64(GPRName::RBX, i64::MIN);
+        assert_eq!(registers.get_gpr_value(GPRName::RBX), 0x8000_0000_0000_0000);
+        registers.set_gpr_value(GPRName::RCX, 42);
+        assert_eq!(registers.get_gpr_as_i64(GPRName::RCX), 42);
+        assert_eq!(registers.get_gpr_as_i64(GPRName::EAX), 0xFFFF_FFFF);
+    }
+
+    #[test]
+    fn test_arithmetic_flags() {
+        // carry: unsigned wrap-around
+        assert!(!Utilities::carry_flag_add(0x7F, 0x01, 8));
+        assert!(Utilities::carry_flag_add(0xFF, 0x01, 8));
+        assert!(Utilities::carry_flag_add(0x80, 0x80, 8));
+        assert!(!Utilities::carry_flag_add(0xFFFF, 0x0000, 16));
+        assert!(Utilities::carry_flag_add(0xFFFF_FFFF, 1, 32));
+        assert!(Utilities::carry_flag_add(u64::MAX, 1, 64));
+        assert!(!Utilities::carry_flag_add(u64::MAX, 0, 64));
+        // bits above the width are ignored
+        assert!(!Utilities::carry_flag_add(0x100, 0x1FF, 8));
+        // overflow: positive + positive, negative + negative, mixed signs
+        assert!(!Utilities::overflow_flag_add(0x01, 0x01, 8));
+        assert!(Utilities::overflow_flag_add(0x7F, 0x01, 8));
+        assert!(Utilities::overflow_flag_add(0x80, 0x80, 8));
+        assert!(!Utilities::overflow_flag_add(0xFF, 0xFF, 8));
+        assert!(!Utilities::overflow_flag_add(0x7F, 0x80, 8));
+        assert!(!Utilities::overflow_flag_add(0x7FFF, 0xFFFF, 16));
+        assert!(Utilities::overflow_flag_add(0x7FFF_FFFF, 1, 32));
+        assert!(Utilities::overflow_flag_add(i64::MAX as u64, 1, 64));
+        assert!(Utilities::overflow_flag_add(i64::MIN as u64, u64::MAX, 64));
+        assert!(!Utilities::overflow_flag_add(i64::MIN as u64, 1, 64));
+        // borrow
+        assert!(!Utilities::borrow_flag_sub(5, 3, 8));
+        assert!(Utilities::borrow_flag_sub(3, 5, 8));
+        assert!(!Utilities::borrow_flag_sub(0x80, 0x80, 8));
+        assert!(Utilities::borrow_flag_sub(0, 1, 16));
+        assert!(!Utilities::borrow_flag_sub(0x1_0000_0000, 1, 64));
+        assert!(Utilities::borrow_flag_sub(0x1_0000_0000, 1, 32));
+        assert!(Utilities::borrow_flag_sub(0, u64::MAX, 64));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_arithmetic_flags_invalid_width() {
+        Utilities::carry_flag_add(1, 1, 12);
+    }
+
+    #[test]
+    fn test_memory_checksum() {
+        let data: Vec<u8> = (0..0x900u32).map(|i| (i * 7 % 251) as u8).collect();
+        let mut a = Memory::new(0x1000);
+        a.write_bytes(0x1100, &data).unwrap();
+        let mut b = Memory::new(0x1000);
+        for (i, chunk) in data.chunks(4).enumerate() {
+            b.write::<u32>(0x1100 + i as u64 * 4, u32::from_le_bytes(chunk.try_into().unwrap())).unwrap();
+        }
+        let mut c = Memory::new(0x0);
+        for (i, byte) in data.iter().enumerate().rev() {
+            c.write::<u8>(0x1100 + i as u64, *byte).unwrap();
+        }
+        let range = 0x1100..0x1100 + data.len() as u64;
+        let expected = a.checksum(range.clone()).unwrap();
+        assert_eq!(b.checksum(range.clone()).unwrap(), expected);
+        assert_eq!(c.checksum(range.clone()).unwrap(), expected);
+        // FNV-1a reference values
+        assert_eq!(a.checksum(0x1100..0x1100).unwrap(), 0xcbf2_9ce4_8422_2325);
+        a.write_bytes(0x4000, b"a").unwrap();
+        assert_eq!(a.checksum(0x4000..0x4001).unwrap(), 0xaf63_dc4c_8601_ec8c);
+        // unmapped bytes read as zero unless strict
+        let mut d = Memory::new(0x1000);
+        d.write_bytes(0x1000, &[0; 16]).unwrap();
+        assert_eq!(d.checksum(0x1000..0x1010), a.checksum(0x9000..0x9010));
+        a.set_strict(true);
+        assert_eq!(a.checksum(0x9000..0x9010), Err(MemoryError::Unmapped { address: 0x9000 }));
+        let mut h1 = std::collections::hash_map::DefaultHasher::new();
+        let mut h2 = std::collections::hash_map::DefaultHasher::new();
+        b.hash_into(range.clone(), &mut h1).unwrap();
+        c.hash_into(range, &mut h2).unwrap();
+        assert_eq!(std::hash::Hasher::finish(&h1), std::hash::Hasher::finish(&h2));
+    }
+
+    #[test]
+    fn test_parity_adjust_flags() {
+        assert!(Utilities::parity_byte(0x00));
+        assert!(Utilities::parity_byte(0x03));
+        assert!(!Utilities::parity_byte(0x01));
+        assert!(Utilities::parity_byte(0xFF));
+        assert!(!Utilities::parity_byte(0x7F));
+        // only the low byte counts
+        assert!(Utilities::parity_byte(0x0100));
+        assert!(!Utilities::parity_byte(0xFFFF_FFFF_FFFF_FF01));
+        // AAA example: 0x08 + 0x09 carries out of the low nibble
+        assert!(Utilities::adjust_flag_add(0x08, 0x09));
+        assert!(Utilities::adjust_flag_add(0x0F, 0x01));
+        assert!(!Utilities::adjust_flag_add(0x07, 0x08));
+        assert!(!Utilities::adjust_flag_add(0xF0, 0xF0));
+        assert!(Utilities::adjust_flag_add(0xFF, 0xFF));
+    }
+
+    #[test]
+    fn test_memory_compare() {
+        use std::cmp::Ordering;
+        let mut memory = Memory::new(0x1000);
+        let data: Vec<u8> = (0..0x1100u32).map(|i| (i % 253) as u8 + 1).collect();
+        memory.write_bytes(0x1000, &data).unwrap();
+        memory.write_bytes(0x4000, &data).unwrap();
+        assert_eq!(memory.compare(0x1000, 0x4000, data.len()), Ordering::Equal);
+        assert!(memory.equal_to_slice(0x4000, &data));
+        // a difference in the last byte
+        memory.write::<u8>(0x4000 + data.len() as u64 - 1, 0).unwrap();
+        assert_eq!(memory.compare(0x1000, 0x4000, data.len()), Ordering::Greater);
+        assert_eq!(memory.compare(0x4000, 0x1000, data.len()), Ordering::Less);
+        assert_eq!(memory.compare(0x1000, 0x4000, data.len() - 1), Ordering::Equal);
+        assert_eq!(memory.first_mismatch(0x4000, &data), Some(data.len() - 1));
+        assert!(!memory.equal_to_slice(0x4000, &data));
+        // overlapping ranges
+        memory.write_bytes(0x8000, &[1, 1, 1, 1, 2]).unwrap();
+        assert_eq!(memory.compare(0x8000, 0x8001, 3), Ordering::Equal);
+        assert_eq!(memory.compare(0x8000, 0x8001, 4), Ordering::Less);
+        // unmapped gaps read as zero
+        memory.write_bytes(0x91FE, &[0xAA, 0xBB]).unwrap();
+        assert!(memory.equal_to_slice(0x91FE, &[0xAA, 0xBB, 0, 0]));
+        assert_eq!(memory.first_mismatch(0x91FE, &[0xAA, 0xBB, 0, 1]), Some(3));
+        assert_eq!(memory.compare(0x91FE, 0x20000, 2), Ordering::Greater);
+    }
+
+    #[test]
+    fn test_extended_simd_registers() {
+        let mut cpu = CPU::default();
+        assert_eq!(cpu.mode(), CPUMode::Mode64);
+        cpu.registers.set_bit(VecRegName::ZMM, 31, 511, true);
+        cpu.registers.set_bit(VecRegName::XMM, 16, 0, true);
+        assert_eq!(cpu.registers.get_bit(VecRegName::ZMM, 31, 511), Some(true));
+        assert_eq!(cpu.registers.get_bit(VecRegName::YMM, 16, 0), Some(true));
+        assert_eq!(cpu.registers.get_bit(VecRegName::ZMM, 32, 0), None);
+        assert!(!cpu.registers.set_by_sections::<u64>(VecRegName::XMM, 32, vec![1, 2]));
+        // only registers 0 to 7 can be encoded in 32-bit mode
+        cpu.set_mode(CPUMode::Mode32);
+        assert_eq!(cpu.registers.get_bit(VecRegName::XMM, 16, 0), None);
+        assert_eq!(cpu.registers.get_bit(VecRegName::ZMM, 31, 511), None);
+        assert_eq!(cpu.registers.get_by_sections::<u64>(VecRegName::XMM, 8), None);
+        cpu.registers.set_bit(VecRegName::XMM, 16, 1, true);
+        assert_eq!(cpu.registers.get_bit(VecRegName::XMM, 7, 0), Some(false));
+        cpu.set_mode(CPUMode::Mode64);
+        assert_eq!(cpu.registers.get_bit(VecRegName::XMM, 16, 1), Some(false));
+        assert_eq!(cpu.registers.get_bit(VecRegName::XMM, 16, 0), Some(true));
+    }
+
+    #[test]
+    fn test_memory_map_file() {
+        let path = std::env::temp_dir().join(format!("cpulib-map-file-{}", std::process::id()));
+        let contents: Vec<u8> = (0..=255).collect();
+        std::fs::write(&path, &contents).unwrap();
+        let mut memory = Memory::new(0x1000);
+        memory.map_file(0x2000, &path, 0x10, 0x20, false).unwrap();
+        assert!(memory.equal_to_slice(0x2000, &contents[0x10..0x30]));
+        assert_eq!(memory.write::<u8>(0x2005, 0), Err(MemoryError::WriteProtected { address: 0x2005 }));
+        memory.map_file(0x3000, &path, 0, 0x100, true).unwrap();
+        memory.write::<u32>(0x3000, 0xFFFF_FFFF).unwrap();
+        assert_eq!(memory.read::<u32>(0x3000), 0xFFFF_FFFF);
+        // writes stay in guest memory
+        assert_eq!(std::fs::read(&path).unwrap(), contents);
+        assert_eq!(memory.map_file(0x3080, &path, 0, 0x10, true), Err(MemoryError::Overlap { address: 0x3080 }));
+        assert_eq!(memory.map_file(0x8000, &path, 0xF8, 0x10, true), Err(MemoryError::FileError { kind: std::io::ErrorKind::UnexpectedEof }));
+        assert!(!memory.is_mapped(0x8000));
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(memory.map_file(0x8000, &path, 0, 1, true), Err(MemoryError::FileError { .. })));
+        // the range is checked before the file is opened
+        assert_eq!(memory.map_file(u64::MAX - 2, &path, 0, 16, false), Err(MemoryError::AddressOverflow { address: u64::MAX - 2 }));
+    }
+
+    #[test]
+    fn test_broadcast() {
+        let mut cpu = CPU::new(0x1000);
+        cpu.registers.set_by_sections::<u64>(VecRegName::ZMM, 0, vec![u64::MAX; 8]);
+        assert!(cpu.registers.broadcast_scalar_to_simd(VecRegName::XMM, 0, Utilities::f32_to_u32(1.0)));
+        let lanes = cpu.registers.get_by_sections::<u32>(VecRegName::XMM, 0).unwrap();
+        assert_eq!(Utilities::u32vec_to_f32vec(lanes), vec![1.0; 4]);
+        assert_eq!(cpu.registers.get_by_sections::<u32>(VecRegName::ZMM, 0).unwrap()[4..], [0; 12]);
+        cpu.registers.broadcast_scalar_to_simd(VecRegName::ZMM, 1, 0xABu8);
+        assert_eq!(cpu.registers.get_by_sections::<u8>(VecRegName::ZMM, 1).unwrap(), vec![0xAB; 64]);
+        cpu.memory.write::<u64>(0x1000, 0x0123_4567_89AB_CDEF).unwrap();
+        cpu.broadcast_from_memory::<u64>(VecRegName::YMM, 2, 0x1000).unwrap();
+        assert_eq!(cpu.registers.get_by_sections::<u64>(VecRegName::YMM, 2).unwrap(), vec![0x0123_4567_89AB_CDEF; 4]);
+        assert!(!cpu.registers.broadcast_scalar_to_simd(VecRegName::XMM, 32, 1u32));
+    }
+
+    #[test]
+    fn test_memory_copy_from() {
+        let mut src = Memory::new(0x1000);
+        let data: Vec<u8> = (0..0x300u32).map(|i| i as u8).collect();
+        src.write_bytes(0x1100, &data).unwrap();
+        let mut dst = Memory::new(0x8000);
+        dst.copy_from(&src, 0x1100..0x1400, 0x8010).unwrap();
+        assert!(dst.equal_to_slice(0x8010, &data));
+        // the copies are independent afterwards
+        dst.write::<u8>(0x8010, 0xFF).unwrap();
+        src.write::<u8>(0x1101, 0xEE).unwrap();
+        assert_eq!(src.read::<u8>(0x1100), 0x00);
+        assert_eq!(dst.read::<u8>(0x8011), 0x01);
+        // unmapped source bytes copy as zero unless the source is strict
+        dst.copy_from(&src, 0x13FE..0x1402, 0x9000).unwrap();
+        assert!(dst.equal_to_slice(0x9000, &[0xFE, 0xFF, 0, 0]));
+        src.set_strict(true);
+        assert_eq!(dst.copy_from(&src, 0x13FE..0x1402, 0xA000), Err(MemoryError::Unmapped { address: 0x1400 }));
+        assert!(!dst.is_mapped(0xA000));
+    }
+
+    #[test]
+    fn test_simd_gather() {
+        let mut cpu = CPU::new(0x1000);
+        let table: Vec<u32> = (0..64).map(|i| 1000 + i).collect();
+        cpu.memory.write_slice(0x2000, &table).unwrap();
+        cpu.registers.set_by_sections::<u32>(VecRegName::XMM, 1, vec![3, 0, 10, (-2i32) as u32]);
+        cpu.registers.set_kmask_value(KMaskName::K1, 0b1111);
+        assert!(cpu.simd_gather::<u32>(VecRegName::XMM, 0, 0x2010, VecRegName::XMM, 1, 4, KMaskName::K1));
+        assert_eq!(cpu.registers.get_by_sections::<u32>(VecRegName::XMM, 0).unwrap(), vec![1007, 1004, 1014, 1002]);
+        assert_eq!(cpu.registers.get_kmask_value(KMaskName::K1), 0);
+        // masked-off lanes are zeroed
+        cpu.registers.set_kmask_value(KMaskName::K2, 0b0101);
+        assert!(cpu.simd_gather::<u32>(VecRegName::XMM, 0, 0x2000, VecRegName::XMM, 1, 4, KMaskName::K2));
+        assert_eq!(cpu.registers.get_by_sections::<u32>(VecRegName::XMM, 0).unwrap(), vec![1003, 0, 1010, 0]);
+        // 64-bit lanes take the low index lanes
+        cpu.memory.write_slice::<u64>(0x3000, &[11, 22, 33, 44]).unwrap();
+        cpu.registers.set_by_sections::<u32>(VecRegName::XMM, 2, vec![3, 2, 1, 0]);
+        cpu.registers.set_kmask_value(KMaskName::K3, 0xFF);
+        assert!(cpu.simd_gather::<u64>(VecRegName::YMM, 4, 0x3000, VecRegName::XMM, 2, 8, KMaskName::K3));
+        assert_eq!(cpu.registers.get_by_sections::<u64>(VecRegName::YMM, 4).unwrap(), vec![44, 33, 22, 11]);
+        // the index register must have a lane per destination lane
+        cpu.registers.set_kmask_value(KMaskName::K3, 0xFF);
+        assert!(!cpu.simd_gather::<u32>(VecRegName::YMM, 4, 0x3000, VecRegName::XMM, 2, 4, KMaskName::K3));
+        assert_eq!(cpu.registers.get_kmask_value(KMaskName::K3), 0xFF);
+    }
+
+    #[test]
+    fn test_memory_wide_addresses() {
+        let mut memory = Memory::new(0x7FFF_FFFF_0000);
+        memory.write::<u64>(0x7FFF_FFFF_0000, 0x0123_4567_89AB_CDEF).unwrap();
+        memory.write::<u32>(0x7FFF_FFFF_FFFC, 0xCAFE_BABE).unwrap();
+        assert_eq!(memory.read::<u64>(0x7FFF_FFFF_0000), 0x0123_4567_89AB_CDEF);
+        assert_eq!(memory.read::<u32>(0x7FFF_FFFF_FFFC), 0xCAFE_BABE);
+        assert!(memory.is_mapped(0x7FFF_FFFF_FFFF));
+        // below the base there is nothing to map
+        assert!(!memory.is_mapped(0x1_0000_0000));
+        assert_eq!(memory.find(&[0xBE, 0xBA], None), Some(0x7FFF_FFFF_FFFC));
+        let mut high = Memory::new(0);
+        high.write::<u16>(0x1_0000_0000, 0xBEEF).unwrap();
+        assert_eq!(high.read::<u16>(0x1_0000_0000), 0xBEEF);
+        assert_eq!(high.read::<u16>(0x0), 0);
+        // accesses may not wrap around the end of the address space
+        assert_eq!(high.write::<u32>(u64::MAX - 1, 0), Err(MemoryError::AddressOverflow { address: u64::MAX - 1 }));
+        assert_eq!(high.try_read::<u32>(u64::MAX - 1), Err(MemoryError::AddressOverflow { address: u64::MAX - 1 }));
+        assert_eq!(high.read::<u32>(u64::MAX - 1), 0);
+    }
+
+    #[test]
+    fn test_simd_scatter() {
+        let mut cpu = CPU::new(0x1000);
+        cpu.registers.set_by_sections::<u32>(VecRegName::XMM, 0, vec![10, 20, 30, 40]);
+        cpu.registers.set_by_sections::<u32>(VecRegName::XMM, 1, vec![3, 0, 1, (-1i32) as u32]);
+        cpu.registers.set_kmask_value(KMaskName::K1, 0b1111);
+        assert!(cpu.simd_scatter::<u32>(VecRegName::XMM, 0, 0x2010, VecRegName::XMM, 1, 4, KMaskName::K1));
+        assert_eq!(cpu.memory.read::<u32>(0x201C), 10);
+        assert_eq!(cpu.memory.read::<u32>(0x2010), 20);
+        assert_eq!(cpu.memory.read::<u32>(0x2014), 30);
+        assert_eq!(cpu.memory.read::<u32>(0x200C), 40);
+        assert_eq!(cpu.registers.get_kmask_value(KMaskName::K1), 0);
+        // masked-off lanes are not stored
+        cpu.registers.set_by_sections::<u32>(VecRegName::XMM, 0, vec![1, 2, 3, 4]);
+        cpu.registers.set_kmask_value(KMaskName::K2, 0b1010);
+        assert!(cpu.simd_scatter::<u32>(VecRegName::XMM, 0, 0x2010, VecRegName::XMM, 1, 4, KMaskName::K2));
+        assert_eq!(cpu.memory.read::<u32>(0x201C), 10);
+        assert_eq!(cpu.memory.read::<u32>(0x2010), 2);
+        assert_eq!(cpu.memory.read::<u32>(0x2014), 30);
+        assert_eq!(cpu.memory.read::<u32>(0x200C), 4);
+        // conflicting indices keep the highest lane's value
+        cpu.registers.set_by_sections::<u32>(VecRegName::XMM, 1, vec![5, 5, 6, 5]);
+        cpu.registers.set_by_sections::<u32>(VecRegName::XMM, 0, vec![100, 200, 300, 400]);
+        cpu.registers.set_kmask_value(KMaskName::K3, 0b0111);
+        assert!(cpu.simd_scatter::<u32>(VecRegName::XMM, 0, 0x3000, VecRegName::XMM, 1, 4, KMaskName::K3));
+        assert_eq!(cpu.memory.read::<u32>(0x3014), 200);
+        assert_eq!(cpu.memory.read::<u32>(0x3018), 300);
+        cpu.registers.set_kmask_value(KMaskName::K3, 0b1111);
+        assert!(cpu.simd_scatter::<u32>(VecRegName::XMM, 0, 0x3000, VecRegName::XMM, 1, 4, KMaskName::K3));
+        assert_eq!(cpu.memory.read::<u32>(0x3014), 400);
+        // a failing write leaves the mask bits of the remaining lanes set
+        cpu.memory.protect(0x4000..0x4004, Protection::ReadOnly);
+        cpu.registers.set_by_sections::<u32>(VecRegName::XMM, 1, vec![1, 0, 2, 3]);
+        cpu.registers.set_kmask_value(KMaskName::K4, 0b1111);
+        assert!(!cpu.simd_scatter::<u32>(VecRegName::XMM, 0, 0x4000, VecRegName::XMM, 1, 4, KMaskName::K4));
+        assert_eq!(cpu.memory.read::<u32>(0x4004), 100);
+        assert_eq!(cpu.registers.get_kmask_value(KMaskName::K4), 0b1110);
+    }
+
+    #[test]
+    fn test_memory_guard() {
+        let mut memory = Memory::new(0x1000);
+        memory.map(0x1000..0x2000).unwrap();
+        memory.map_guard(0x2000..0x3000).unwrap();
+        // guard hits are reported in lenient mode as well
+        assert!(!memory.is_strict());
+        assert_eq!(memory.try_read::<u32>(0x1FFE), Err(MemoryError::GuardHit { address: 0x2000 }));
+        assert_eq!(memory.write::<u8>(0x2800, 1), Err(MemoryError::GuardHit { address: 0x2800 }));
+        assert_eq!(memory.fetch::<u8>(0x2FFF), Err(MemoryError::GuardHit { address: 0x2FFF }));
+        assert_eq!(memory.read::<u32>(0x1FFE), 0);
+        assert!(!memory.is_mapped(0x2800));
+        assert_eq!(memory.map(0x2F00..0x3100), Err(MemoryError::Overlap { address: 0x2F00 }));
+        assert_eq!(memory.regions().collect::<Vec<_>>(), vec![
+            Region { range: 0x1000..0x2000, kind: RegionKind::Ram },
+            Region { range: 0x2000..0x3000, kind: RegionKind::Guard },
+        ]);
+        // removing part of a guard region leaves the rest guarded
+        memory.unmap_guard(0x2000..0x2800);
+        memory.write::<u8>(0x2000, 1).unwrap();
+        assert_eq!(memory.try_read::<u8>(0x2800), Err(MemoryError::GuardHit { address: 0x2800 }));
+        assert_eq!(memory.regions().map(|region| region.kind).collect::<Vec<_>>(),
+                   vec![RegionKind::Ram, RegionKind::Guard]);
+        memory.unmap_guard(0x2800..0x3000);
+        assert_eq!(memory.try_read::<u8>(0x2800), Ok(0));
+        // translated addresses are checked too
+        memory.map_guard(0x5000..0x6000).unwrap();
+        memory.set_translator(Box::new(|address, _| Ok(address + 0x4000)));
+        assert_eq!(memory.try_read::<u8>(0x1000), Err(MemoryError::GuardHit { address: 0x5000 }));
+        assert_eq!(memory.write::<u16>(0x0FFF, 0), Err(MemoryError::GuardHit { address: 0x5000 }));
+    }
+
+    #[test]
+    fn test_memory_paging() {
+        let mut table = PageTable::new(0x1000);
+        table.map_page(0x10, 0x2, PagePerms { read: true, write: true, execute: false });
+        table.map_page(0x12345, 0x3, PagePerms { read: true, write: false, execute: true });
+        assert_eq!(table.translate(0x10_123), Ok(0x2123));
+        assert_eq!(table.translate(0x1234_5FFF), Ok(0x3FFF));
+        assert_eq!(table.translate(0x11_000), Err(PageFaultError { address: 0x11_000, kind: AccessKind::Read, present: false }));
+        assert_eq!(table.translate_access(0x1234_5000, AccessKind::Write),
+                   Err(PageFaultError { address: 0x1234_5000, kind: AccessKind::Write, present: true }));
+        let mut memory = Memory::new(0x1000);
+        memory.enable_paging(table);
+        memory.write::<u32>(0x10_FFC, 0xDEAD_BEEF).unwrap();
+        assert_eq!(memory.read::<u32>(0x10_FFC), 0xDEAD_BEEF);
+        assert_eq!(memory.fetch::<u8>(0x1234_5000), Ok(0));
+        // the access crosses into an unmapped page, so nothing is written
+        assert_eq!(memory.write::<u64>(0x10_FFC, 0),
+                   Err(MemoryError::PageFault { address: 0x11_000, kind: AccessKind::Write, present: false }));
+        assert_eq!(memory.try_read::<u8>(0x11_000),
+                   Err(MemoryError::PageFault { address: 0x11_000, kind: AccessKind::Read, present: false }));
+        assert_eq!(memory.write::<u8>(0x1234_5000, 1),
+                   Err(MemoryError::PageFault { address: 0x1234_5000, kind: AccessKind::Write, present: true }));
+        memory.page_table_mut().unwrap().map_page(0x11, 0x4, PagePerms { read: true, write: true, execute: false });
+        memory.write::<u64>(0x10_FFC, 0x1122_3344_5566_7788).unwrap();
+        assert_eq!(memory.read::<u64>(0x10_FFC), 0x1122_3344_5566_7788);
+        // without paging the physical addresses are visible directly
+        let table = memory.disable_paging().unwrap();
+        assert_eq!(memory.read::<u32>(0x2FFC), 0x5566_7788);
+        assert_eq!(memory.read::<u32>(0x4000), 0x1122_3344);
+        assert_eq!(table.translate(0x11_000), Ok(0x4000));
+    }
+
+    #[test]
+    fn test_memory_unaligned_segment_gaps() {
+        let mut memory = Memory::new(0x1000);
+        let mut expected = vec![0u8; 0x600];
+        for (i, byte) in expected.iter_mut().enumerate() {
+            *byte = i as u8 ^ 0x5A;
+        }
+        memory.write_slice::<u8>(0x1000, &expected).unwrap();
+        // leave segments that end and start in the middle of a block
+        memory.unmap_range(0x1100..0x1180);
+        memory.unmap_range(0x1300..0x1480);
+        for byte in &mut expected[0x100..0x180] {
+            *byte = 0;
+        }
+        for byte in &mut expected[0x300..0x480] {
+            *byte = 0;
+        }
+        // writes into the gaps must not create segments shadowing the remaining bytes
+        for (address, value) in [(0x1140, 0xA1), (0x1100, 0xA2), (0x117F, 0xA3), (0x13FF, 0xA4), (0x1400, 0xA5), (0x1300, 0xA6)] {
+            memory.write::<u8>(address, value).unwrap();
+            expected[(address - 0x1000) as usize] = value;
+        }
+        memory.write::<u32>(0x147E, 0xB0B1_B2B3).unwrap();
+        expected[0x47E..0x482].copy_from_slice(&0xB0B1_B2B3u32.to_le_bytes());
+        for (i, byte) in expected.iter().enumerate() {
+            assert_eq!(memory.read::<u8>(0x1000 + i as u64), *byte, "byte at {:#x}", 0x1000 + i);
+        }
+        assert_eq!(memory.mapped_size(), 0x600);
+        // the limit counts only the bytes actually added
+        let mut limited = Memory::new(0);
+        limited.write::<u8>(0x1FF, 1).unwrap();
+        limited.unmap_range(0x10..0x1F0);
+        limited.set_limit(Some(0x200));
+        limited.write::<u8>(0x100, 2).unwrap();
+        assert_eq!(limited.mapped_size(), 0x200);
+        assert_eq!(limited.read::<u8>(0x1FF), 1);
+    }
+
+    #[test]
+    fn test_extract_bit_range() {
+        let mut registers = Registers::new();
+        registers.set_by_sections::<u64>(VecRegName::ZMM, 3, vec![
+            0x0123_4567_89AB_CDEF, 0xFEDC_BA98_7654_3210, 0, 0, 0, 0, 0, 0xFFFF_0000_0000_0000,
+        ]);
+        // a 48-bit operand
+        assert_eq!(registers.extract_bit_range(3, 0, 47), Some(0x4567_89AB_CDEF));
+        // an 80-bit operand straddling the 64-bit lane boundary
+        assert_eq!(registers.extract_bit_range(3, 0, 79), Some(0x3210_0123_4567_89AB_CDEF));
+        // unaligned ranges
+        assert_eq!(registers.extract_bit_range(3, 4, 11), Some(0xDE));
+        assert_eq!(registers.extract_bit_range(3, 60, 67), Some(0x00));
+        assert_eq!(registers.extract_bit_range(3, 56, 71), Some(0x1001));
+        assert_eq!(registers.extract_bit_range(3, 0, 127), Some(0xFEDC_BA98_7654_3210_0123_4567_89AB_CDEF));
+        assert_eq!(registers.extract_bit_range(3, 496, 511), Some(0xFFFF));
+        assert_eq!(registers.extract_bit_range(3, 5, 5), Some(1));
+        // invalid ranges
+        assert_eq!(registers.extract_bit_range(3, 0, 128), None);
+        assert_eq!(registers.extract_bit_range(3, 500, 512), None);
+        assert_eq!(registers.extract_bit_range(3, 10, 9), None);
+        registers.set_mode(CPUMode::Mode32);
+        assert_eq!(registers.extract_bit_range(8, 0, 7), None);
+    }
+
+    #[test]
+    fn test_memory_view() {
+        let mut memory = Memory::new(0x1000);
+        memory.map(0x1000..0x1400).unwrap();
+        memory.write::<u32>(0x1100, 0x4433_2211).unwrap();
+        assert_eq!(memory.view(0x1100..0x1104), Some(&[0x11, 0x22, 0x33, 0x44][..]));
+        assert_eq!(memory.view(0x1000..0x1400).map(|bytes| bytes.len()), Some(0x400));
+        // ranges leaving the segment can't be viewed
+        assert_eq!(memory.view(0x13FC..0x1404), None);
+        assert_eq!(memory.view(0x0FFF..0x1001), None);
+        memory.map(0x2000..0x2200).unwrap();
+        assert_eq!(memory.view(0x1300..0x2100), None);
+        // mutation through the view is visible to reads and marks pages dirty
+        memory.enable_dirty_tracking(0x100);
+        memory.view_mut(0x11FE..0x1202).unwrap().copy_from_slice(&[0xAA, 0xBB, 0xCC, 0xDD]);
+        assert_eq!(memory.read::<u32>(0x11FE), 0xDDCC_BBAA);
+        assert_eq!(memory.dirty_pages(), vec![0x1100, 0x1200]);
+        // read-only bytes can be viewed but not mutated
+        memory.protect(0x1300..0x1301, Protection::ReadOnly);
+        assert!(memory.view(0x1300..0x1310).is_some());
+        assert!(memory.view_mut(0x12F0..0x1310).is_none());
+        // translated memory falls back to copying
+        memory.set_translator(Box::new(|address, _| Ok(address)));
+        assert_eq!(memory.view(0x1100..0x1104), None);
+    }
+
+    #[test]
+    fn test_vec_reg_widths() {
+        assert_eq!(VecRegName::XMM.width_bits(), 128);
+        assert_eq!(VecRegName::YMM.width_bits(), 256);
+        assert_eq!(VecRegName::ZMM.width_bits(), 512);
+        assert_eq!(VecRegName::YMM.width_bytes(), 32);
+        assert_eq!(VecRegName::XMM.lane_count::<u8>(), 16);
+        assert_eq!(VecRegName::YMM.lane_count::<u32>(), 8);
+        assert_eq!(VecRegName::ZMM.lane_count::<u64>(), 8);
+        assert_eq!(VecRegName::ZMM.lane_count::<u128>(), 4);
+    }
+
+    #[test]
+    fn test_memory_reserve() {
+        let mut memory = Memory::new(0);
+        memory.reserve(0x10_0000..0x50_0000).unwrap();
+        assert_eq!(memory.mapped_size(), 0x40_0000);
+        assert_eq!(memory.regions().collect::<Vec<_>>(), vec![Region { range: 0x10_0000..0x50_0000, kind: RegionKind::Ram }]);
+        assert_eq!(memory.read::<u64>(0x10_0000), 0);
+        assert_eq!(memory.read::<u64>(0x4F_FFF8), 0);
+        assert_eq!(memory.first_nonzero_after(0x10_0000), None);
+        // reserving over existing memory keeps its contents and fills the gaps with single segments
+        memory.write::<u32>(0x60_0000, 0x1234_5678).unwrap();
+        memory.reserve(0x4F_0000..0x70_0000).unwrap();
+        assert_eq!(memory.regions().count(), 1);
+        assert_eq!(memory.read::<u32>(0x60_0000), 0x1234_5678);
+        assert_eq!(memory.mapped_size(), 0x60_0000);
+        memory.write::<u32>(0x6F_FFFC, 0xAABB_CCDD).unwrap();
+        assert_eq!(memory.mapped_size(), 0x60_0000);
+        // the limit is checked before anything is mapped
+        memory.set_limit(Some(0x60_1000));
+        assert_eq!(memory.reserve(0x80_0000..0x80_2000), Err(MemoryError::LimitExceeded { address: 0x80_1000, limit: 0x60_1000 }));
+        assert_eq!(memory.mapped_size(), 0x60_0000);
+        assert!(!memory.is_mapped(0x80_0000));
+        memory.reserve(0x80_0000..0x80_1000).unwrap();
+        // MMIO and guard regions can't be reserved over
+        memory.map_guard(0x90_0000..0x90_1000).unwrap();
+        assert_eq!(memory.reserve(0x8F_F000..0x90_1000), Err(MemoryError::Overlap { address: 0x90_0000 }));
+    }
+
+    #[test]
+    fn test_gpr_aliases() {
+        assert!(GPRName::EAX.canonical() == GPRName::RAX);
+        assert!(GPRName::AH.canonical() == GPRName::RAX);
+        assert!(GPRName::SIL.canonical() == GPRName::RSI);
+        assert!(GPRName::R12W.canonical() == GPRName::R12);
+        assert!(GPRName::R15.canonical() == GPRName::R15);
+        assert_eq!(GPRName::RAX.bit_range(), (0, 63));
+        assert_eq!(GPRName::EAX.bit_range(), (0, 31));
+        assert_eq!(GPRName::DX.bit_range(), (0, 15));
+        assert_eq!(GPRName::AH.bit_range(), (8, 15));
+        assert_eq!(GPRName::R9B.bit_range(), (0, 7));
+        // the bit range selects the alias from its parent
+        let mut registers = Registers::new();
+        registers.set_gpr_value(GPRName::RBX, 0x1122_3344_5566_7788);
+        for name in [GPRName::EBX, GPRName::BX, GPRName::BL, GPRName::BH] {
+            let (low, high) = name.bit_range();
+            let parent = registers.get_gpr_value(name.canonical());
+            assert_eq!(parent >> low & (u64::MAX >> (63 - (high - low))), registers.get_gpr_value(name));
+        }
+    }
+
+    #[test]
+    fn test_memory_endianness() {
+        fn raw(memory: &Memory, address: u64, length: usize) -> Vec<u8> {
+            let mut bytes = vec![0; length];
+            memory.read_exact(address, &mut bytes).unwrap();
+            bytes
+        }
+        fn check<T: MemoryIO + Copy + PartialEq + std::fmt::Debug>(value: T, unit: usize) {
+            let mut little = Memory::new_with_endianness(0x1000, Endianness::Little);
+            let mut big = Memory::new_with_endianness(0x1000, Endianness::Big);
+            little.write::<T>(0x1000, value).unwrap();
+            big.write::<T>(0x1000, value).unwrap();
+            assert_eq!(little.read::<T>(0x1000), value);
+            assert_eq!(big.read::<T>(0x1000), value);
+            // the raw layouts differ only by swapping each unit
+            let mut swapped = raw(&little, 0x1000, T::size());
+            for chunk in swapped.chunks_mut(unit) {
+                chunk.reverse();
+            }
+            assert_eq!(raw(&big, 0x1000, T::size()), swapped);
+            little.write_vec::<T>(0x2000, vec![value, value]).unwrap();
+            big.write_vec::<T>(0x2000, vec![value, value]).unwrap();
+            assert_eq!(little.read_vec::<T>(0x2000, 2), vec![value, value]);
+            assert_eq!(big.read_vec::<T>(0x2000, 2), vec![value, value]);
+            assert_eq!(raw(&big, 0x2000 + T::size() as u64, T::size()), swapped);
+        }
+        check::<u8>(0x12, 1);
+        check::<u16>(0x1234, 2);
+        check::<u32>(0x1234_5678, 4);
+        check::<u64>(0x0123_4567_89AB_CDEF, 8);
+        check::<u128>(0x0011_2233_4455_6677_8899_AABB_CCDD_EEFF, 16);
+        check::<u256>(u256::from_dec_str("1234567890123456789012345678901234567890").unwrap(), 32);
+        check::<u512>(u512::MAX - u512::from(0x1234), 64);
+        check::<[u32; 4]>([1, 2, 3, 0xAABB_CCDD], 4);
+        check::<[u64; 8]>([1, 2, 3, 4, 5, 6, 7, 0x1122_3344_5566_7788], 8);
+        let mut big = Memory::new_with_endianness(0, Endianness::Big);
+        assert_eq!(big.endianness(), Endianness::Big);
+        assert_eq!(Memory::new(0).endianness(), Endianness::Little);
+        big.write::<u32>(0x10, 0x1122_3344).unwrap();
+        assert_eq!(raw(&big, 0x10, 4), vec![0x11, 0x22, 0x33, 0x44]);
+        big.write_f32(0x20, 1.0).unwrap();
+        assert_eq!(raw(&big, 0x20, 4), vec![0x3F, 0x80, 0x00, 0x00]);
+        assert_eq!(big.read_f32(0x20), 1.0);
+    }
+
+    #[test]
+    fn test_step_cycles() {
+        let mut cpu = CPU::new(0x1000);
+        cpu.setup_stack(0x9000, 0x1000).unwrap();
+        cpu.registers.set_ip_value(IPName::RIP, 0x400000);
+        cpu.set_instruction_latency(Instruction::Pushfq, 3);
+        cpu.set_instruction_latency(Instruction::Popfq, 9);
+        cpu.set_instruction_latency(Instruction::Cqo, 2);
+        assert_eq!(cpu.instruction_latency(Instruction::Nop), 1);
+        let program = [
+            Instruction::Stc, Instruction::Pushfq, Instruction::Clc, Instruction::Nop,
+            Instruction::Popfq, Instruction::Cqo, Instruction::Cdqe, Instruction::Lahf,
+        ];
+        for instruction in program {
+            cpu.step(instruction).unwrap();
+        }
+        let expected: u64 = program.iter().map(|instruction| cpu.instruction_latency(*instruction)).sum();
+        assert_eq!(expected, 19);
+        assert_eq!(cpu.get_cycle_count(), expected);
+        assert_eq!(cpu.registers.get_ip_value(IPName::RIP), 0x400000 + 10);
+        // POPFQ restored the carry flag saved by PUSHFQ
+        assert_eq!(cpu.registers.get_flags_value(FLAGSName::RFLAGS) & 1, 1);
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::AH), 0x03);
+        cpu.tick(100);
+        assert_eq!(cpu.get_cycle_count(), 119);
+        cpu.reset_cycle_count();
+        assert_eq!(cpu.get_cycle_count(), 0);
+        // a faulting instruction charges nothing
+        cpu.registers.set_gpr_value(GPRName::RSP, 0x8000);
+        assert!(cpu.step(Instruction::Pushfq).is_err());
+        assert_eq!(cpu.get_cycle_count(), 0);
+        assert_eq!(cpu.registers.get_ip_value(IPName::RIP), 0x400000 + 10);
+    }
+
+    #[test]
+    fn test_implicit_operand_instructions() {
+        let mut cpu = CPU::new(0x1000);
+        cpu.registers.set_gpr_value(GPRName::RAX, 0xFFFF_FFFF_8000_0000);
+        cpu.step(Instruction::Cqo).unwrap();
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::RDX), u64::MAX);
+        cpu.step(Instruction::Cdqe).unwrap();
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::RAX), 0xFFFF_FFFF_8000_0000);
+        cpu.registers.set_gpr_value(GPRName::RAX, 0x1234_5678_7FFF_FFFF);
+        cpu.step(Instruction::Cdqe).unwrap();
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::RAX), 0x7FFF_FFFF);
+        cpu.step(Instruction::Cqo).unwrap();
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::RDX), 0);
+        cpu.step(Instruction::Std).unwrap();
+        assert_eq!(cpu.registers.get_flags_value(FLAGSName::RFLAGS) & 0x400, 0x400);
+        cpu.step(Instruction::Cld).unwrap();
+        assert_eq!(cpu.registers.get_flags_value(FLAGSName::RFLAGS) & 0x400, 0);
+        cpu.step(Instruction::Cmc).unwrap();
+        assert_eq!(cpu.registers.get_flags_value(FLAGSName::RFLAGS) & 1, 1);
+        // SAHF only loads SF, ZF, AF, PF and CF
+        cpu.registers.set_gpr_value(GPRName::AH, 0xFF);
+        cpu.step(Instruction::Sahf).unwrap();
+        assert_eq!(cpu.registers.get_flags_value(FLAGSName::RFLAGS) & 0xFF, 0xD5);
+        cpu.step(Instruction::Lahf).unwrap();
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::AH), 0xD7);
+    }
+
+    #[test]
+    fn test_memory_defragment() {
+        let mut memory = Memory::new(0);
+        for i in 0..32u64 {
+            memory.write::<u32>(i * 0x400 + i, 0xC0DE_0000 | i as u32).unwrap();
+        }
+        memory.write::<u8>(0x10_0000, 0xEE).unwrap();
+        let report = memory.fragmentation();
+        assert_eq!(report, FragReport { segment_count: 33, gap_count: 32, gap_bytes: 0x10_0200 - 33 * 0x200, average_segment_size: 0x200 });
+        let before = memory.read_vec::<u8>(0, 0x8400);
+        memory.set_strict(true);
+        assert_eq!(memory.try_read::<u8>(0x300), Err(MemoryError::Unmapped { address: 0x300 }));
+        // gaps next to guard regions stay unmapped
+        memory.map_guard(0x7A00..0x7B00).unwrap();
+        memory.defragment(0x200);
+        let report = memory.fragmentation();
+        assert_eq!(report.segment_count, 3);
+        assert_eq!(report.gap_count, 2);
+        assert_eq!(memory.read_vec::<u8>(0, 0x8400), before);
+        assert_eq!(memory.read::<u8>(0x10_0000), 0xEE);
+        assert_eq!(memory.try_read::<u8>(0x300), Ok(0));
+        assert_eq!(memory.try_read::<u8>(0x7A00), Err(MemoryError::GuardHit { address: 0x7A00 }));
+        // the limit bounds the memory spent on filling gaps
+        let mut limited = Memory::new(0);
+        for i in 0..4u64 {
+            limited.write::<u8>(i * 0x400, 1).unwrap();
+        }
+        limited.set_limit(Some(0xC00));
+        limited.defragment(0x200);
+        assert_eq!(limited.mapped_size(), 0xC00);
+        assert_eq!(limited.fragmentation().segment_count, 2);
+        assert_eq!(Memory::new(0).fragmentation(), FragReport::default());
+    }
+
+    #[test]
+    fn test_memory_compact() {
+        let mut memory = Memory::new(0x1000);
+        assert_eq!(memory.fragmentation_ratio(), 0.0);
+        memory.write::<u8>(0x1000, 1).unwrap();
+        assert_eq!(memory.fragmentation_ratio(), 0.0);
+        memory.write::<u8>(0x1600, 2).unwrap();
+        // 0x200 + 0x200 of a 0x800 span is mapped
+        assert_eq!(memory.fragmentation_ratio(), 0.5);
+        memory.write::<u8>(0x2E00, 3).unwrap();
+        assert_eq!(memory.fragmentation_ratio(), 0x1A00 as f64 / 0x2000 as f64);
+        assert_eq!(memory.compact(), Ok(()));
+        assert_eq!(memory.fragmentation_ratio(), 0.0);
+        assert_eq!(memory.fragmentation().segment_count, 1);
+        assert_eq!(memory.mapped_size(), 0x2000);
+        assert_eq!(memory.read::<u8>(0x1000), 1);
+        assert_eq!(memory.read::<u8>(0x1600), 2);
+        assert_eq!(memory.read::<u8>(0x2E00), 3);
+        assert_eq!(memory.read::<u8>(0x2000), 0);
+
+        // gaps that can't be filled are reported, and the others are still filled
+        let mut memory = Memory::new(0x1000);
+        memory.write::<u8>(0x1000, 1).unwrap();
+        memory.write::<u8>(0x1400, 2).unwrap();
+        memory.map_guard(0x1800..0x1900).unwrap();
+        memory.write::<u8>(0x1A00, 3).unwrap();
+        assert_eq!(memory.compact(), Err(MemoryError::Overlap { address: 0x1800 }));
+        assert_eq!(memory.fragmentation().segment_count, 2);
+        assert!(memory.fragmentation_ratio() > 0.0);
+        memory.unmap_guard(0x1800..0x1900);
+        memory.set_limit(Some(0x800));
+        assert_eq!(memory.compact(), Err(MemoryError::LimitExceeded { address: 0x1600, limit: 0x800 }));
+        memory.set_limit(None);
+        assert_eq!(memory.compact(), Ok(()));
+        assert_eq!(memory.fragmentation_ratio(), 0.0);
+    }
+
+    #[test]
+    fn test_memory_poisoning() {
+        let mut memory = Memory::new(0x1000);
+        memory.set_poisoning(true);
+        memory.set_strict(true);
+        // only the low two bytes of the u32 are written
+        memory.write::<u16>(0x1100, 0xBEEF).unwrap();
+        assert_eq!(memory.try_read::<u16>(0x1100), Ok(0xBEEF));
+        assert_eq!(memory.try_read::<u32>(0x1100), Err(MemoryError::Uninitialized { address: 0x1102 }));
+        assert_eq!(memory.try_read::<u32>(0x10FE), Err(MemoryError::Uninitialized { address: 0x10FE }));
+        memory.write::<u8>(0x1102, 0xAD).unwrap();
+        assert_eq!(memory.try_read::<u32>(0x1100), Err(MemoryError::Uninitialized { address: 0x1103 }));
+        memory.write::<u8>(0x1103, 0xDE).unwrap();
+        assert_eq!(memory.try_read::<u32>(0x1100), Ok(0xDEAD_BEEF));
+        // lenient reads call the handler instead
+        let hits = std::rc::Rc::new(RefCell::new(Vec::new()));
+        let log = hits.clone();
+        memory.set_uninit_handler(Box::new(move |address| log.borrow_mut().push(address)));
+        assert_eq!(memory.read::<u32>(0x10FF), 0xADBE_EF00);
+        assert_eq!(memory.read::<u32>(0x1100), 0xDEAD_BEEF);
+        assert_eq!(*hits.borrow(), vec![0x10FF]);
+        // mapped, reserved and filled ranges are initialized
+        memory.map(0x4000..0x4100).unwrap();
+        memory.reserve(0x1000..0x1200).unwrap();
+        memory.fill(0x2000, 0x10, 0xFF).unwrap();
+        assert_eq!(memory.try_read::<u64>(0x4000), Ok(0));
+        assert_eq!(memory.try_read::<u64>(0x11F8), Ok(0));
+        assert_eq!(memory.try_read::<u64>(0x2008), Ok(u64::MAX));
+        assert_eq!(memory.try_read::<u8>(0x2010), Err(MemoryError::Uninitialized { address: 0x2010 }));
+        // poison survives merging and splitting segments
+        memory.write::<u8>(0x1200, 1).unwrap();
+        memory.unmap_range(0x1201..0x1202);
+        assert_eq!(memory.try_read::<u8>(0x1200), Ok(1));
+        assert_eq!(memory.try_read::<u8>(0x1202), Err(MemoryError::Uninitialized { address: 0x1202 }));
+        // translated reads point at the guest address
+        memory.set_translator(Box::new(|address, _| Ok(address + 0x1000)));
+        assert_eq!(memory.try_read::<u16>(0x100F), Err(MemoryError::Uninitialized { address: 0x1010 }));
+        memory.clear_translator();
+        // disabling poisoning initializes everything
+        memory.set_poisoning(false);
+        assert_eq!(memory.try_read::<u8>(0x2010), Ok(0));
+        assert_eq!(hits.borrow().len(), 1);
+    }
+
+    #[test]
+    fn test_registers_bytes() {
+        let mut registers = Registers::new();
+        registers.set_by_sections::<u64>(VecRegName::ZMM, 31, (1..=8).collect());
+        registers.set_by_sections::<u32>(VecRegName::XMM, 0, vec![0x11, 0x22, 0x33, 0x44]);
+        registers.set_gpr_value(GPRName::RCX, 0x0123_4567_89AB_CDEF);
+        registers.set_gpr_value(GPRName::R15, 0xFFFF);
+        registers.set_flags_value(FLAGSName::RFLAGS, 0x246);
+        registers.set_ip_value(IPName::RIP, 0x40_1000);
+        registers.set_kmask_value(KMaskName::K7, 0xF0F0);
+        let bytes = registers.to_bytes();
+        assert_eq!(bytes.len(), REGISTERS_BYTES_LEN);
+        assert_eq!(bytes.len(), 2256);
+        assert_eq!(&bytes[0..4], &[0x11, 0, 0, 0]);
+        assert_eq!(&bytes[2048 + 16..2048 + 24], &0x0123_4567_89AB_CDEFu64.to_le_bytes());
+        assert_eq!(&bytes[2176..2178], &[0x46, 0x02]);
+        assert_eq!(&bytes[2248..2256], &0xF0F0u64.to_le_bytes());
+        let decoded = Registers::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.to_bytes(), bytes);
+        assert_eq!(decoded.get_by_sections::<u64>(VecRegName::ZMM, 31).unwrap(), (1..=8).collect::<Vec<u64>>());
+        // a flipped bit shows up in the matching register
+        let mut flipped = bytes.clone();
+        flipped[2048 + 7 * 8] ^= 0x10;
+        flipped[31 * 64 + 63] ^= 0x80;
+        flipped[2184] ^= 0x01;
+        let decoded = Registers::from_bytes(&flipped).unwrap();
+        assert_eq!(decoded.get_gpr_value(GPRName::RSP), 0x10);
+        assert_eq!(decoded.get_bit(VecRegName::ZMM, 31, 511), Some(true));
+        assert_eq!(decoded.get_ip_value(IPName::RIP), 0x40_1001);
+        assert_eq!(decoded.get_gpr_value(GPRName::RCX), 0x0123_4567_89AB_CDEF);
+        assert_eq!(Registers::from_bytes(&bytes[1..]).err(), Some(DeserializeError::InvalidLength { expected: 2256, actual: 2255 }));
+    }
+
+    #[test]
+    fn test_memory_fork() {
+        let mut memory = Memory::new(0x1000);
+        memory.write::<u32>(0x1000, 0x1111_1111).unwrap();
+        memory.write::<u32>(0x2000, 0x2222_2222).unwrap();
+        memory.write::<u32>(0x3000, 0x3333_3333).unwrap();
+        memory.protect(0x3000..0x3004, Protection::ReadOnly);
+        let mut fork = memory.fork();
+        assert_eq!(memory.segment_share_count(0x1000), Some(2));
+        assert_eq!(fork.read::<u32>(0x2000), 0x2222_2222);
+        assert_eq!(fork.write::<u8>(0x3000, 0), Err(MemoryError::WriteProtected { address: 0x3000 }));
+        // both sides diverge without seeing each other's writes
+        memory.write::<u32>(0x1000, 0xAAAA_AAAA).unwrap();
+        fork.write::<u32>(0x2000, 0xBBBB_BBBB).unwrap();
+        fork.write::<u32>(0x5000, 0xCCCC_CCCC).unwrap();
+        assert_eq!(memory.read::<u32>(0x1000), 0xAAAA_AAAA);
+        assert_eq!(fork.read::<u32>(0x1000), 0x1111_1111);
+        assert_eq!(memory.read::<u32>(0x2000), 0x2222_2222);
+        assert_eq!(fork.read::<u32>(0x2000), 0xBBBB_BBBB);
+        assert!(!memory.is_mapped(0x5000));
+        // only the written segments were copied
+        assert_eq!(memory.segment_share_count(0x1000), Some(1));
+        assert_eq!(fork.segment_share_count(0x2000), Some(1));
+        assert_eq!(memory.segment_share_count(0x3000), Some(2));
+        assert_eq!(fork.segment_share_count(0x3000), Some(2));
+        drop(fork);
+        assert_eq!(memory.segment_share_count(0x3000), Some(1));
+        // views of a shared segment copy it before handing out a mutable borrow
+        let fork = memory.fork();
+        memory.view_mut(0x2000..0x2004).unwrap().copy_from_slice(&[1, 2, 3, 4]);
+        assert_eq!(fork.read::<u32>(0x2000), 0x2222_2222);
+        assert_eq!(memory.read::<u32>(0x2000), 0x0403_0201);
+    }
+
+    #[test]
+    fn test_memory_bytes() {
+        let mut memory = Memory::new(0x1000);
+        memory.write::<u32>(0x1004, 0xDEAD_BEEF).unwrap();
+        memory.write::<u64>(0x13FC, 0x0102_0304_0506_0708).unwrap();
+        memory.write::<u8>(0x8000, 0x7F).unwrap();
+        let bytes = memory.to_bytes();
+        assert_eq!(&bytes[..16], &[0x00, 0x10, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(&bytes[16..32], &[0x00, 0x10, 0, 0, 0, 0, 0, 0, 0x00, 0x06, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(bytes.len(), 16 + 16 + 0x600 + 16 + 0x200);
+        let decoded = Memory::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.base_address, 0x1000);
+        assert_eq!(decoded.read::<u32>(0x1004), 0xDEAD_BEEF);
+        assert_eq!(decoded.read::<u64>(0x13FC), 0x0102_0304_0506_0708);
+        assert_eq!(decoded.read::<u8>(0x8000), 0x7F);
+        assert!(!decoded.is_mapped(0x1600));
+        assert_eq!(decoded.regions().collect::<Vec<_>>(), memory.regions().collect::<Vec<_>>());
+        assert_eq!(decoded.to_bytes(), bytes);
+        // malformed input is rejected
+        assert_eq!(Memory::from_bytes(&bytes[..bytes.len() - 1]).err(), Some(DeserializeError::Truncated { offset: 16 + 16 + 0x600 + 16 }));
+        assert_eq!(Memory::from_bytes(&bytes[..12]).err(), Some(DeserializeError::Truncated { offset: 8 }));
+        let mut extra = bytes.clone();
+        extra.push(0);
+        assert_eq!(Memory::from_bytes(&extra).err(), Some(DeserializeError::InvalidLength { expected: bytes.len(), actual: bytes.len() + 1 }));
+        let mut overlapping = bytes.clone();
+        overlapping[16 + 16 + 0x600..16 + 16 + 0x600 + 8].copy_from_slice(&0x1500u64.to_le_bytes());
+        assert_eq!(Memory::from_bytes(&overlapping).err(), Some(DeserializeError::InvalidSegment { address: 0x1500 }));
+        let mut huge = bytes.clone();
+        huge[24..32].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert_eq!(Memory::from_bytes(&huge).err(), Some(DeserializeError::InvalidSegment { address: 0x1000 }));
+        assert_eq!(Memory::from_bytes(&Memory::new(0).to_bytes()).unwrap().mapped_size(), 0);
+    }
+
+    #[test]
+    fn test_memory_dump() {
+        let mut memory = Memory::new(0x10000);
+        memory.write::<u64>(0x10008, 0x1122_3344_5566_7788).unwrap();
+        memory.write::<u16>(0x1_0000_0000, 0xABCD).unwrap();
+        let dump = memory.export_dump();
+        assert_eq!(&dump[..8], b"CPULIBMD");
+        assert_eq!(&dump[8..12], &[1, 0, 0, 0]);
+        assert_eq!(&dump[12..20], &0x10000u64.to_le_bytes());
+        assert_eq!(&dump[20..28], &0x10000u64.to_le_bytes());
+        assert_eq!(dump.len(), 20 + 2 * 16 + 2 * 0x200);
+        let imported = Memory::import_dump(&dump).unwrap();
+        assert_eq!(imported.base_address, 0x10000);
+        assert_eq!(imported.read::<u64>(0x10008), 0x1122_3344_5566_7788);
+        assert_eq!(imported.read::<u16>(0x1_0000_0000), 0xABCD);
+        assert_eq!(imported.export_dump(), dump);
+        // corrupt or truncated dumps are rejected
+        let mut bad_magic = dump.clone();
+        bad_magic[0] = b'X';
+        assert_eq!(Memory::import_dump(&bad_magic).err(), Some(MemoryError::InvalidDump { offset: 0 }));
+        let mut bad_version = dump.clone();
+        bad_version[8] = 2;
+        assert_eq!(Memory::import_dump(&bad_version).err(), Some(MemoryError::InvalidDump { offset: 8 }));
+        assert_eq!(Memory::import_dump(&dump[..4]).err(), Some(MemoryError::InvalidDump { offset: 0 }));
+        assert_eq!(Memory::import_dump(&dump[..30]).err(), Some(MemoryError::InvalidDump { offset: 28 }));
+        assert_eq!(Memory::import_dump(&dump[..dump.len() - 1]).err(), Some(MemoryError::InvalidDump { offset: 20 + 16 + 0x200 + 16 }));
+        let mut overlapping = dump.clone();
+        let second = 20 + 16 + 0x200;
+        overlapping[second..second + 8].copy_from_slice(&0x10100u64.to_le_bytes());
+        assert_eq!(Memory::import_dump(&overlapping).err(), Some(MemoryError::Overlap { address: 0x10100 }));
+        let mut below_base = dump.clone();
+        below_base[second..second + 8].copy_from_slice(&0x100u64.to_le_bytes());
+        assert_eq!(Memory::import_dump(&below_base).err(), Some(MemoryError::InvalidDump { offset: second }));
+    }
+
+    #[test]
+    fn test_simd_raw_bytes() {
+        let mut registers = Registers::new();
+        let mut zmm = [0u8; 64];
+        for (i, byte) in zmm.iter_mut().enumerate() {
+            *byte = i as u8 + 1;
+        }
+        registers.set_zmm_bytes(5, zmm);
+        assert_eq!(registers.get_zmm_bytes(5), zmm);
+        assert_eq!(registers.get_by_sections::<u64>(VecRegName::ZMM, 5).unwrap()[0], 0x0807_0605_0403_0201);
+        assert_eq!(registers.get_bit(VecRegName::ZMM, 5, 0), Some(true));
+        assert_eq!(registers.get_bit(VecRegName::ZMM, 5, 1), Some(false));
+        assert_eq!(registers.get_ymm_bytes(5)[..], zmm[..32]);
+        assert_eq!(registers.get_xmm_bytes(5)[..], zmm[..16]);
+        // narrower writes clear the upper bits
+        registers.set_ymm_bytes(5, [0xAA; 32]);
+        assert_eq!(registers.get_zmm_bytes(5)[31..33], [0xAA, 0x00]);
+        registers.set_xmm_bytes(5, [0x55; 16]);
+        assert_eq!(registers.get_ymm_bytes(5)[15..17], [0x55, 0x00]);
+        // inaccessible registers read as zeros and ignore writes
+        registers.set_zmm_bytes(8, zmm);
+        registers.set_mode(CPUMode::Mode32);
+        assert_eq!(registers.get_xmm_bytes(8), [0; 16]);
+        registers.set_zmm_bytes(8, [0xFF; 64]);
+        registers.set_mode(CPUMode::Mode64);
+        assert_eq!(registers.get_zmm_bytes(8), zmm);
+        assert_eq!(registers.get_zmm_bytes(32), [0; 64]);
+    }
+
+    #[test]
+    fn test_memory_hexdump() {
+        let mut memory = Memory::new(0);
+        memory.map(0x1000..0x1010).unwrap();
+        memory.write_bytes(0x1000, b"Hello, world!\n\x00\x7f").unwrap();
+        assert_eq!(
+            memory.hexdump(0x1008, 12),
+            "00001008  6f 72 6c 64 21 0a 00 7f  ?? ?? ?? ??              |orld!.......|\n00001014\n"
+        );
+        assert_eq!(
+            memory.hexdump_all(),
+            "00001000  48 65 6c 6c 6f 2c 20 77  6f 72 6c 64 21 0a 00 7f  |Hello, world!...|\n00001010\n"
+        );
+        assert_eq!(memory.hexdump(0x2000, 0), "00002000\n");
+    }
+
+    #[test]
+    fn test_registers_format_table() {
+        let mut registers = Registers::new();
+        let table = registers.format_table();
+        assert_eq!(table.lines().count(), 16 + 1 + 32 + 1 + 2);
+        assert!(table.starts_with("RAX: 0x0000000000000000\nRBX: 0x0000000000000000\n"));
+        assert!(table.contains("\nR15: 0x0000000000000000\n\nZMM0: 0x00000000000000000000000000000000 "));
+        assert!(table.ends_with("RFLAGS: 0x0000000000000000 [ ]\nRIP: 0x0000000000000000\n"));
+        assert!(table.lines().filter(|line| !line.is_empty())
+            .all(|line| line.split(": ").nth(1).unwrap().chars().all(|c| "0x [ ]".contains(c))));
+        registers.set_gpr_value(GPRName::RSP, 0x7fff_0000);
+        registers.set_by_sections::<u64>(VecRegName::XMM, 1, vec![0x1122, 0x3344]);
+        registers.set_flags_value(FLAGSName::RFLAGS, 0x246);
+        registers.set_ip_value(IPName::RIP, 0x40_1000);
+        let table = registers.format_table();
+        assert!(table.contains("RSP: 0x000000007fff0000\n"));
+        let zero = format!("0x{:032x}", 0);
+        assert!(table.contains(&format!("ZMM1: {zero} {zero} {zero} 0x00000000000033440000000000001122\n")));
+        assert!(table.contains("RFLAGS: 0x0000000000000246 [ PF ZF IF ]\nRIP: 0x0000000000401000\n"));
+    }
+
+    #[test]
+    fn test_sign_extend_typed() {
+        assert_eq!(Utilities::sext8_16(0x80), 0xFF80);
+        assert_eq!(Utilities::sext8_16(0x7F), 0x007F);
+        assert_eq!(Utilities::sext8_32(0xFE), 0xFFFF_FFFE);
+        assert_eq!(Utilities::sext8_32(0x01), 0x0000_0001);
+        assert_eq!(Utilities::sext8_64(0xFF), u64::MAX);
+        assert_eq!(Utilities::sext8_64(0x40), 0x40);
+        assert_eq!(Utilities::sext16_32(0x8000), 0xFFFF_8000);
+        assert_eq!(Utilities::sext16_32(0x7FFF), 0x0000_7FFF);
+        assert_eq!(Utilities::sext16_64(0xC000), 0xFFFF_FFFF_FFFF_C000);
+        assert_eq!(Utilities::sext16_64(0x1234), 0x1234);
+        assert_eq!(Utilities::sext32_64(0x8000_0000), 0xFFFF_FFFF_8000_0000);
+        assert_eq!(Utilities::sext32_64(0x7FFF_FFFF), 0x7FFF_FFFF);
+        // odd widths such as a 20-bit displacement
+        assert_eq!(Utilities::sign_extend(0x8_0000, 20), -0x8_0000);
+        assert_eq!(Utilities::sign_extend(0xF_FFFF, 20), -1);
+        assert_eq!(Utilities::sign_extend(0x7_FFFF, 20), 0x7_FFFF);
+        assert_eq!(Utilities::sign_extend(0x14, 5), -12);
+        assert_eq!(Utilities::zero_extend(0xFFF_FFFF, 20), 0xF_FFFF);
+        assert_eq!(Utilities::zero_extend(0x3F, 5), 0x1F);
+        assert_eq!(Utilities::sign_extend(0x8000_0000_0000_0000, 64) as u64, 0x8000_0000_0000_0000);
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn test_tracing_events() {
+        use std::sync::{Arc, Mutex};
+        use tracing::field::{Field, Visit};
+        use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+
+        struct FieldRecorder(String);
+
+        impl Visit for FieldRecorder {
+            fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+                self.0 += &format!("{}={:?} ", field.name(), value);
+            }
+        }
+
+        struct Capture(Arc<Mutex<Vec<String>>>);
+
+        impl<S: tracing::Subscriber> Layer<S> for Capture {
+            fn on_event(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+                let mut recorder = FieldRecorder(String::new());
+                event.record(&mut recorder);
+                self.0.lock().unwrap().push(recorder.0);
+            }
+        }
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let subscriber = tracing_subscriber::registry().with(Capture(events.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            let mut cpu = CPU::new(0);
+            cpu.step(Instruction::Nop).unwrap();
+            cpu.memory.write::<u32>(0x1000, 0xDEAD_BEEF).unwrap();
+            assert_eq!(cpu.memory.read::<u32>(0x1000), 0xDEAD_BEEF);
+            cpu.registers.set_gpr_value(GPRName::RCX, 42);
+            cpu.registers.set_by_sections::<u32>(VecRegName::XMM, 3, vec![1, 2, 3, 4]);
+        });
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 5);
+        assert!(events[0].starts_with("function=\"CPU::step\" instruction=Nop rip=0 "));
+        assert!(events[1].starts_with("function=\"Memory::write\" address=4096 size=4 value=[239, 190, 173, 222] "));
+        assert!(events[2].starts_with("function=\"Memory::read\" address=4096 size=4 value=[239, 190, 173, 222] "));
+        assert!(events[3].starts_with("function=\"Registers::set_gpr_value\" register=RCX value=42 "));
+        assert!(events[4].starts_with("function=\"Registers::set_by_sections\" register=XMM index=3 lanes=4 lane_bits=32 "));
+    }
+
+    #[test]
+    fn test_flags_after_arithmetic() {
+        let flags = |cf, pf, af, zf, sf, of| FlagsDelta { cf, pf, af, zf, sf, of };
+        let cases = [
+            // (a, b, carry, width, add flags, sub flags)
+            (0x7F, 0x01, false, 8, flags(false, false, true, false, true, true), flags(false, true, false, false, false, false)),
+            (0xFF, 0x01, false, 8, flags(true, true, true, true, false, false), flags(false, false, false, false, true, false)),
+            (0x80, 0x80, false, 8, flags(true, true, false, true, false, true), flags(false, true, false, true, false, false)),
+            (0x00, 0x01, false, 8, flags(false, false, false, false, false, false), flags(true, true, true, false, true, false)),
+            (0x80, 0x01, false, 8, flags(false, true, false, false, true, false), flags(false, false, true, false, false, true)),
+            (0x00, 0x00, true, 8, flags(false, false, false, false, false, false), flags(true, true, true, false, true, false)),
+            (0x7FFF, 0x0001, false, 16, flags(false, true, true, false, true, true), flags(false, false, false, false, false, false)),
+            (0x8000, 0xFFFF, true, 16, flags(true, true, true, false, true, false), flags(true, true, true, false, true, false)),
+            (0x7FFF_FFFF, 0x7FFF_FFFF, true, 32, flags(false, true, true, false, true, true), flags(true, true, true, false, true, false)),
+            (0xFFFF_FFFF, 0x0000_0001, false, 32, flags(true, true, true, true, false, false), flags(false, false, false, false, true, false)),
+            (u64::MAX, 1, false, 64, flags(true, true, true, true, false, false), flags(false, false, false, false, true, false)),
+            (1 << 63, 1, false, 64, flags(false, false, false, false, true, false), flags(false, true, true, false, false, true)),
+            // bits above the operand width are ignored
+            (0x1_00, 0x1_00, false, 8, flags(false, true, false, true, false, false), flags(false, true, false, true, false, false)),
+        ];
+        for (a, b, carry, width, add, sub) in cases {
+            assert_eq!(Utilities::flags_after_add(a, b, carry, width), add, "add {:#x} {:#x} {} {}", a, b, carry, width);
+            assert_eq!(Utilities::flags_after_sub(a, b, carry, width), sub, "sub {:#x} {:#x} {} {}", a, b, carry, width);
+        }
+        assert_eq!(Utilities::flags_after_logic(0, 32), flags(false, true, false, true, false, false));
+        assert_eq!(Utilities::flags_after_logic(0x8000_0003, 32), flags(false, true, false, false, true, false));
+        assert_eq!(Utilities::flags_after_logic(0x1_0001, 16), flags(false, false, false, false, false, false));
+
+        let mut registers = Registers::new();
+        registers.set_flags_value(FLAGSName::RFLAGS, 0x0402 | 0x0001);
+        Utilities::flags_after_add(0x7F, 0x01, false, 8).apply_to(&mut registers);
+        assert_eq!(registers.get_flags_value(FLAGSName::RFLAGS), 0x0402 | 0x0010 | 0x0080 | 0x0800);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_flags_after_add_invalid_width() {
+        Utilities::flags_after_add(1, 1, false, 12);
+    }
+
+    #[cfg(feature = "proptest")]
+    proptest::proptest! {
+        #[test]
+        fn proptest_registers_round_trip(registers in proptest::prelude::any::<Registers>()) {
+            let bytes = registers.to_bytes();
+            proptest::prop_assert_eq!(registers.get_flags_value(FLAGSName::RFLAGS) & !0xFD5, 0x2);
+            proptest::prop_assert_eq!(Registers::from_bytes(&bytes).unwrap().to_bytes(), bytes);
+        }
+
+        #[test]
+        fn proptest_registers_modification(registers in proptest::prelude::any::<Registers>(), index in 0..16usize, value: u64) {
+            let name = registers::GPR64_NAMES[index];
+            let mut modified = Registers::from_bytes(&registers.to_bytes()).unwrap();
+            proptest::prop_assert!(registers.diff(&modified).is_empty());
+            modified.set_gpr_value(name, value);
+            let expected = if registers.get_gpr_value(name) == value { vec![] } else { vec![RegisterRef::Gpr(name)] };
+            proptest::prop_assert_eq!(registers.diff(&modified), expected.clone());
+            proptest::prop_assert_eq!(modified.diff(&registers), expected);
+        }
+
+        #[test]
+        fn proptest_memory_round_trip(memory in proptest::prelude::any::<Memory>()) {
+            let bytes = memory.to_bytes();
+            let restored = Memory::from_bytes(&bytes).unwrap();
+            proptest::prop_assert_eq!(restored.regions().collect::<Vec<_>>(), memory.regions().collect::<Vec<_>>());
+            proptest::prop_assert_eq!(restored.to_bytes(), bytes);
+            proptest::prop_assert!(memory.diff(&restored).is_empty());
+        }
+
+        #[test]
+        fn proptest_memory_modification(memory in proptest::prelude::any::<Memory>(), offset: u64, value: u8) {
+            let mut modified = memory.fork();
+            let regions: Vec<Region> = memory.regions().collect();
+            proptest::prop_assume!(!regions.is_empty());
+            let region = &regions[(offset % regions.len() as u64) as usize];
+            let address = region.range.start + offset % (region.range.end - region.range.start);
+            modified.write::<u8>(address, value).unwrap();
+            proptest::prop_assert_eq!(modified.regions().collect::<Vec<_>>(), regions.clone());
+            let byte = address..address + 1;
+            let expected = if memory.read::<u8>(address) == value { vec![] } else { vec![byte.clone()] };
+            proptest::prop_assert_eq!(memory.diff(&modified), expected.clone());
+            proptest::prop_assert_eq!(modified.diff(&memory), expected);
+            // unmapping the byte is a difference whatever its value was
+            modified.unmap_range(byte.clone());
+            proptest::prop_assert_eq!(memory.diff(&modified), vec![byte]);
+        }
+    }
+
+    #[test]
+    fn test_saturate() {
+        assert_eq!(Utilities::saturate_i16_to_i8(128), 127);
+        assert_eq!(Utilities::saturate_i16_to_i8(127), 127);
+        assert_eq!(Utilities::saturate_i16_to_i8(-128), -128);
+        assert_eq!(Utilities::saturate_i16_to_i8(-129), -128);
+        assert_eq!(Utilities::saturate_i16_to_u8(256), 255);
+        assert_eq!(Utilities::saturate_i16_to_u8(255), 255);
+        assert_eq!(Utilities::saturate_i16_to_u8(-1), 0);
+        assert_eq!(Utilities::saturate_i32_to_i16(32768), 32767);
+        assert_eq!(Utilities::saturate_i32_to_i16(-32769), -32768);
+        assert_eq!(Utilities::saturate_i32_to_i16(-32768), -32768);
+        assert_eq!(Utilities::saturate_i32_to_u16(65536), 65535);
+        assert_eq!(Utilities::saturate_i32_to_u16(i32::MIN), 0);
+        assert_eq!(Utilities::saturate_i16_to_i8_vec(&[300, -300, 5]), vec![127, -128, 5]);
+        assert_eq!(Utilities::saturate_i16_to_u8_vec(&[300, -300, 5]), vec![255, 0, 5]);
+        assert_eq!(Utilities::saturate_i32_to_i16_vec(&[70000, -70000, -5]), vec![32767, -32768, -5]);
+        assert_eq!(Utilities::saturate_i32_to_u16_vec(&[70000, -70000, 5]), vec![65535, 0, 5]);
+        assert_eq!(Utilities::saturate_signed(128, 8), 127);
+        assert_eq!(Utilities::saturate_signed(-129, 8), -128);
+        assert_eq!(Utilities::saturate_signed(1 << 40, 32), i32::MAX as i64);
+        assert_eq!(Utilities::saturate_signed(-2, 1), -1);
+        assert_eq!(Utilities::saturate_signed(i64::MIN, 64), i64::MIN);
+        assert_eq!(Utilities::saturate_unsigned(256, 8), 255);
+        assert_eq!(Utilities::saturate_unsigned(-1, 16), 0);
+        assert_eq!(Utilities::saturate_unsigned(0x10_0000, 20), 0xF_FFFF);
+        assert_eq!(Utilities::saturate_unsigned(i64::MAX, 64), i64::MAX as u64);
+    }
+
+    #[test]
+    fn test_registers_swap() {
+        let mut registers = Registers::new();
+        registers.set_by_sections::<u64>(VecRegName::ZMM, 1, (1..=8).collect());
+        registers.set_by_sections::<u64>(VecRegName::ZMM, 20, (11..=18).collect());
+        registers.swap_simd(1, 20);
+        assert_eq!(registers.get_by_sections::<u64>(VecRegName::ZMM, 1), Some((11..=18).collect()));
+        assert_eq!(registers.get_by_sections::<u64>(VecRegName::ZMM, 20), Some((1..=8).collect()));
+        registers.swap_simd(1, 1);
+        assert_eq!(registers.get_by_sections::<u64>(VecRegName::ZMM, 1), Some((11..=18).collect()));
+        registers.set_mode(CPUMode::Mode32);
+        registers.swap_simd(1, 20);
+        registers.set_mode(CPUMode::Mode64);
+        assert_eq!(registers.get_by_sections::<u64>(VecRegName::ZMM, 1), Some((11..=18).collect()));
+
+        registers.set_gpr_value(GPRName::RAX, 0x1111_2222_3333_4444);
+        registers.set_gpr_value(GPRName::R9, 0x5555_6666_7777_8888);
+        registers.swap_gpr(GPRName::RAX, GPRName::R9);
+        assert_eq!(registers.get_gpr_value(GPRName::RAX), 0x5555_6666_7777_8888);
+        assert_eq!(registers.get_gpr_value(GPRName::R9), 0x1111_2222_3333_4444);
+        registers.swap_gpr(GPRName::R9, GPRName::R9);
+        assert_eq!(registers.get_gpr_value(GPRName::R9), 0x1111_2222_3333_4444);
+        registers.swap_gpr(GPRName::AL, GPRName::AH);
+        assert_eq!(registers.get_gpr_value(GPRName::RAX), 0x5555_6666_7777_8888);
+        registers.swap_gpr(GPRName::AX, GPRName::R9W);
+        assert_eq!(registers.get_gpr_value(GPRName::RAX), 0x5555_6666_7777_4444);
+        assert_eq!(registers.get_gpr_value(GPRName::R9), 0x1111_2222_3333_8888);
+    }
+
+    #[test]
+    fn test_parse_register() {
+        for gpr in GPRName::ALL {
+            let name = gpr.to_string();
+            assert_eq!(Utilities::parse_register(&name, 32), Some(RegisterRef::Gpr(gpr)));
+            assert_eq!(Utilities::parse_register(&name.to_lowercase(), 8), Some(RegisterRef::Gpr(gpr)));
+        }
+        assert_eq!(Utilities::parse_register("xmm0", 32), Some(RegisterRef::Vec(VecRegName::XMM, 0)));
+        assert_eq!(Utilities::parse_register("Ymm15", 32), Some(RegisterRef::Vec(VecRegName::YMM, 15)));
+        assert_eq!(Utilities::parse_register("ZMM31", 32), Some(RegisterRef::Vec(VecRegName::ZMM, 31)));
+        assert_eq!(Utilities::parse_register("xmm7", 8), Some(RegisterRef::Vec(VecRegName::XMM, 7)));
+        assert_eq!(Utilities::parse_register("eflags", 32), Some(RegisterRef::Flags(FLAGSName::EFLAGS)));
+        assert_eq!(Utilities::parse_register("rip", 32), Some(RegisterRef::Ip(IPName::RIP)));
+        assert_eq!(Utilities::parse_register("k7", 32), Some(RegisterRef::KMask(KMaskName::K7)));
+        for name in ["xmm8", "zmm31", "xmm", "xmm01", "xmm+1", "rax ", "k8", "r16", ""] {
+            assert_eq!(Utilities::parse_register(name, 8), None, "{}", name);
+        }
+        assert_eq!(Utilities::parse_register("zmm32", 32), None);
+        // a 16-register configuration, such as SSE in 64-bit mode
+        assert_eq!(Utilities::parse_register("xmm15", 16), Some(RegisterRef::Vec(VecRegName::XMM, 15)));
+        assert_eq!(Utilities::parse_register("xmm16", 16), None);
+        assert_eq!(Utilities::parse_register("ymm31", 16), None);
+        assert_eq!(Utilities::parse_register("xmm8", CPUMode::Mode32.simd_register_count()), None);
+    }
+
+    #[test]
+    fn test_memory_memmove() {
+        let mut memory = Memory::new(0);
+        let pattern: Vec<u8> = (0..=255).collect();
+        // destination inside the source range: must copy backwards
+        memory.write_bytes(0x1000, &pattern).unwrap();
+        memory.memmove(0x1010, 0x1000, 0x100).unwrap();
+        assert_eq!(memory.read_vec::<u8>(0x1000, 0x10), pattern[..0x10]);
+        assert_eq!(memory.read_vec::<u8>(0x1010, 0x100), pattern);
+        // source inside the destination range: must copy forwards
+        memory.write_bytes(0x2010, &pattern).unwrap();
+        memory.memmove(0x2000, 0x2010, 0x100).unwrap();
+        assert_eq!(memory.read_vec::<u8>(0x2000, 0x100), pattern);
+        assert_eq!(memory.read_vec::<u8>(0x2100, 0x10), pattern[0xF0..]);
+        // overlapping by a single byte
+        memory.write_bytes(0x3000, &[1, 2, 3, 4]).unwrap();
+        memory.memmove(0x3001, 0x3000, 4).unwrap();
+        assert_eq!(memory.read_vec::<u8>(0x3000, 5), vec![1, 1, 2, 3, 4]);
+        memory.memmove(0x3000, 0x3001, 4).unwrap();
+        assert_eq!(memory.read_vec::<u8>(0x3000, 5), vec![1, 2, 3, 4, 4]);
+        // a failed write leaves the destination untouched
+        memory.protect(0x3002..0x3004, Protection::ReadOnly);
+        assert!(memory.memmove(0x3000, 0x2000, 4).is_err());
+        assert_eq!(memory.read_vec::<u8>(0x3000, 5), vec![1, 2, 3, 4, 4]);
+    }
+
+    #[test]
+    fn test_hex_formatting() {
+        assert_eq!(Utilities::to_hex_u256(&u256::from(0xFFu64)), format!("0x{}ff", "0".repeat(62)));
+        assert_eq!(Utilities::to_hex_u256(&u256::zero()), format!("0x{}", "0".repeat(64)));
+        assert_eq!(Utilities::to_hex_u512(&(u512::one() << 300)), format!("0x{}1{}", "0".repeat(52), "0".repeat(75)));
+        assert_eq!(Utilities::to_hex_u512(&u512::MAX), format!("0x{}", "f".repeat(128)));
+
+        let value = u256([0x0123_4567_89AB_CDEF, 0, 0xFFFF, 1 << 63]);
+        assert_eq!(Utilities::parse_hex_u256(&Utilities::to_hex_u256(&value)), Ok(value));
+        assert_eq!(Utilities::parse_hex_u256("0xDEAD_beef"), Ok(u256::from(0xDEAD_BEEFu64)));
+        assert_eq!(Utilities::parse_hex_u256("1_0000_0000_0000_0000"), Ok(u256::one() << 64));
+        assert_eq!(Utilities::parse_hex_u256(&format!("0X{}{}", "0".repeat(70), "f".repeat(64))), Ok(u256::MAX));
+        assert_eq!(Utilities::parse_hex_u256(&format!("1{}", "0".repeat(64))), Err(ParseHexError::Overflow));
+        assert_eq!(Utilities::parse_hex_u256("0x"), Err(ParseHexError::Empty));
+        assert_eq!(Utilities::parse_hex_u256("__"), Err(ParseHexError::Empty));
+        assert_eq!(Utilities::parse_hex_u256("0x12g4"), Err(ParseHexError::InvalidDigit('g')));
+        assert_eq!(Utilities::parse_hex_u512(&format!("{}_1", "f".repeat(100))), Ok((u512::one() << 404) - 15));
+
+        assert_eq!(Utilities::lanes_to_hex(&[1u16, 0xABC], "0x", " "), "0x0001 0x0abc");
+        assert_eq!(Utilities::lanes_to_hex(&[0xDEADu32, 0], "", "_"), "0000dead_00000000");
+        assert_eq!(Utilities::lanes_to_hex(&[7u8, 0x80, 0xFF], "", ""), "0780ff");
+        assert_eq!(Utilities::lanes_to_hex::<u64>(&[], "0x", " "), "");
+    }
+
+    #[test]
+    fn test_memory_mapped_regions() {
+        let mut memory = Memory::new(0);
+        assert_eq!(memory.mapped_regions(), vec![]);
+        assert_eq!(memory.lowest_mapped_address(), None);
+        assert_eq!(memory.highest_mapped_address(), None);
+        memory.map(0x3000..0x3100).unwrap();
+        memory.map(0x1000..0x1200).unwrap();
+        memory.map(0x1200..0x1400).unwrap();
+        let device = Box::new(CountingDevice { reads: Rc::new(Cell::new(0)), writes: Rc::new(RefCell::new(Vec::new())) });
+        memory.map_mmio(0x3100..0x3104, device).unwrap();
+        memory.map_guard(0x2000..0x3000).unwrap();
+        assert_eq!(memory.mapped_regions(), vec![0x1000..0x1400, 0x3000..0x3104]);
+        assert_eq!(memory.lowest_mapped_address(), Some(0x1000));
+        assert_eq!(memory.highest_mapped_address(), Some(0x3103));
+    }
+
+    #[test]
+    fn test_wide_integer_lanes() {
+        let mut registers = Registers::new();
+        let lanes: [u64; 8] = std::array::from_fn(|i| 0x1111_1111_1111_1111 * i as u64 + 0x0102_0304);
+        registers.set_by_sections::<u64>(VecRegName::ZMM, 2, lanes.to_vec());
+        let zmm = registers.get_by_sections::<u512>(VecRegName::ZMM, 2).unwrap()[0];
+        assert_eq!(Utilities::u512_to_u64_lanes(zmm), lanes);
+        assert_eq!(Utilities::u64_lanes_to_u512(lanes), zmm);
+        assert_eq!(Utilities::u512_to_u128_lanes(zmm).to_vec(), registers.get_by_sections::<u128>(VecRegName::ZMM, 2).unwrap());
+        let dwords = registers.get_by_sections::<u32>(VecRegName::ZMM, 2).unwrap();
+        assert_eq!(Utilities::u512_to_f32_lanes(zmm).map(f32::to_bits).to_vec(), dwords);
+        assert_eq!(Utilities::u512_to_f64_lanes(zmm).map(f64::to_bits), lanes);
+
+        let ymm = registers.get_by_sections::<u256>(VecRegName::YMM, 2).unwrap()[0];
+        assert_eq!(Utilities::u256_to_u64_lanes(ymm).to_vec(), lanes[..4]);
+        assert_eq!(Utilities::u256_to_u128_lanes(ymm).to_vec(), registers.get_by_sections::<u128>(VecRegName::YMM, 2).unwrap());
+        assert_eq!(Utilities::u256_to_f32_lanes(ymm).map(f32::to_bits).to_vec(), dwords[..8]);
+
+        let floats = [1.5f32, -2.0, 0.25, f32::MAX, 0.0, -0.0, 3.0, 1e-3];
+        let value = Utilities::f32_lanes_to_u256(floats);
+        assert_eq!(Utilities::u256_to_f32_lanes(value), floats);
+        assert_eq!(value.0[0], 0xC000_0000_3FC0_0000);
+        let doubles = [1.0f64, -1.0, 0.5, 2.0];
+        assert_eq!(Utilities::u256_to_f64_lanes(Utilities::f64_lanes_to_u256(doubles)), doubles);
+        let wide = [u128::MAX, 1, 2, 3];
+        assert_eq!(Utilities::u512_to_u128_lanes(Utilities::u128_lanes_to_u512(wide)), wide);
+        assert_eq!(Utilities::u128_lanes_to_u256([1, 2]), (u256::from(2u64) << 128) + 1);
+        assert_eq!(Utilities::u64_lanes_to_u256([1, 0, 0, 1]), (u256::one() << 192) + 1);
+        let halves: [f64; 8] = std::array::from_fn(|i| i as f64 / 2.0);
+        assert_eq!(Utilities::u512_to_f64_lanes(Utilities::f64_lanes_to_u512(halves)), halves);
+        let singles: [f32; 16] = std::array::from_fn(|i| -(i as f32));
+        assert_eq!(Utilities::u512_to_f32_lanes(Utilities::f32_lanes_to_u512(singles)), singles);
+    }
+
+    #[test]
+    fn test_bswap() {
+        assert_eq!(Utilities::bswap16(0x1234), 0x3412);
+        assert_eq!(Utilities::bswap32(0x1234_5678), 0x7856_3412);
+        assert_eq!(Utilities::bswap64(0x0102_0304_0506_0708), 0x0807_0605_0403_0201);
+        assert_eq!(Utilities::bswap128(0x0102_0304_0506_0708_090A_0B0C_0D0E_0F10), 0x100F_0E0D_0C0B_0A09_0807_0605_0403_0201);
+        let wide = u256([0x0102_0304_0506_0708, 0, 0, 0xAABB]);
+        assert_eq!(Utilities::bswap_u256(wide), u256([0xBBAA_0000_0000_0000, 0, 0, 0x0807_0605_0403_0201]));
+        assert_eq!(Utilities::bswap_u256(Utilities::bswap_u256(wide)), wide);
+        let wider = u512([1, 2, 3, 4, 5, 6, 7, 0x0102_0304_0506_0708]);
+        assert_eq!(Utilities::bswap_u512(wider).0[0], 0x0807_0605_0403_0201);
+        assert_eq!(Utilities::bswap_u512(wider).0[7], 1 << 56);
+        assert_eq!(Utilities::bswap_u512(Utilities::bswap_u512(wider)), wider);
+        for v in [0u64, 1, u64::MAX, 0xDEAD_BEEF_0BAD_F00D] {
+            assert_eq!(Utilities::bswap64(Utilities::bswap64(v)), v);
+            assert_eq!(Utilities::bswap32(Utilities::bswap32(v as u32)), v as u32);
+            assert_eq!(Utilities::bswap16(Utilities::bswap16(v as u16)), v as u16);
+        }
+        assert_eq!(Utilities::bswap_lanes(vec![0x1122u16, 0x3344]), vec![0x2211, 0x4433]);
+        assert_eq!(Utilities::bswap_lanes(vec![0x0102_0304_0506_0708u64]), vec![0x0807_0605_0403_0201]);
+        assert_eq!(Utilities::bswap_lanes(vec![wide]), vec![Utilities::bswap_u256(wide)]);
+    }
+
+    #[test]
+    fn test_default_registers_and_memory() {
+        let registers = Registers::default();
+        assert_eq!(registers.to_bytes(), Registers::new().to_bytes());
+        assert_eq!(registers.mode(), CPUMode::Mode64);
+        assert_eq!(registers.get_by_sections::<u64>(VecRegName::ZMM, 31), Some(vec![0; 8]));
+        let mut memory = Memory::default();
+        assert_eq!(memory.base_address, 0);
+        assert_eq!(memory.mapped_regions(), vec![]);
+        memory.write::<u32>(0x10, 7).unwrap();
+        assert_eq!(memory.read::<u32>(0x10), 7);
+    }
+
+    #[test]
+    fn test_vec_reg_size_and_lanes() {
+        assert_eq!(Utilities::get_vec_reg_size(&VecRegName::XMM), 128);
+        assert_eq!(Utilities::get_vec_reg_size(&VecRegName::YMM), 256);
+        assert_eq!(Utilities::get_vec_reg_size(&VecRegName::ZMM), 512);
+        let widths = [
+            Utilities::element_bits::<u8>(), Utilities::element_bits::<u16>(), Utilities::element_bits::<u32>(),
+            Utilities::element_bits::<u64>(), Utilities::element_bits::<u128>(), Utilities::element_bits::<u256>(),
+            Utilities::element_bits::<u512>(),
+        ];
+        assert_eq!(widths, [8, 16, 32, 64, 128, 256, 512]);
+        let expected = [
+            (VecRegName::XMM, [16, 8, 4, 2, 1, 0, 0]),
+            (VecRegName::YMM, [32, 16, 8, 4, 2, 1, 0]),
+            (VecRegName::ZMM, [64, 32, 16, 8, 4, 2, 1]),
+        ];
+        for (reg, lanes) in expected {
+            for (bits, count) in widths.iter().zip(lanes) {
+                assert_eq!(Utilities::lane_count(&reg, *bits), count, "{} with {}-bit elements", reg, bits);
+            }
+            assert_eq!(reg.lane_count::<u32>(), Utilities::lane_count(&reg, 32));
+        }
+        let mut registers = Registers::new();
+        assert!(registers.set_by_sections::<u256>(VecRegName::YMM, 0, vec![u256::MAX]));
+        assert!(!registers.set_by_sections::<u256>(VecRegName::XMM, 0, vec![u256::MAX]));
+        assert!(!registers.set_by_sections::<u512>(VecRegName::ZMM, 0, vec![u512::MAX; 2]));
+        assert_eq!(registers.get_by_sections::<u512>(VecRegName::ZMM, 0), Some(vec![u512::from(u256::MAX)]));
+        assert_eq!(registers.get_by_sections::<u256>(VecRegName::XMM, 0), Some(vec![]));
+    }
+
+    #[test]
+    fn test_cpu_full_multiply() {
+        let mut cpu = CPU::new(0);
+        let flags = |cpu: &CPU| cpu.registers.get_flags_value(FLAGSName::RFLAGS) & 0x801;
+        cpu.registers.set_gpr_value(GPRName::RAX, 0xFFFF_FFFF_FFFF_FFFF);
+        cpu.registers.set_gpr_value(GPRName::RBX, 0x1_0000_0001);
+        assert_eq!(cpu.mulq_full(GPRName::RBX), 0xFFFF_FFFF_FFFF_FFFF * 0x1_0000_0001);
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::RDX), 0x0000_0001_0000_0000);
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::RAX), 0xFFFF_FFFE_FFFF_FFFF);
+        assert_eq!(flags(&cpu), 0x801);
+        cpu.registers.set_gpr_value(GPRName::RAX, 3);
+        assert_eq!(cpu.mulq_full(GPRName::RBX), 0x3_0000_0003);
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::RDX), 0);
+        assert_eq!(flags(&cpu), 0);
+
+        // -2^62 * 4 = -2^64 needs the high half
+        cpu.registers.set_gpr_i64(GPRName::RAX, -(1 << 62));
+        cpu.registers.set_gpr_value(GPRName::RCX, 4);
+        assert_eq!(cpu.imulq_full(GPRName::RCX), -(1 << 64));
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::RDX), u64::MAX);
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::RAX), 0);
+        assert_eq!(flags(&cpu), 0x801);
+        // a negative product fitting in 64 bits sign-extends into RDX without overflow
+        cpu.registers.set_gpr_i64(GPRName::RAX, -3);
+        assert_eq!(cpu.imulq_full(GPRName::RCX), -12);
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::RDX), u64::MAX);
+        assert_eq!(cpu.registers.get_gpr_as_i64(GPRName::RAX), -12);
+        assert_eq!(flags(&cpu), 0);
+        cpu.registers.set_gpr_i64(GPRName::RAX, i64::MIN);
+        assert_eq!(cpu.imulq_full(GPRName::RAX), 1 << 126);
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::RDX), 1 << 62);
+    }
+
+    #[test]
+    fn test_lane_width_converters() {
+        assert_eq!(Utilities::widen_u8_to_u16(vec![0, 0x7F, 0x80, 0xFF]), vec![0, 0x7F, 0x80, 0xFF]);
+        assert_eq!(Utilities::widen_u16_to_u32(vec![0xFFFF]), vec![0xFFFF]);
+        assert_eq!(Utilities::widen_u32_to_u64(vec![0x8000_0000]), vec![0x8000_0000]);
+        assert_eq!(Utilities::widen_i8_to_i16(vec![-1, -128, 127, 0]), vec![-1, -128, 127, 0]);
+        assert_eq!(Utilities::widen_i16_to_i32(vec![-32768, 5]), vec![-32768, 5]);
+        assert_eq!(Utilities::widen_i32_to_i64(vec![i32::MIN, -2]), vec![i32::MIN as i64, -2]);
+        assert_eq!(Utilities::widen_i8_to_i16(vec![-1])[0] as u16, 0xFFFF);
+
+        assert_eq!(Utilities::narrow_u16_to_u8(vec![0x1FF, 0x80, 0x100], Narrowing::Truncate), vec![0xFF, 0x80, 0x00]);
+        assert_eq!(Utilities::narrow_u16_to_u8(vec![0x1FF, 0xFF, 0x100], Narrowing::Saturate), vec![0xFF, 0xFF, 0xFF]);
+        assert_eq!(Utilities::narrow_u32_to_u16(vec![0x1_2345, 0xFFFF], Narrowing::Saturate), vec![0xFFFF, 0xFFFF]);
+        assert_eq!(Utilities::narrow_u64_to_u32(vec![0x1_0000_0001], Narrowing::Truncate), vec![1]);
+        assert_eq!(Utilities::narrow_i16_to_i8(vec![128, -129, 127, -128], Narrowing::Saturate), vec![127, -128, 127, -128]);
+        assert_eq!(Utilities::narrow_i16_to_i8(vec![128, -129, 0x1FF], Narrowing::Truncate), vec![-128, 127, -1]);
+        assert_eq!(Utilities::narrow_i32_to_i16(vec![40000, -40000], Narrowing::Saturate), vec![32767, -32768]);
+        assert_eq!(Utilities::narrow_i64_to_i32(vec![i64::MIN, i64::MAX, -7], Narrowing::Saturate), vec![i32::MIN, i32::MAX, -7]);
+
+        let mut registers = Registers::new();
+        let widened = Utilities::widen_i8_to_i16(vec![-2; 8]);
+        assert!(registers.set_by_sections(VecRegName::XMM, 0, widened.into_iter().map(|x| x as u16).collect()));
+        assert_eq!(registers.get_by_sections::<u16>(VecRegName::XMM, 0), Some(vec![0xFFFE; 8]));
+    }
+
+    #[test]
+    fn test_cpu_rotate() {
+        let mut cpu = CPU::new(0);
+        let flags = |cpu: &CPU| cpu.registers.get_flags_value(FLAGSName::RFLAGS) & 0x801;
+        cpu.registers.set_gpr_value(GPRName::RAX, 0x1234_5678_9ABC_DE81);
+        cpu.rol_gpr(GPRName::AL, 1);
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::RAX), 0x1234_5678_9ABC_DE03);
+        assert_eq!(flags(&cpu), 0x801);
+        cpu.ror_gpr(GPRName::AL, 1);
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::AL), 0x81);
+        assert_eq!(flags(&cpu), 0x801);
+        cpu.ror_gpr(GPRName::AL, 1);
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::AL), 0xC0);
+        assert_eq!(flags(&cpu), 0x001);
+        // rotating by the register width keeps the value and sets CF from the result
+        cpu.rol_gpr(GPRName::AL, 8);
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::AL), 0xC0);
+        assert_eq!(flags(&cpu), 0x000);
+        cpu.registers.set_gpr_value(GPRName::BX, 0x8001);
+        cpu.ror_gpr(GPRName::BX, 16);
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::BX), 0x8001);
+        assert_eq!(flags(&cpu), 0x001);
+        // 32- and 64-bit counts are masked, so rotating by the width does nothing
+        cpu.registers.set_gpr_value(GPRName::RCX, 0x8000_0000_0000_0001);
+        cpu.rol_gpr(GPRName::RCX, 64);
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::RCX), 0x8000_0000_0000_0001);
+        assert_eq!(flags(&cpu), 0x001);
+        cpu.rol_gpr(GPRName::RCX, 1);
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::RCX), 0x0000_0000_0000_0003);
+        assert_eq!(flags(&cpu), 0x801);
+        cpu.ror_gpr(GPRName::ECX, 33);
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::RCX), 0x8000_0001);
+        assert_eq!(flags(&cpu), 0x801);
+
+        // through carry: CF takes part in the rotation
+        cpu.registers.set_flags_value(FLAGSName::RFLAGS, 0x001);
+        cpu.registers.set_gpr_value(GPRName::DL, 0x80);
+        cpu.rcl_gpr(GPRName::DL, 1);
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::DL), 0x01);
+        assert_eq!(flags(&cpu), 0x801);
+        cpu.rcr_gpr(GPRName::DL, 1);
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::DL), 0x80);
+        assert_eq!(flags(&cpu), 0x801);
+        cpu.rcr_gpr(GPRName::DL, 1);
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::DL), 0xC0);
+        assert_eq!(flags(&cpu), 0x000);
+        // RCL by 9 on an 8-bit register is a full turn
+        cpu.rcl_gpr(GPRName::DL, 9);
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::DL), 0xC0);
+        assert_eq!(flags(&cpu), 0x000);
+        cpu.rcl_gpr(GPRName::DL, 8);
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::DL), 0x60);
+        assert_eq!(flags(&cpu), 0x000);
+        cpu.registers.set_gpr_value(GPRName::RDX, 1);
+        cpu.rcr_gpr(GPRName::RDX, 1);
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::RDX), 0);
+        assert_eq!(flags(&cpu), 0x001);
+        cpu.rcr_gpr(GPRName::RDX, 1);
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::RDX), 1 << 63);
+        assert_eq!(flags(&cpu), 0x800);
+    }
+
+    #[test]
+    fn test_float_to_int_conversions() {
+        // NaN and infinities produce the integer indefinite value
+        assert_eq!(Utilities::cvtt_f32_to_i32(f32::NAN), i32::MIN);
+        assert_eq!(Utilities::cvtt_f32_to_i32(f32::INFINITY), i32::MIN);
+        assert_eq!(Utilities::cvtt_f32_to_i32(f32::NEG_INFINITY), i32::MIN);
+        assert_eq!(Utilities::cvtt_f64_to_i64(f64::NAN), i64::MIN);
+        assert_eq!(Utilities::cvtt_f64_to_i64(f64::INFINITY), i64::MIN);
+        assert_eq!(Utilities::cvtt_f64_to_i64(f64::NEG_INFINITY), i64::MIN);
+        // around the edges of the destination range
+        assert_eq!(Utilities::cvtt_f32_to_i32(2147483648.0), i32::MIN);
+        assert_eq!(Utilities::cvtt_f32_to_i32(2147483520.0), 2147483520);
+        assert_eq!(Utilities::cvtt_f32_to_i32(-2147483648.0), i32::MIN);
+        assert_eq!(Utilities::cvtt_f32_to_i32(-2147483904.0), i32::MIN);
+        assert_eq!(Utilities::cvtt_f64_to_i64(9223372036854775808.0), i64::MIN);
+        assert_eq!(Utilities::cvtt_f64_to_i64(9223372036854774784.0), 9223372036854774784);
+        assert_eq!(Utilities::cvtt_f64_to_i64(-9223372036854777856.0), i64::MIN);
+        assert_eq!(Utilities::cvtt_f32_to_i32(-1.9), -1);
+        assert_eq!(Utilities::cvtt_f64_to_i64(1.9), 1);
+
+        // every rounding mode on a tie
+        let cases = [
+            (RoundingMode::NearestEven, [2, -2, 4]),
+            (RoundingMode::Down, [2, -3, 3]),
+            (RoundingMode::Up, [3, -2, 4]),
+            (RoundingMode::TowardZero, [2, -2, 3]),
+        ];
+        for (mode, expected) in cases {
+            assert_eq!(Utilities::cvt_f32_to_i32_rounded_vec(&[2.5, -2.5, 3.5], mode), expected);
+            assert_eq!(Utilities::cvt_f64_to_i64_rounded_vec(&[2.5, -2.5, 3.5], mode),
+                       expected.map(i64::from));
+        }
+        assert_eq!(Utilities::cvt_f32_to_i32_rounded(f32::NAN, RoundingMode::Up), i32::MIN);
+        assert_eq!(Utilities::cvt_f32_to_i32_rounded(2147483520.0, RoundingMode::Up), 2147483520);
+        assert_eq!(Utilities::cvtt_f32_to_i32_vec(&[1.5, f32::NAN, -0.5]), vec![1, i32::MIN, 0]);
+        assert_eq!(Utilities::cvtt_f64_to_i64_vec(&[-1.5, f64::INFINITY]), vec![-1, i64::MIN]);
+    }
+
+    #[test]
+    fn test_cpu_bit_scan_and_count() {
+        let mut cpu = CPU::default();
+        let flags = |cpu: &CPU| cpu.registers.get_flags_value(FLAGSName::RFLAGS) & 0x8C1;
+        // (source, destination, width)
+        let widths = [
+            (GPRName::AX, GPRName::CX, 16u32),
+            (GPRName::EAX, GPRName::ECX, 32),
+            (GPRName::RAX, GPRName::RCX, 64),
+        ];
+        for (src, dst, width) in widths {
+            let ones = u64::MAX >> (64 - width);
+
+            // all zero: BSF and BSR leave the destination alone, the counts yield the width
+            cpu.registers.set_gpr_value(GPRName::RAX, 0);
+            cpu.registers.set_gpr_value(GPRName::RCX, 0x55);
+            cpu.registers.set_flags_value(FLAGSName::RFLAGS, 0x801);
+            assert!(!cpu.bsf(dst, src));
+            assert_eq!(cpu.registers.get_gpr_value(dst), 0x55);
+            assert_eq!(flags(&cpu), 0x841);
+            assert!(!cpu.bsr(dst, src));
+            assert_eq!(cpu.registers.get_gpr_value(dst), 0x55);
+            assert_eq!(cpu.lzcnt_gpr(dst, src), width);
+            assert_eq!(cpu.registers.get_gpr_value(dst), width as u64);
+            assert_eq!(flags(&cpu), 0x001);
+            assert_eq!(cpu.tzcnt_gpr(dst, src), width);
+            assert_eq!(flags(&cpu), 0x001);
+
+            // a power of two
+            cpu.registers.set_gpr_value(src, 1 << (width - 4));
+            assert!(cpu.bsf(dst, src));
+            assert_eq!(cpu.registers.get_gpr_value(dst), width as u64 - 4);
+            assert_eq!(flags(&cpu), 0x001);
+            assert!(cpu.bsr(dst, src));
+            assert_eq!(cpu.registers.get_gpr_value(dst), width as u64 - 4);
+            assert_eq!(cpu.lzcnt_gpr(dst, src), 3);
+            assert_eq!(flags(&cpu), 0x000);
+            assert_eq!(cpu.tzcnt_gpr(dst, src), width - 4);
+            assert_eq!(cpu.registers.get_gpr_value(dst), width as u64 - 4);
+
+            // all ones
+            cpu.registers.set_gpr_value(src, ones);
+            assert!(cpu.bsf(dst, src));
+            assert_eq!(cpu.registers.get_gpr_value(dst), 0);
+            assert!(cpu.bsr(dst, src));
+            assert_eq!(cpu.registers.get_gpr_value(dst), width as u64 - 1);
+            assert_eq!(cpu.lzcnt_gpr(dst, src), 0);
+            assert_eq!(flags(&cpu), 0x040);
+            assert_eq!(cpu.tzcnt_gpr(dst, src), 0);
+            assert_eq!(flags(&cpu), 0x040);
+        }
+
+        // bits above the source width do not take part
+        cpu.registers.set_gpr_value(GPRName::RAX, 0xFFFF_0000);
+        assert!(!cpu.bsr(GPRName::CX, GPRName::AX));
+        assert_eq!(cpu.lzcnt_gpr(GPRName::CX, GPRName::AX), 16);
+        assert_eq!(cpu.tzcnt_gpr(GPRName::CX, GPRName::AX), 16);
+    }
+
+    #[test]
+    fn test_bit_scan_helpers() {
+        let carry = FlagsDelta { cf: true, ..FlagsDelta::default() };
+        let zero = FlagsDelta { zf: true, ..FlagsDelta::default() };
+        for width in [16usize, 32, 64] {
+            let top = 1u64 << (width - 1);
+            let w = width as u32;
+            // zero input
+            assert_eq!(Utilities::lzcnt(0, width), (w, carry));
+            assert_eq!(Utilities::tzcnt(0, width), (w, carry));
+            assert_eq!(Utilities::bsf(0, width), None);
+            assert_eq!(Utilities::bsr(0, width), None);
+            assert_eq!(Utilities::popcnt(0, width), (0, zero));
+            // lowest bit
+            assert_eq!(Utilities::lzcnt(1, width), (w - 1, FlagsDelta::default()));
+            assert_eq!(Utilities::tzcnt(1, width), (0, zero));
+            assert_eq!(Utilities::bsf(1, width), Some(0));
+            assert_eq!(Utilities::bsr(1, width), Some(0));
+            assert_eq!(Utilities::popcnt(1, width), (1, FlagsDelta::default()));
+            // highest bit
+            assert_eq!(Utilities::lzcnt(top, width), (0, zero));
+            assert_eq!(Utilities::tzcnt(top, width), (w - 1, FlagsDelta::default()));
+            assert_eq!(Utilities::bsf(top, width), Some(w - 1));
+            assert_eq!(Utilities::bsr(top, width), Some(w - 1));
+            assert_eq!(Utilities::popcnt(top, width), (1, FlagsDelta::default()));
+        }
+        // bits above the operand width are ignored
+        assert_eq!(Utilities::tzcnt(0x1_0000, 16), (16, carry));
+        assert_eq!(Utilities::bsr(0x1_8000, 16), Some(15));
+        assert_eq!(Utilities::popcnt(u64::MAX, 32).0, 32);
+    }
+
+    #[test]
+    fn test_cpu_popcnt() {
+        let mut cpu = CPU::default();
+        let arithmetic = 0x8D5;
+        for (src, dst, width) in [(GPRName::AX, GPRName::DX, 16u32), (GPRName::EAX, GPRName::EDX, 32), (GPRName::RAX, GPRName::RDX, 64)] {
+            for (value, expected) in [(0u64, 0u32), (1, 1), (0xFF, 8), (u64::MAX, width)] {
+                cpu.registers.set_gpr_value(GPRName::RAX, value);
+                cpu.registers.set_gpr_value(GPRName::RDX, u64::MAX);
+                cpu.registers.set_flags_value(FLAGSName::RFLAGS, arithmetic | 0x400);
+                assert_eq!(cpu.popcnt_gpr(dst, src), expected);
+                assert_eq!(cpu.registers.get_gpr_value(dst), expected as u64);
+                let zf = if value == 0 { 0x40 } else { 0 };
+                assert_eq!(cpu.registers.get_flags_value(FLAGSName::RFLAGS), 0x400 | zf);
+            }
+        }
+    }
+
+    #[test]
+    fn test_clmul() {
+        assert_eq!(Utilities::clmul64(0, 0xDEAD_BEEF), 0);
+        assert_eq!(Utilities::clmul64(0xDEAD_BEEF, 1), 0xDEAD_BEEF);
+        // (x + 1)^2 = x^2 + 1
+        assert_eq!(Utilities::clmul64(3, 3), 5);
+        assert_eq!(Utilities::clmul64(0xFF, 0xFF), 0x5555);
+        assert_eq!(Utilities::clmul64(1 << 63, 1 << 63), 1 << 126);
+        assert_eq!(Utilities::clmul64(u64::MAX, 2), (u64::MAX as u128) << 1);
+        // the low terms of the GHASH polynomial x^128 + x^7 + x^2 + x + 1, squared
+        assert_eq!(Utilities::clmul64(0x87, 0x87), 0x4015);
+        // the bit-reflected GHASH constant used by PCLMULQDQ-based reduction
+        assert_eq!(Utilities::clmul64(0xC200_0000_0000_0000, 0xC200_0000_0000_0000), 0x5004_0000_0000_0000_0000_0000_0000_0000);
+
+        // the PCLMULQDQ examples from Intel's carry-less multiplication white paper
+        let a = 0x7b5b_5465_7374_5665_6374_6f72_5d53_475d;
+        let b = 0x4869_2853_6861_7929_5b47_7565_726f_6e5d;
+        assert_eq!(Utilities::clmul128(a, b, 0x00), 0x1d4d_84c8_5c34_40c0_9296_33d5_d36f_0451);
+        assert_eq!(Utilities::clmul128(a, b, 0x01), 0x1a2b_f6db_3a30_862f_babf_262d_f4b7_d5c9);
+        assert_eq!(Utilities::clmul128(a, b, 0x10), 0x1bd1_7c8d_556a_b5a1_7fa5_40ac_2a28_1315);
+        assert_eq!(Utilities::clmul128(a, b, 0x11), 0x1d1e_1f2c_592e_7c45_d66e_e03e_410f_d4ed);
+        // only bits 0 and 4 of the immediate matter
+        assert_eq!(Utilities::clmul128(a, b, 0xEE), Utilities::clmul128(a, b, 0x00));
+    }
+
+    #[test]
+    fn test_cpu_bswap() {
+        let mut cpu = CPU::default();
+        let value = 0x0123_4567_89AB_CDEFu64;
+        cpu.registers.set_flags_value(FLAGSName::RFLAGS, 0x8D7);
+        cpu.registers.set_gpr_value(GPRName::RAX, value);
+        cpu.bswap_gpr(GPRName::RAX);
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::RAX), value.swap_bytes());
+        cpu.bswap_gpr(GPRName::RAX);
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::RAX), value);
+        assert_eq!(cpu.registers.get_flags_value(FLAGSName::RFLAGS), 0x8D7);
+
+        // the 32-bit form swaps the low doubleword and clears the upper half
+        cpu.registers.set_gpr_value(GPRName::R9, value);
+        cpu.bswap_gpr(GPRName::R9D);
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::R9), (value as u32).swap_bytes() as u64);
+
+        // the 16-bit form zeroes the register and leaves the rest alone
+        cpu.registers.set_gpr_value(GPRName::RBX, value);
+        cpu.bswap_gpr(GPRName::BX);
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::RBX), 0x0123_4567_89AB_0000);
+    }
+
+    #[test]
+    fn test_crc32c() {
+        let checksum = |data: &[u8]| !Utilities::crc32c_slice(!0, data);
+        // standard CRC32C check values (RFC 3720, appendix B.4)
+        assert_eq!(checksum(b"123456789"), 0xE306_9283);
+        assert_eq!(checksum(&[0x00; 32]), 0x8A91_36AA);
+        assert_eq!(checksum(&[0xFF; 32]), 0x62A8_AB43);
+        let ascending: Vec<u8> = (0..32).collect();
+        assert_eq!(checksum(&ascending), 0x46DD_794E);
+        assert_eq!(checksum(&[]), 0);
+
+        // the wide forms match feeding their bytes one at a time, in little-endian order
+        let data = 0x0123_4567_89AB_CDEFu64;
+        let crc = 0x1234_5678;
+        assert_eq!(Utilities::crc32c_u64(crc, data), Utilities::crc32c_slice(crc, &data.to_le_bytes()));
+        assert_eq!(Utilities::crc32c_u32(crc, data as u32), Utilities::crc32c_slice(crc, &(data as u32).to_le_bytes()));
+        assert_eq!(Utilities::crc32c_u16(crc, data as u16), Utilities::crc32c_slice(crc, &(data as u16).to_le_bytes()));
+        assert_eq!(Utilities::crc32c_u8(crc, data as u8), Utilities::crc32c_slice(crc, &[data as u8]));
+
+        // incremental and one-shot accumulation agree
+        let text = b"123456789";
+        let (head, tail) = text.split_at(4);
+        let incremental = Utilities::crc32c_slice(Utilities::crc32c_slice(!0, head), tail);
+        assert_eq!(!incremental, 0xE306_9283);
+        let quad = u64::from_le_bytes(text[..8].try_into().unwrap());
+        assert_eq!(!Utilities::crc32c_u8(Utilities::crc32c_u64(!0, quad), text[8]), 0xE306_9283);
+    }
+
+    #[test]
+    fn test_cpu_cpuid() {
+        let mut cpu = CPU::default();
+        let vendor = |cpu: &CPU| {
+            [GPRName::EBX, GPRName::EDX, GPRName::ECX].iter()
+                .flat_map(|reg| (cpu.registers.get_gpr_value(*reg) as u32).to_le_bytes())
+                .collect::<Vec<u8>>()
+        };
+        cpu.registers.set_gpr_value(GPRName::RAX, 0xFFFF_FFFF_0000_0000);
+        cpu.registers.set_gpr_value(GPRName::RBX, u64::MAX);
+        cpu.cpuid();
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::RAX), 1);
+        assert_eq!(vendor(&cpu), b"GenuineIntel");
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::RBX) >> 32, 0);
+
+        // leaf 1 carries the feature bits: SSE2 in EDX, POPCNT in ECX
+        cpu.registers.set_gpr_value(GPRName::RAX, 1);
+        cpu.cpuid();
+        assert_ne!(cpu.registers.get_gpr_value(GPRName::EDX) & 1 << 26, 0);
+        assert_ne!(cpu.registers.get_gpr_value(GPRName::ECX) & 1 << 23, 0);
+        cpu.set_cpuid_leaf(1, 0x000906EA, 0, 0, 1 << 25);
+        cpu.registers.set_gpr_value(GPRName::RAX, 1);
+        cpu.cpuid();
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::EAX), 0x000906EA);
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::ECX), 0);
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::EDX), 1 << 25);
+
+        // unconfigured leaves are zero, and subleaves take precedence
+        cpu.registers.set_gpr_value(GPRName::RAX, 7);
+        cpu.cpuid();
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::RAX) | cpu.registers.get_gpr_value(GPRName::RBX), 0);
+        cpu.set_cpuid_leaf(0, 7, 0x6C75_7043, 0x6269_6C75, 0x7043_6269);
+        cpu.set_cpuid_leaf(7, 0, 0, 0, 0);
+        cpu.set_cpuid_subleaf(7, 0, [0, 1 << 5, 0, 0]);
+        for (subleaf, ebx) in [(0, 1 << 5), (1, 0)] {
+            cpu.registers.set_gpr_value(GPRName::RAX, 7);
+            cpu.registers.set_gpr_value(GPRName::RCX, subleaf);
+            cpu.cpuid();
+            assert_eq!(cpu.registers.get_gpr_value(GPRName::EBX), ebx);
+        }
+        cpu.registers.set_gpr_value(GPRName::RAX, 0);
+        cpu.cpuid();
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::EAX), 7);
+        assert_eq!(vendor(&cpu), b"CpulibCpulib".to_vec());
+    }
+
+    #[test]
+    fn test_randomize_state() {
+        let biased = RandomizeOptions { interesting_values: true };
+        for options in [RandomizeOptions::default(), biased] {
+            let mut first = Registers::new();
+            let mut second = Registers::new();
+            Utilities::randomize_registers_with(&mut first, 42, options);
+            Utilities::randomize_registers_with(&mut second, 42, options);
+            assert_eq!(first.to_bytes(), second.to_bytes());
+            Utilities::randomize_registers_with(&mut second, 43, options);
+            assert_ne!(first.to_bytes(), second.to_bytes());
+
+            // every vector register is populated
+            for index in 0..32 {
+                assert_ne!(first.get_zmm_bytes(index), [0; 64], "ZMM{}", index);
+            }
+            assert_eq!(first.get_flags_value(FLAGSName::RFLAGS) & !0xFD5, 0x2);
+
+            let mut memory = Memory::new(0x1000);
+            let mut other = Memory::new(0x1000);
+            Utilities::randomize_memory_with(&mut memory, 0x1003..0x1400, 7, options).unwrap();
+            Utilities::randomize_memory_with(&mut other, 0x1003..0x1400, 7, options).unwrap();
+            let bytes = memory.read_bytes_checked(0x1003, 0x3FD);
+            assert_eq!(bytes, other.read_bytes_checked(0x1003, 0x3FD));
+            assert!(bytes.iter().all(|byte| byte.is_some()));
+            assert!(bytes.iter().any(|byte| *byte != Some(0)));
+        }
+
+        // the biased generator produces edge cases, the plain one does not
+        let mut registers = Registers::new();
+        Utilities::randomize_registers(&mut registers, 1);
+        let mut plain = registers.to_bytes();
+        Utilities::randomize_registers_with(&mut registers, 1, biased);
+        let mut edge = registers.to_bytes();
+        let edge_cases = |bytes: &[u8]| bytes.chunks_exact(8)
+            .filter(|chunk| [0, u64::MAX, 0x7FF8_0000_0000_0000].contains(&u64::from_le_bytes((*chunk).try_into().unwrap())))
+            .count();
+        plain.truncate(32 * 64);
+        edge.truncate(32 * 64);
+        assert_eq!(edge_cases(&plain), 0);
+        assert!(edge_cases(&edge) > 0);
+
+        // an empty range writes nothing
+        let mut memory = Memory::new(0);
+        Utilities::randomize_memory(&mut memory, 0x10..0x10, 1).unwrap();
+        assert!(memory.read_bytes_checked(0x10, 1)[0].is_none());
+    }
+
+    #[test]
+    fn test_cpu_rdtsc() {
+        let mut cpu = CPU::default();
+        cpu.registers.set_gpr_value(GPRName::RAX, u64::MAX);
+        cpu.registers.set_gpr_value(GPRName::RDX, u64::MAX);
+        cpu.rdtsc();
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::RAX), 0);
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::RDX), 0);
+
+        cpu.tick(0x1234_5678_9ABC_DEF0);
+        cpu.rdtsc();
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::RAX), 0x9ABC_DEF0);
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::RDX), 0x1234_5678);
+
+        // stepping advances the counter RDTSC reads
+        cpu.set_instruction_latency(Instruction::Nop, 0x10);
+        cpu.step(Instruction::Nop).unwrap();
+        cpu.rdtsc();
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::RAX), 0x9ABC_DF00);
+        cpu.reset_cycle_count();
+        cpu.rdtsc();
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::RAX) | cpu.registers.get_gpr_value(GPRName::RDX), 0);
+    }
+
+    #[test]
+    fn test_float_min_max_semantics() {
+        let bits32 = |v: f32| v.to_bits();
+        let bits64 = |v: f64| v.to_bits();
+        // ordinary values
+        assert_eq!(Utilities::minps_scalar(1.5, -2.0), -2.0);
+        assert_eq!(Utilities::maxps_scalar(1.5, -2.0), 1.5);
+        assert_eq!(Utilities::minpd_scalar(-1.0, 3.0), -1.0);
+        assert_eq!(Utilities::maxpd_scalar(-1.0, 3.0), 3.0);
+        // NaN in either position yields the second operand
+        assert_eq!(Utilities::minps_scalar(f32::NAN, 1.0), 1.0);
+        assert!(Utilities::minps_scalar(1.0, f32::NAN).is_nan());
+        assert_eq!(Utilities::maxps_scalar(f32::NAN, 1.0), 1.0);
+        assert!(Utilities::maxps_scalar(1.0, f32::NAN).is_nan());
+        assert_eq!(Utilities::minpd_scalar(f64::NAN, 1.0), 1.0);
+        assert!(Utilities::minpd_scalar(1.0, f64::NAN).is_nan());
+        assert_eq!(Utilities::maxpd_scalar(f64::NAN, 1.0), 1.0);
+        assert!(Utilities::maxpd_scalar(1.0, f64::NAN).is_nan());
+        // the NaN returned is the second operand itself, payload included
+        let payload = f32::from_bits(0x7FC0_1234);
+        assert_eq!(bits32(Utilities::minps_scalar(f32::NAN, payload)), 0x7FC0_1234);
+        // zeros of differing signs yield the second operand
+        assert_eq!(bits32(Utilities::minps_scalar(0.0, -0.0)), bits32(-0.0));
+        assert_eq!(bits32(Utilities::minps_scalar(-0.0, 0.0)), bits32(0.0));
+        assert_eq!(bits32(Utilities::maxps_scalar(0.0, -0.0)), bits32(-0.0));
+        assert_eq!(bits32(Utilities::maxps_scalar(-0.0, 0.0)), bits32(0.0));
+        assert_eq!(bits64(Utilities::minpd_scalar(0.0, -0.0)), bits64(-0.0));
+        assert_eq!(bits64(Utilities::maxpd_scalar(-0.0, 0.0)), bits64(0.0));
+
+        let minimum = Utilities::minps_vec(&[1.0, f32::NAN, -0.0], &[2.0, 5.0, 0.0]).unwrap();
+        assert_eq!(minimum.iter().map(|v| v.to_bits()).collect::<Vec<_>>(), vec![bits32(1.0), bits32(5.0), bits32(0.0)]);
+        assert_eq!(Utilities::maxps_vec(&[1.0, 4.0], &[2.0, 3.0]), Ok(vec![2.0, 4.0]));
+        assert_eq!(Utilities::minpd_vec(&[1.0, f64::NAN], &[0.5, 7.0]), Ok(vec![0.5, 7.0]));
+        assert_eq!(Utilities::maxpd_vec(&[1.0], &[]), Err(LengthMismatch { left: 1, right: 0 }));
+    }
+
+    #[test]
+    fn test_cpu_syscall() {
+        let mut cpu = CPU::default();
+        let arguments = [GPRName::RDI, GPRName::RSI, GPRName::RDX, GPRName::R10, GPRName::R8, GPRName::R9];
+        for (i, reg) in arguments.iter().enumerate() {
+            cpu.registers.set_gpr_value(*reg, 0x100 + i as u64);
+        }
+        cpu.registers.set_gpr_value(GPRName::RAX, 39);
+        cpu.registers.set_ip_value(IPName::RIP, 0x40_1000);
+        cpu.registers.set_flags_value(FLAGSName::RFLAGS, 0x246);
+
+        // without a handler only RCX, R11 and RIP change
+        let info = cpu.syscall();
+        assert_eq!(info, SyscallInfo { number: 39, args: [0x100, 0x101, 0x102, 0x103, 0x104, 0x105] });
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::RAX), 39);
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::RCX), 0x40_1002);
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::R11), 0x246);
+        assert_eq!(cpu.registers.get_ip_value(IPName::RIP), 0x40_1002);
+
+        // a fake write(2) that reads the buffer from guest memory
+        let written = Rc::new(RefCell::new(Vec::new()));
+        let sink = written.clone();
+        cpu.set_syscall_handler(Box::new(move |info, cpu| match info.number {
+            1 => {
+                let mut buffer = vec![0; info.args[2] as usize];
+                cpu.memory.read_exact(info.args[1], &mut buffer).unwrap();
+                sink.borrow_mut().extend(buffer);
+                info.args[2]
+            }
+            _ => -38i64 as u64,
+        }));
+        cpu.memory.write_bytes(0x40_3000, b"hello").unwrap();
+        cpu.registers.set_gpr_value(GPRName::RAX, 1);
+        cpu.registers.set_gpr_value(GPRName::RSI, 0x40_3000);
+        cpu.registers.set_gpr_value(GPRName::RDX, 5);
+        assert_eq!(cpu.syscall().number, 1);
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::RAX), 5);
+        assert_eq!(*written.borrow(), b"hello");
+        assert_eq!(cpu.registers.get_ip_value(IPName::RIP), 0x40_1004);
+
+        // the handler stays installed across calls
+        cpu.registers.set_gpr_value(GPRName::RAX, 9999);
+        cpu.syscall();
+        assert_eq!(cpu.registers.get_gpr_as_i64(GPRName::RAX), -38);
+
+        cpu.clear_syscall_handler();
+        cpu.registers.set_gpr_value(GPRName::RAX, 1);
+        cpu.syscall();
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::RAX), 1);
+
+        // SYSRET restores RIP and RFLAGS from RCX and R11
+        cpu.registers.set_gpr_value(GPRName::RCX, 0x40_2000);
+        cpu.registers.set_gpr_value(GPRName::R11, 0x801);
+        cpu.sysret();
+        assert_eq!(cpu.registers.get_ip_value(IPName::RIP), 0x40_2000);
+        assert_eq!(cpu.registers.get_flags_value(FLAGSName::RFLAGS), 0x803);
+    }
+
+    #[test]
+    fn test_denormal_handling() {
+        let tiny32 = f32::from_bits(1);
+        let tiny64 = f64::from_bits(1);
+        let largest_subnormal32 = f32::from_bits(0x007F_FFFF);
+        for flush in [Utilities::apply_daz_f32, Utilities::apply_ftz_f32] {
+            assert_eq!(flush(tiny32, false).to_bits(), 1);
+            assert_eq!(flush(tiny32, true).to_bits(), 0);
+            assert_eq!(flush(-tiny32, true).to_bits(), (-0.0f32).to_bits());
+            assert_eq!(flush(largest_subnormal32, true).to_bits(), 0);
+            // the smallest normal value is the flush boundary and is kept
+            assert_eq!(flush(f32::MIN_POSITIVE, true), f32::MIN_POSITIVE);
+            assert_eq!(flush(-f32::MIN_POSITIVE, true), -f32::MIN_POSITIVE);
+            assert_eq!(flush(-0.0, true).to_bits(), (-0.0f32).to_bits());
+            assert!(flush(f32::NAN, true).is_nan());
+        }
+        for flush in [Utilities::apply_daz_f64, Utilities::apply_ftz_f64] {
+            assert_eq!(flush(tiny64, false).to_bits(), 1);
+            assert_eq!(flush(tiny64, true).to_bits(), 0);
+            assert_eq!(flush(-tiny64, true).to_bits(), (-0.0f64).to_bits());
+            assert_eq!(flush(f64::MIN_POSITIVE, true), f64::MIN_POSITIVE);
+            assert_eq!(flush(f64::INFINITY, true), f64::INFINITY);
+        }
+
+        // MXCSR: DAZ is bit 6, UM bit 11, FTZ bit 15; 0x1F80 is the reset value
+        let reset = 0x1F80;
+        assert_eq!(Utilities::canonicalize_operand_f32(tiny32, reset), tiny32);
+        assert_eq!(Utilities::canonicalize_operand_f32(-tiny32, reset | 1 << 6).to_bits(), (-0.0f32).to_bits());
+        assert_eq!(Utilities::canonicalize_operand_f64(tiny64, reset | 1 << 15), tiny64);
+        assert_eq!(Utilities::canonicalize_result_f32(tiny32, reset | 1 << 6), tiny32);
+        assert_eq!(Utilities::canonicalize_result_f32(-tiny32, reset | 1 << 15).to_bits(), (-0.0f32).to_bits());
+        assert_eq!(Utilities::canonicalize_result_f64(tiny64, reset | 1 << 15).to_bits(), 0);
+        // FTZ has no effect while underflow is unmasked
+        assert_eq!(Utilities::canonicalize_result_f64(tiny64, (reset | 1 << 15) & !(1 << 11)), tiny64);
+        assert_eq!(Utilities::canonicalize_result_f32(f32::MIN_POSITIVE, reset | 1 << 15), f32::MIN_POSITIVE);
+    }
+
+    #[test]
+    fn test_f80_conversions() {
+        // known encodings
+        assert_eq!(Utilities::f64_to_f80_bits(1.0), (0x8000_0000_0000_0000, 0x3FFF));
+        assert_eq!(Utilities::f64_to_f80_bits(-2.0), (0x8000_0000_0000_0000, 0xC000));
+        assert_eq!(Utilities::f64_to_f80_bits(std::f64::consts::PI), (0xC90F_DAA2_2168_C000, 0x4000));
+        assert_eq!(Utilities::f64_to_f80_bits(0.0), (0, 0));
+        assert_eq!(Utilities::f64_to_f80_bits(-0.0), (0, 0x8000));
+        assert_eq!(Utilities::f64_to_f80_bits(f64::INFINITY), (0x8000_0000_0000_0000, 0x7FFF));
+        assert_eq!(Utilities::f64_to_f80_bits(f64::NEG_INFINITY), (0x8000_0000_0000_0000, 0xFFFF));
+        assert_eq!(Utilities::f64_to_f80_bits(f64::NAN), (0xC000_0000_0000_0000, 0x7FFF));
+        // the smallest subnormal double is 2^-1074, a normal extended value
+        assert_eq!(Utilities::f64_to_f80_bits(f64::from_bits(1)), (0x8000_0000_0000_0000, 16383 - 1074));
+
+        // the x87 FLDPI constant rounds to the double closest to pi
+        assert_eq!(Utilities::f80_bits_to_f64(0xC90F_DAA2_2168_C235, 0x4000), std::f64::consts::PI);
+        // ties round to even
+        assert_eq!(Utilities::f80_bits_to_f64(0x8000_0000_0000_0400, 0x3FFF), 1.0);
+        assert_eq!(Utilities::f80_bits_to_f64(0x8000_0000_0000_0C00, 0x3FFF), 1.0 + 2.0 * f64::EPSILON);
+        assert_eq!(Utilities::f80_bits_to_f64(0x8000_0000_0000_0401, 0x3FFF), 1.0 + f64::EPSILON);
+        // rounding up carries into the exponent, and out of range values saturate to infinity or zero
+        assert_eq!(Utilities::f80_bits_to_f64(u64::MAX, 0x3FFF), 2.0);
+        assert_eq!(Utilities::f80_bits_to_f64(u64::MAX, 0x43FE), f64::INFINITY);
+        assert_eq!(Utilities::f80_bits_to_f64(0x8000_0000_0000_0000, 0xFFFE), f64::NEG_INFINITY);
+        assert_eq!(Utilities::f80_bits_to_f64(0x8000_0000_0000_0000, 1).to_bits(), 0);
+        assert_eq!(Utilities::f80_bits_to_f64(0x8000_0000_0000_0000, 0x8001).to_bits(), (-0.0f64).to_bits());
+        // 2^-1075 is a tie between 0 and the smallest subnormal; 1.5 * 2^-1075 rounds up
+        assert_eq!(Utilities::f80_bits_to_f64(0x8000_0000_0000_0000, 16383 - 1075).to_bits(), 0);
+        assert_eq!(Utilities::f80_bits_to_f64(0xC000_0000_0000_0000, 16383 - 1075).to_bits(), 1);
+        assert!(Utilities::f80_bits_to_f64(0xC000_0000_0000_0001, 0x7FFF).is_nan());
+        assert_eq!(Utilities::f80_bits_to_f64(0x8000_0000_0000_0000, 0x7FFF), f64::INFINITY);
+
+        // exact doubles round-trip
+        let values = [1.0, -1.5, std::f64::consts::E, f64::MAX, f64::MIN_POSITIVE, f64::from_bits(1),
+                      f64::from_bits(0x000F_FFFF_FFFF_FFFF), -0.0, f64::INFINITY, 1e-300, 123456789.125];
+        for value in values {
+            let (mantissa, sign_exponent) = Utilities::f64_to_f80_bits(value);
+            assert_eq!(Utilities::f80_bits_to_f64(mantissa, sign_exponent).to_bits(), value.to_bits());
+        }
+        let nan = f64::from_bits(0x7FF8_0000_0000_1234);
+        let (mantissa, sign_exponent) = Utilities::f64_to_f80_bits(nan);
+        assert_eq!(Utilities::f80_bits_to_f64(mantissa, sign_exponent).to_bits(), nan.to_bits());
+
+        // through memory: 8 bytes of significand, then the sign and exponent word
+        let mut memory = Memory::new(0);
+        memory.write_f80(0x100, -std::f64::consts::PI).unwrap();
+        let mut bytes = [0; 10];
+        memory.read_exact(0x100, &mut bytes).unwrap();
+        assert_eq!(bytes, [0x00, 0xC0, 0x68, 0x21, 0xA2, 0xDA, 0x0F, 0xC9, 0x00, 0xC0]);
+        assert_eq!(memory.read_f80(0x100), -std::f64::consts::PI);
+        memory.write_bytes(0x200, &[0x35, 0xC2, 0x68, 0x21, 0xA2, 0xDA, 0x0F, 0xC9, 0x00, 0x40]).unwrap();
+        assert_eq!(memory.read_f80(0x200), std::f64::consts::PI);
+        // the layout does not follow the byte order of the memory
+        let mut memory = Memory::new_with_endianness(0, Endianness::Big);
+        memory.write_f80(0x100, 1.5).unwrap();
+        assert_eq!(memory.read_f80(0x100), 1.5);
+        memory.write_f80(0x200, -std::f64::consts::PI).unwrap();
+        memory.read_exact(0x200, &mut bytes).unwrap();
+        assert_eq!(bytes, [0x00, 0xC0, 0x68, 0x21, 0xA2, 0xDA, 0x0F, 0xC9, 0x00, 0xC0]);
+        assert_eq!(memory.read_f80(0x200), -std::f64::consts::PI);
+    }
+
+    #[test]
+    fn test_movemask() {
+        assert_eq!(Utilities::movemask_u8(&[]), 0);
+        assert_eq!(Utilities::movemask_u8(&[0x80, 0x7F, 0xFF, 0x00]), 0b0101);
+        assert_eq!(Utilities::movemask_u8(&[0x80; 64]), u64::MAX);
+        assert_eq!(Utilities::movemask_u8(&[0x80; 70]), u64::MAX);
+        assert_eq!(Utilities::movemask_f32(&[1.0, -1.0, -0.0, 0.0]), 0b0110);
+        assert_eq!(Utilities::movemask_f32(&[f32::from_bits(0xFFC0_0000), f32::NAN]), 0b01);
+        assert_eq!(Utilities::movemask_f32(&[-1.0; 16]), 0xFFFF);
+        assert_eq!(Utilities::movemask_f64(&[-2.5, 3.0, f64::NEG_INFINITY, 0.0]), 0b0101);
+        assert_eq!(Utilities::movemask_f64(&[-0.0; 8]), 0xFF);
+
+        let mut registers = Registers::new();
+        let mut bytes = [0u8; 64];
+        bytes[0] = 0x80;
+        bytes[15] = 0xF0;
+        bytes[16] = 0x81;
+        bytes[63] = 0xFF;
+        registers.set_zmm_bytes(3, bytes);
+        assert_eq!(registers.pmovmskb(VecRegName::XMM, 3), Some(0x8001));
+        assert_eq!(registers.pmovmskb(VecRegName::YMM, 3), Some(0x1_8001));
+        assert_eq!(registers.pmovmskb(VecRegName::ZMM, 3), Some(0x8000_0000_0001_8001));
+        registers.set_mode(CPUMode::Mode32);
+        assert_eq!(registers.pmovmskb(VecRegName::XMM, 8), None);
+    }
+
+    #[test]
+    fn test_compress_expand() {
+        let lanes = [10u32, 11, 12, 13, 14, 15, 16, 17];
+        let mask = [true, false, false, true, true, false, true, false];
+        let compressed = Utilities::compress_vec(&lanes, &mask);
+        assert_eq!(compressed, vec![10, 13, 14, 16]);
+        assert_eq!(Utilities::expand_vec(&compressed, &mask), vec![10, 0, 0, 13, 14, 0, 16, 0]);
+        assert_eq!(Utilities::expand_vec(&[1u32, 2, 3, 4, 5, 6], &mask), vec![1, 0, 0, 2, 3, 0, 4, 0]);
+
+        // expanding a compressed vector restores the selected lanes and zeroes the others
+        for bits in [0u8, 0xFF, 0xA5, 0x3C] {
+            let mask: Vec<bool> = (0..8).map(|i| bits >> i & 1 != 0).collect();
+            let expected: Vec<u32> = lanes.iter().zip(&mask).map(|(lane, selected)| if *selected { *lane } else { 0 }).collect();
+            assert_eq!(Utilities::expand_vec(&Utilities::compress_vec(&lanes, &mask), &mask), expected);
+        }
+
+        // non-numeric lanes and short sources
+        let floats = Utilities::compress_vec(&[1.5f64, -2.0, 3.25], &[false, true, true]);
+        assert_eq!(floats, vec![-2.0, 3.25]);
+        assert_eq!(Utilities::expand_vec(&[7i64], &[true, true, false]), vec![7, 0, 0]);
+        assert_eq!(Utilities::compress_vec::<u8>(&[1, 2, 3], &[true]), vec![1]);
+    }
+
+    #[test]
+    fn test_float_byte_vectors() {
+        let signaling = f32::from_bits(0x7F80_0001);
+        let payload = f64::from_bits(0xFFF4_0000_DEAD_BEEF);
+        let floats = vec![1.0f32, -0.0, signaling, f32::from_bits(0x7FC0_1234)];
+        let bytes = Utilities::f32vec_to_bytes(floats.clone());
+        assert_eq!(&bytes[..8], &[0x00, 0x00, 0x80, 0x3F, 0x00, 0x00, 0x00, 0x80]);
+        let decoded = Utilities::bytes_to_f32vec(&bytes).unwrap();
+        assert_eq!(Utilities::f32vec_to_u32vec(decoded), Utilities::f32vec_to_u32vec(floats.clone()));
+        assert_eq!(Utilities::bytes_to_f32vec(&bytes[..5]), Err(ByteLengthError { length: 5, lane_size: 4 }));
+
+        let doubles = vec![std::f64::consts::PI, payload];
+        let wide = Utilities::f64vec_to_bytes(doubles.clone());
+        assert_eq!(wide.len(), 16);
+        assert_eq!(Utilities::f64vec_to_u64vec(Utilities::bytes_to_f64vec(&wide).unwrap()), Utilities::f64vec_to_u64vec(doubles));
+        assert_eq!(Utilities::bytes_to_f64vec(&wide[..12]), Err(ByteLengthError { length: 12, lane_size: 8 }));
+        assert_eq!(Utilities::bytes_to_f64vec(&[]), Ok(vec![]));
+
+        // the bytes interoperate with memory and register APIs
+        let mut memory = Memory::new(0);
+        memory.write_bytes(0x40, &bytes).unwrap();
+        assert_eq!(memory.read::<u32>(0x48), 0x7F80_0001);
+        assert_eq!(memory.read_f32(0x4C).to_bits(), 0x7FC0_1234);
+        memory.write_bytes(0x80, &wide).unwrap();
+        assert_eq!(memory.read::<u64>(0x88), payload.to_bits());
+        let mut registers = Registers::new();
+        let mut zmm = [0u8; 64];
+        zmm[..16].copy_from_slice(&bytes);
+        registers.set_zmm_bytes(0, zmm);
+        let lanes = registers.get_by_sections::<u32>(VecRegName::XMM, 0).unwrap();
+        assert_eq!(lanes, Utilities::f32vec_to_u32vec(floats));
+    }
+
+    #[test]
+    fn test_memory_write_below_base() {
+        let mut memory = Memory::new(0x1000);
+        assert_eq!(memory.write::<u8>(0x10, 1), Err(MemoryError::Unmapped { address: 0x10 }));
+        // a write straddling the base reports its first byte below it and writes nothing
+        assert_eq!(memory.write::<u32>(0xFFE, 0xDEAD_BEEF), Err(MemoryError::Unmapped { address: 0xFFE }));
+        assert!(!memory.is_mapped(0x1000));
+        memory.write::<u8>(0x1000, 1).unwrap();
+
+        let mut limited = Memory::new(0x1000);
+        limited.set_limit(Some(4096));
+        assert_eq!(limited.write::<u8>(0x10, 1), Err(MemoryError::Unmapped { address: 0x10 }));
+        assert_eq!(limited.write_bytes(0x800, &[0; 0x1000]), Err(MemoryError::Unmapped { address: 0x800 }));
+        assert_eq!(limited.mapped_size(), 0);
+    }
+
+    #[test]
+    fn test_setup_stack_below_zero() {
+        let mut cpu = CPU::new(0);
+        assert_eq!(cpu.setup_stack(0x100, 0x1000), Err(MemoryError::AddressOverflow { address: 0x100 }));
+        // the stack fits, but its guard page would not
+        assert_eq!(cpu.setup_stack(0x1800, 0x1000), Err(MemoryError::AddressOverflow { address: 0x800 }));
+        assert!(cpu.memory.mapped_regions().is_empty());
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::RSP), 0);
+        cpu.setup_stack(0x2000, 0x1000).unwrap();
+        assert_eq!(cpu.registers.get_gpr_value(GPRName::RSP), 0x2000);
+    }
+
+    #[test]
+    fn test_memory_alloc_around_mappings() {
+        let mut memory = Memory::new(0x1000);
+        memory.set_arena(0x2000..0x3000).unwrap();
+        memory.map(0x2000..0x2100).unwrap();
+        memory.map_guard(0x2200..0x2300).unwrap();
+        memory.write::<u64>(0x2000, 0x1122_3344_5566_7788).unwrap();
+        let first = memory.alloc(0x100, 1).unwrap();
+        assert_eq!(first, 0x2100);
+        let second = memory.alloc(0x10, 16).unwrap();
+        assert_eq!(second, 0x2300);
+        // the mapped region keeps its contents and allocated blocks start zeroed
+        assert_eq!(memory.read::<u64>(0x2000), 0x1122_3344_5566_7788);
+        assert_eq!(memory.read::<u64>(second), 0);
+        // a freed block is unmapped and handed out again
+        memory.write::<u8>(first, 0xAA).unwrap();
+        memory.free(first).unwrap();
+        assert!(!memory.is_mapped(first));
+        assert_eq!(memory.alloc(0x80, 1).unwrap(), first);
+        assert_eq!(memory.alloc(0xD00, 1), Err(MemoryError::OutOfMemory { size: 0xD00 }));
+    }
+
+    #[test]
+    fn test_memory_equal_to_slice_strict() {
+        let mut memory = Memory::new(0x1000);
+        memory.write_bytes(0x91FE, &[0xAA, 0xBB]).unwrap();
+        memory.write_bytes(0x9400, &[0xCC, 0xDD]).unwrap();
+        assert_eq!(memory.equal_to_slice_strict(0x91FE, &[0xAA, 0xBB]), Ok(None));
+        assert_eq!(memory.equal_to_slice_strict(0x91FE, &[0xAA, 0xBC]), Ok(Some(1)));
+        // the lenient variants read the unmapped gap as zeros, the strict one refuses it
+        assert!(memory.equal_to_slice(0x91FE, &[0xAA, 0xBB, 0, 0]));
+        assert_eq!(memory.equal_to_slice_strict(0x91FE, &[0xAA, 0xBB, 0, 0]), Err(MemoryError::Unmapped { address: 0x9200 }));
+        // a mismatch before the gap does not hide it
+        assert_eq!(memory.equal_to_slice_strict(0x91FE, &[0xAA, 0xBC, 0, 0]), Err(MemoryError::Unmapped { address: 0x9200 }));
+        let mut across = vec![0; 0x204];
+        across[..2].copy_from_slice(&[0xAA, 0xBB]);
+        across[0x202..].copy_from_slice(&[0xCC, 0xDD]);
+        assert!(memory.equal_to_slice(0x91FE, &across));
+        assert!(memory.equal_to_slice_strict(0x91FE, &across).is_err());
+        // poisoned bytes are reported too
+        memory.set_poisoning(true);
+        memory.write::<u8>(0xA000, 7).unwrap();
+        assert_eq!(memory.equal_to_slice_strict(0xA000, &[7]), Ok(None));
+        assert_eq!(memory.equal_to_slice_strict(0xA000, &[7, 0]), Err(MemoryError::Uninitialized { address: 0xA001 }));
+    }
+
+    #[test]
+    fn test_registers_diff() {
+        let a = Registers::new();
+        let mut b = Registers::new();
+        assert!(a.diff(&b).is_empty());
+        b.set_gpr_value(GPRName::AL, 1);
+        b.set_bit(VecRegName::XMM, 3, 0, true);
+        b.set_flags_value(FLAGSName::RFLAGS, a.get_flags_value(FLAGSName::RFLAGS) | 0x1);
+        b.set_ip_value(IPName::RIP, 0x1000);
+        b.set_kmask_value(KMaskName::K7, 0xFF);
+        assert_eq!(a.diff(&b), vec![
+            RegisterRef::Vec(VecRegName::ZMM, 3),
+            RegisterRef::Gpr(GPRName::RAX),
+            RegisterRef::Flags(FLAGSName::RFLAGS),
+            RegisterRef::Ip(IPName::RIP),
+            RegisterRef::KMask(KMaskName::K7),
+        ]);
+        assert_eq!(b.diff(&a), a.diff(&b));
+        // writing back the same value is not a difference
+        b.set_gpr_value(GPRName::RAX, 0);
+        assert!(!b.diff(&a).contains(&RegisterRef::Gpr(GPRName::RAX)));
+    }
+
+    #[test]
+    fn test_memory_diff() {
+        let mut a = Memory::new(0x1000);
+        a.write_bytes(0x1000, &[1, 2, 3, 4]).unwrap();
+        let mut b = a.fork();
+        assert!(a.diff(&b).is_empty());
+        b.write_bytes(0x1001, &[9, 9]).unwrap();
+        b.write::<u8>(0x1010, 0).unwrap();
+        assert_eq!(a.diff(&b), vec![0x1001..0x1003]);
+        // bytes mapped on one side only differ even when they read as zero
+        b.write::<u8>(0x3000, 0).unwrap();
+        a.unmap_range(0x1100..0x1110);
+        assert_eq!(a.diff(&b), vec![0x1001..0x1003, 0x1100..0x1110, 0x3000..0x3200]);
+        assert_eq!(b.diff(&a), a.diff(&b));
+    }
 }
@@ -5,12 +5,43 @@ use primitive_types::U512 as u512;
 extern crate byteorder;
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 
+extern crate bit_vec;
+use bit_vec::BitVec;
+
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::{Display, Formatter, Write as _};
+use std::fs::File;
+use std::hash::Hasher;
+use std::io::{Read, Seek, SeekFrom};
+use std::ops::Range;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::{DeserializeError, Utilities};
+
 /// Trait for memory I/O operations, allowing types to be read from and written
 /// to byte arrays, along with querying their memory size.
 pub trait MemoryIO {
+    /// Builds a value from little endian bytes. Inputs shorter than `size()` are zero-extended
+    /// and bytes past `size()` are ignored, so this never panics on a length mismatch.
     fn from_bytes(bytes: &[u8]) -> Self;
     fn to_bytes(&self) -> Vec<u8>;
     fn size() -> usize;
+    /// The size of the scalar units making up the value. Big-endian memory reverses the bytes
+    /// of each unit rather than of the whole value.
+    fn unit_size() -> usize {
+        Self::size()
+    }
+}
+
+/// Copies `bytes` into a fixed-size buffer, zero-extending short inputs and dropping any excess.
+fn fit_bytes<const N: usize>(bytes: &[u8]) -> [u8; N] {
+    let mut buf = [0; N];
+    let len = bytes.len().min(N);
+    buf[..len].copy_from_slice(&bytes[..len]);
+    buf
 }
 
 /// Macro to implement `MemoryIO` trait for basic unsigned integer types.
@@ -19,7 +50,8 @@ macro_rules! impl_memory_io {
     ($t:ty, $type_str:ident, $size:expr) => {
         impl MemoryIO for $t {
             fn from_bytes(bytes: &[u8]) -> Self {
-                let mut rdr = std::io::Cursor::new(bytes);
+                let buf = fit_bytes::<$size>(bytes);
+                let mut rdr = std::io::Cursor::new(&buf[..]);
                 match stringify!($type_str) {
                     "u8" => rdr.read_u8().unwrap() as $t,
                     "u16" => rdr.read_u16::<LittleEndian>().unwrap() as $t,
@@ -56,11 +88,45 @@ impl_memory_io!(u32, u32, 4);
 impl_memory_io!(u64, u64, 8);
 impl_memory_io!(u128, u128, 16);
 
+/// Macro to implement `MemoryIO` trait for arrays of unsigned integers matching the XMM/YMM/ZMM widths.
+/// Each element is stored in little endian format, with element 0 at the lowest address.
+macro_rules! impl_memory_io_array {
+    ($t:ty, $len:expr) => {
+        impl MemoryIO for [$t; $len] {
+            fn from_bytes(bytes: &[u8]) -> Self {
+                const ELEMENT_SIZE: usize = std::mem::size_of::<$t>();
+                let buf = fit_bytes::<{ ELEMENT_SIZE * $len }>(bytes);
+                let mut values = [0; $len];
+                for (value, chunk) in values.iter_mut().zip(buf.chunks_exact(ELEMENT_SIZE)) {
+                    *value = <$t>::from_le_bytes(chunk.try_into().unwrap());
+                }
+                values
+            }
+
+            fn to_bytes(&self) -> Vec<u8> {
+                self.iter().flat_map(|value| value.to_le_bytes()).collect()
+            }
+
+            fn size() -> usize {
+                std::mem::size_of::<$t>() * $len
+            }
+
+            fn unit_size() -> usize {
+                std::mem::size_of::<$t>()
+            }
+        }
+    };
+}
+
+impl_memory_io_array!(u32, 4);
+impl_memory_io_array!(u32, 8);
+impl_memory_io_array!(u64, 8);
+
 /// Implements `MemoryIO` for `u256` type, enabling conversion between `u256` and byte arrays.
 /// The conversion is handled in little endian format.
 impl MemoryIO for u256 {
     fn from_bytes(bytes: &[u8]) -> Self {
-        u256::from_little_endian(bytes)
+        u256::from_little_endian(&fit_bytes::<32>(bytes))
     }
 
     fn to_bytes(&self) -> Vec<u8> {
@@ -78,7 +144,7 @@ impl MemoryIO for u256 {
 /// The conversion is handled in little endian format.
 impl MemoryIO for u512 {
     fn from_bytes(bytes: &[u8]) -> Self {
-        u512::from_little_endian(bytes)
+        u512::from_little_endian(&fit_bytes::<64>(bytes))
     }
 
     fn to_bytes(&self) -> Vec<u8> {
@@ -93,19 +159,480 @@ impl MemoryIO for u512 {
 }
 
 const DEFAULT_SIZE: usize = 512; // 512 bytes
+const STACK_BUFFER_SIZE: usize = 64; // large enough for u512
+const CHUNK_SIZE: usize = 4096; // bytes read at a time when scanning ranges
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+const PAGE_TABLE_ENTRIES: u64 = 512; // entries in each second-level page table
+const DUMP_MAGIC: [u8; 8] = *b"CPULIBMD";
+const DUMP_VERSION: u32 = 1;
+
+/// An enumeration of errors reported by fallible memory operations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MemoryError {
+    /// A write touched a byte inside a read-only region.
+    WriteProtected { address: u64 },
+    /// A new mapping overlaps memory that is already mapped.
+    Overlap { address: u64 },
+    /// A strict read touched a byte that is not mapped, or an access targeted an address below the base address.
+    Unmapped { address: u64 },
+    /// The address translator refused an access.
+    TranslationFault { address: u64, kind: AccessKind },
+    /// Mapping the memory needed by an access would exceed the configured limit.
+    LimitExceeded { address: u64, limit: usize },
+    /// The allocator has no free block of the requested size.
+    OutOfMemory { size: usize },
+    /// A free named an address that is not the start of an outstanding allocation.
+    InvalidFree { address: u64 },
+    /// The program break would move below its initial position.
+    InvalidBreak { address: u64 },
+    /// An access or mapping ran outside the 64-bit address space: its end address does not fit in a
+    /// `u64`, or, for a range given by its end and size, its start would lie below 0.
+    AddressOverflow { address: u64 },
+    /// A memory dump is malformed at the given offset: bad magic or version, truncated, or a record below the base address.
+    InvalidDump { offset: usize },
+    /// Reading the file backing a mapping failed.
+    FileError { kind: std::io::ErrorKind },
+    /// An access required an alignment its address does not have.
+    Misaligned { address: u64, alignment: usize },
+    /// An access touched a byte inside a guard region.
+    GuardHit { address: u64 },
+    /// The page table has no mapping for an address, or the mapping does not permit the access.
+    PageFault { address: u64, kind: AccessKind, present: bool },
+    /// A strict read touched a byte that was never written while poisoning was enabled.
+    Uninitialized { address: u64 },
+}
+
+/// Implements the `Display` trait for `MemoryError`.
+///
+/// Produces a short human-readable description including the faulting address.
+impl Display for MemoryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MemoryError::WriteProtected { address } => write!(f, "write to protected address {:#x}", address),
+            MemoryError::Overlap { address } => write!(f, "mapping overlaps existing memory at {:#x}", address),
+            MemoryError::Unmapped { address } => write!(f, "access to unmapped address {:#x}", address),
+            MemoryError::TranslationFault { address, kind } => write!(f, "translation fault on {:?} at {:#x}", kind, address),
+            MemoryError::LimitExceeded { address, limit } => write!(f, "memory limit of {} bytes exceeded at {:#x}", limit, address),
+            MemoryError::OutOfMemory { size } => write!(f, "out of memory allocating {} bytes", size),
+            MemoryError::InvalidFree { address } => write!(f, "free of unallocated address {:#x}", address),
+            MemoryError::InvalidBreak { address } => write!(f, "program break {:#x} is below the initial break", address),
+            MemoryError::AddressOverflow { address } => write!(f, "access at {:#x} runs outside the address space", address),
+            MemoryError::InvalidDump { offset } => write!(f, "invalid memory dump at offset {}", offset),
+            MemoryError::FileError { kind } => write!(f, "failed to read mapped file: {}", kind),
+            MemoryError::Misaligned { address, alignment } => write!(f, "address {:#x} is not {}-byte aligned", address, alignment),
+            MemoryError::GuardHit { address } => write!(f, "access to guard region at {:#x}", address),
+            MemoryError::PageFault { address, kind, present } => write!(f, "page fault on {:?} at {:#x} ({})", kind, address,
+                                                                        if *present { "protection" } else { "not present" }),
+            MemoryError::Uninitialized { address } => write!(f, "read from uninitialized address {:#x}", address),
+        }
+    }
+}
+
+impl std::error::Error for MemoryError {}
+
+/// The error reported by an access whose address is not naturally aligned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AlignmentError {
+    /// The address of the access.
+    pub address: u64,
+    /// The alignment the access requires, in bytes.
+    pub required_alignment: usize,
+}
+
+/// Implements the `Display` trait for `AlignmentError`.
+impl Display for AlignmentError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "address {:#x} is not {}-byte aligned", self.address, self.required_alignment)
+    }
+}
+
+impl std::error::Error for AlignmentError {}
+
+/// Converts an `AlignmentError` into the equivalent `MemoryError::Misaligned`.
+impl From<AlignmentError> for MemoryError {
+    fn from(error: AlignmentError) -> Self {
+        MemoryError::Misaligned { address: error.address, alignment: error.required_alignment }
+    }
+}
+
+/// An enumeration of access protections that can be applied to a memory range.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Protection {
+    ReadWrite, ReadOnly
+}
+
+/// Trait for devices handling memory-mapped I/O accesses.
+///
+/// Offsets passed to the handler are relative to the start of the region it was mapped at.
+pub trait MmioHandler {
+    /// Handles a read of `size` bytes (at most 8) at `offset`, returning the value in the low bytes.
+    fn read(&mut self, offset: u64, size: usize) -> u64;
+    /// Handles a write of `data` at `offset`.
+    fn write(&mut self, offset: u64, data: &[u8]);
+}
+
+/// An enumeration of the kinds of memory access seen by an address translator.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum AccessKind {
+    Read, Write, Execute
+}
+
+/// The signature of an address-translation hook installed with `Memory::set_translator`.
+pub type Translator = dyn Fn(u64, AccessKind) -> Result<u64, MemoryError>;
+
+/// The signature of a handler installed with `Memory::set_uninit_handler`, called with the address
+/// of the first uninitialized byte of a lenient read.
+pub type UninitHandler = dyn Fn(u64);
+
+/// The permissions of a page mapped in a `PageTable`.
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+pub struct PagePerms {
+    pub read: bool,
+    pub write: bool,
+    pub execute: bool,
+}
+
+impl PagePerms {
+    /// Returns whether the permissions allow an access of the given kind.
+    pub fn allows(&self, kind: AccessKind) -> bool {
+        match kind {
+            AccessKind::Read => self.read,
+            AccessKind::Write => self.write,
+            AccessKind::Execute => self.execute,
+        }
+    }
+}
+
+/// The error reported when a `PageTable` can't translate an access.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageFaultError {
+    /// The virtual address of the access.
+    pub address: u64,
+    /// The kind of the access.
+    pub kind: AccessKind,
+    /// `true` if the page is mapped but its permissions forbid the access, `false` if it is not mapped.
+    pub present: bool,
+}
+
+/// Implements the `Display` trait for `PageFaultError`.
+impl Display for PageFaultError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "page fault on {:?} at {:#x} ({})", self.kind, self.address,
+               if self.present { "protection" } else { "not present" })
+    }
+}
+
+impl std::error::Error for PageFaultError {}
+
+/// Converts a `PageFaultError` into the equivalent `MemoryError::PageFault`.
+impl From<PageFaultError> for MemoryError {
+    fn from(error: PageFaultError) -> Self {
+        MemoryError::PageFault { address: error.address, kind: error.kind, present: error.present }
+    }
+}
+
+/// Represents a mapping from a virtual page to a physical page.
+#[derive(Debug, Clone, Copy)]
+struct PageEntry {
+    physical_page: u64,
+    permissions: PagePerms,
+}
+
+/// A two-level page table translating virtual addresses to physical addresses.
+///
+/// A virtual page number is split into a directory index (the high bits) and a table index
+/// (the low 9 bits); second-level tables are only created for directory entries that are used.
+#[derive(Debug, Clone)]
+pub struct PageTable {
+    page_size: u64,
+    directory: HashMap<u64, Vec<Option<PageEntry>>>,
+}
+
+impl PageTable {
+    /// Creates an empty page table.
+    ///
+    /// # Arguments
+    /// * `page_size` - The size of a page in bytes; must be a power of two.
+    ///
+    /// # Panics
+    /// Panics if `page_size` is not a power of two.
+    pub fn new(page_size: usize) -> Self {
+        assert!(page_size.is_power_of_two(), "page size must be a power of two");
+        PageTable {
+            page_size: page_size as u64,
+            directory: HashMap::new(),
+        }
+    }
+
+    /// Returns the size of a page in bytes.
+    pub fn page_size(&self) -> usize {
+        self.page_size as usize
+    }
+
+    /// Maps a virtual page to a physical page, replacing any previous mapping of the virtual page.
+    ///
+    /// # Arguments
+    /// * `virtual_page` - The virtual page number, i.e. the virtual address divided by the page size.
+    /// * `physical_page` - The physical page number it maps to.
+    /// * `permissions` - The accesses allowed on the page.
+    pub fn map_page(&mut self, virtual_page: u64, physical_page: u64, permissions: PagePerms) {
+        let table = self.directory
+            .entry(virtual_page / PAGE_TABLE_ENTRIES)
+            .or_insert_with(|| vec![None; PAGE_TABLE_ENTRIES as usize]);
+        table[(virtual_page % PAGE_TABLE_ENTRIES) as usize] = Some(PageEntry { physical_page, permissions });
+    }
+
+    /// Removes the mapping of a virtual page, if any.
+    ///
+    /// # Arguments
+    /// * `virtual_page` - The virtual page number to unmap.
+    pub fn unmap_page(&mut self, virtual_page: u64) {
+        let directory_index = virtual_page / PAGE_TABLE_ENTRIES;
+        if let Some(table) = self.directory.get_mut(&directory_index) {
+            table[(virtual_page % PAGE_TABLE_ENTRIES) as usize] = None;
+            if table.iter().all(Option::is_none) {
+                self.directory.remove(&directory_index);
+            }
+        }
+    }
+
+    /// Looks up the entry mapping a virtual page.
+    fn entry(&self, virtual_page: u64) -> Option<&PageEntry> {
+        self.directory
+            .get(&(virtual_page / PAGE_TABLE_ENTRIES))
+            .and_then(|table| table[(virtual_page % PAGE_TABLE_ENTRIES) as usize].as_ref())
+    }
+
+    /// Translates a virtual address as a read.
+    ///
+    /// # Arguments
+    /// * `virtual_address` - The address to translate.
+    ///
+    /// # Returns
+    /// The physical address, or a `PageFaultError` if the page is not mapped or is not readable.
+    pub fn translate(&self, virtual_address: u64) -> Result<u64, PageFaultError> {
+        self.translate_access(virtual_address, AccessKind::Read)
+    }
+
+    /// Translates a virtual address for an access of the given kind.
+    ///
+    /// # Arguments
+    /// * `virtual_address` - The address to translate.
+    /// * `kind` - The kind of access being performed.
+    ///
+    /// # Returns
+    /// The physical address, or a `PageFaultError` if the page is not mapped or its permissions forbid the access.
+    pub fn translate_access(&self, virtual_address: u64, kind: AccessKind) -> Result<u64, PageFaultError> {
+        let entry = self.entry(virtual_address / self.page_size)
+            .ok_or(PageFaultError { address: virtual_address, kind, present: false })?;
+        if !entry.permissions.allows(kind) {
+            return Err(PageFaultError { address: virtual_address, kind, present: true });
+        }
+        Ok(entry.physical_page * self.page_size + virtual_address % self.page_size)
+    }
+}
+
+/// An enumeration of the byte orders values can be stored in.
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+pub enum Endianness {
+    #[default]
+    Little,
+    Big,
+}
+
+/// Describes how fragmented the RAM of a `Memory` is, as reported by `Memory::fragmentation`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FragReport {
+    /// The number of RAM segments.
+    pub segment_count: usize,
+    /// The number of unmapped gaps between consecutive segments.
+    pub gap_count: usize,
+    /// The total size in bytes of the gaps between consecutive segments.
+    pub gap_bytes: u64,
+    /// The average size of a segment in bytes, or 0 if there are none.
+    pub average_segment_size: usize,
+}
+
+/// Selects which mappings besides RAM are dropped by `Memory::clear_with`.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct ClearOptions {
+    /// Also removes every memory-mapped I/O region.
+    pub mmio: bool,
+    /// Also lifts every read-only protection.
+    pub protection: bool,
+    /// Also removes every guard region.
+    pub guards: bool,
+}
+
+/// An enumeration of the kinds of region reported by `Memory::regions`.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum RegionKind {
+    Ram, Mmio, Guard
+}
+
+/// Describes a contiguous range of the address space and what occupies it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Region {
+    /// The half-open range of addresses covered by the region.
+    pub range: Range<u64>,
+    /// What occupies the range.
+    pub kind: RegionKind,
+}
+
+/// Represents a range of addresses served by an `MmioHandler` instead of RAM.
+struct MmioRegion {
+    range: Range<u64>,
+    handler: RefCell<Box<dyn MmioHandler>>,
+}
+
+/// Records the pages written while dirty tracking is enabled.
+#[derive(Clone)]
+struct DirtyPages {
+    page_size: u64,
+    pages: BTreeSet<u64>,
+}
+
+/// Represents a block handed out by `Memory::alloc`.
+#[derive(Clone)]
+struct Allocation {
+    range: Range<u64>,
+}
 
 /// Represents a segment of memory with a start address and data content.
 /// Used to manage discrete blocks of memory within a larger memory structure.
+/// The data is shared between forks of a `Memory` until one of them writes to it.
+#[derive(Clone, Default)]
 struct MemorySegment {
-    start_address: usize,
-    data: Vec<u8>,
+    start_address: u64,
+    data: Arc<Vec<u8>>,
+    /// Marks the bytes never written while poisoning was enabled; `None` if every byte is initialized.
+    uninit: Option<BitVec>,
+}
+
+impl MemorySegment {
+    /// Creates a segment whose bytes are all initialized.
+    fn new(start_address: u64, data: Vec<u8>) -> Self {
+        MemorySegment { start_address, data: Arc::new(data), uninit: None }
+    }
+
+    /// Creates a zero-filled segment whose bytes are all uninitialized if `poisoned` is set.
+    fn zeroed(start_address: u64, length: usize, poisoned: bool) -> Self {
+        MemorySegment {
+            start_address,
+            data: Arc::new(vec![0; length]),
+            uninit: poisoned.then(|| BitVec::from_elem(length, true)),
+        }
+    }
+
+    /// Returns the real address one past the last byte of the segment.
+    fn end(&self) -> u64 {
+        self.start_address + self.data.len() as u64
+    }
+
+    /// Copies part of the segment into a new segment.
+    ///
+    /// # Arguments
+    /// * `range` - The range of offsets inside the segment to copy.
+    fn slice(&self, range: Range<usize>) -> MemorySegment {
+        MemorySegment {
+            start_address: self.start_address + range.start as u64,
+            data: Arc::new(self.data[range.clone()].to_vec()),
+            uninit: self.uninit.as_ref().map(|uninit| range.map(|i| uninit[i]).collect()),
+        }
+    }
+
+    /// Appends a segment starting right at the end of this one.
+    fn append(&mut self, next: MemorySegment) {
+        if self.uninit.is_some() || next.uninit.is_some() {
+            let mut uninit = self.uninit.take().unwrap_or_else(|| BitVec::from_elem(self.data.len(), false));
+            uninit.extend(next.uninit.unwrap_or_else(|| BitVec::from_elem(next.data.len(), false)).iter());
+            self.uninit = Some(uninit);
+        }
+        self.data_mut().extend_from_slice(&next.data);
+    }
+
+    /// Returns the data for writing, first copying it if it is shared with a fork.
+    fn data_mut(&mut self) -> &mut Vec<u8> {
+        Arc::make_mut(&mut self.data)
+    }
+
+    /// Marks a run of bytes as initialized.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset of the first byte inside the segment.
+    /// * `length` - The number of bytes in the run.
+    fn set_initialized(&mut self, offset: usize, length: usize) {
+        if let Some(uninit) = &mut self.uninit {
+            for i in offset..offset + length {
+                uninit.set(i, false);
+            }
+        }
+    }
+
+    /// Returns the offset of the first uninitialized byte of a run, if any.
+    ///
+    /// # Arguments
+    /// * `offset` - The offset of the first byte inside the segment.
+    /// * `length` - The number of bytes in the run.
+    fn first_uninit(&self, offset: usize, length: usize) -> Option<usize> {
+        let uninit = self.uninit.as_ref()?;
+        (offset..offset + length).find(|&i| uninit[i])
+    }
+}
+
+/// Converts the length of a guest address range to a host buffer length.
+///
+/// # Panics
+/// Panics if the range is longer than the host can address.
+fn host_length(range: &Range<u64>) -> usize {
+    usize::try_from(range.end.saturating_sub(range.start)).expect("range is too large for the host")
+}
+
+/// Takes the next `length` bytes of a serialized input.
+///
+/// # Arguments
+/// * `bytes` - The whole input.
+/// * `offset` - The offset of the field, advanced past it on success.
+/// * `length` - The length of the field in bytes.
+///
+/// # Returns
+/// The bytes of the field, or `DeserializeError::Truncated` if the input ends first.
+fn take_bytes<'a>(bytes: &'a [u8], offset: &mut usize, length: u64) -> Result<&'a [u8], DeserializeError> {
+    let end = usize::try_from(length).ok()
+        .and_then(|length| offset.checked_add(length))
+        .filter(|end| *end <= bytes.len())
+        .ok_or(DeserializeError::Truncated { offset: *offset })?;
+    let field = &bytes[*offset..end];
+    *offset = end;
+    Ok(field)
+}
+
+/// Takes the next little-endian `u64` of a serialized input.
+fn take_u64(bytes: &[u8], offset: &mut usize) -> Result<u64, DeserializeError> {
+    take_bytes(bytes, offset, 8).map(|field| u64::from_le_bytes(field.try_into().unwrap()))
 }
 
 /// Represents a memory model with segmented memory blocks.
 /// Provides functionality for reading and writing data to specific memory addresses.
 pub struct Memory {
     segments: Vec<MemorySegment>,
-    pub base_address: usize,
+    read_only: Vec<Range<u64>>,
+    mmio: Vec<MmioRegion>,
+    guards: Vec<Range<u64>>,
+    translator: Option<Box<Translator>>,
+    paging: Option<PageTable>,
+    limit: Option<usize>,
+    strict: bool,
+    arena: Option<Range<u64>>,
+    allocations: Vec<Allocation>,
+    initial_brk: u64,
+    brk: u64,
+    dirty: Option<DirtyPages>,
+    endianness: Endianness,
+    poisoning: bool,
+    uninit_handler: Option<Box<UninitHandler>>,
+    pub base_address: u64,
 }
 
 impl Memory {
@@ -118,169 +645,2356 @@ impl Memory {
     ///
     /// # Returns
     /// A new `Memory` instance with the specified base address.
-    pub fn new(base: usize) -> Self {
+    pub fn new(base: u64) -> Self {
         Memory {
             segments: Vec::new(),
+            read_only: Vec::new(),
+            mmio: Vec::new(),
+            guards: Vec::new(),
+            translator: None,
+            paging: None,
+            limit: None,
+            strict: false,
+            arena: None,
+            allocations: Vec::new(),
+            initial_brk: 0,
+            brk: 0,
+            dirty: None,
+            endianness: Endianness::Little,
+            poisoning: false,
+            uninit_handler: None,
             base_address: base,
         }
     }
 
-    /// Searches for a memory segment that contains a specified real address.
+    /// Creates a new instance of `Memory` storing values in the given byte order.
     ///
-    /// Iterates through the memory segments to find a segment where the real address falls within
-    /// the segment's range (from its start address to the end of its data).
+    /// The byte order applies to every typed access (`read`, `write`, `read_vec`, `write_vec` and the
+    /// methods built on them); raw byte accesses such as `read_exact` and `write_bytes` are unaffected.
     ///
     /// # Arguments
-    /// * `real_address` - The real memory address to locate within the segments.
+    /// * `base` - The base address from which all memory addresses will be calculated.
+    /// * `endianness` - The byte order of typed accesses.
     ///
     /// # Returns
-    /// An `Option<usize>` representing the index of the found memory segment in the `segments` vector.
-    /// Returns `None` if no suitable segment is found.
-    fn find_segment(&self, real_address: usize) -> Option<usize> {
-        for (index, segment) in self.segments.iter().enumerate() {
-            if real_address >= segment.start_address && real_address < segment.start_address + segment.data.len() {
-                return Some(index);
-            }
+    /// A new `Memory` instance with the specified base address and byte order.
+    pub fn new_with_endianness(base: u64, endianness: Endianness) -> Self {
+        Memory {
+            endianness,
+            ..Memory::new(base)
         }
-        None
     }
 
-    /// Reads a single byte from memory at a given address.
+    /// Creates a copy-on-write fork of the memory.
     ///
-    /// Calculates the real address by subtracting the base address from the given address.
-    /// If the address is within a memory segment, returns the byte at the calculated offset within the segment.
-    /// If the address is not mapped to any segment, returns 0.
+    /// The fork starts with the same contents, protections, guard regions, page table, allocator state,
+    /// dirty pages and settings. Segment data is shared between the two instances until either writes
+    /// to a segment, at which point the writer gets its own copy of that segment, so neither instance
+    /// ever observes the other's writes after the fork. MMIO regions, the translator and the
+    /// uninitialized-read handler can't be duplicated and are not carried over.
+    pub fn fork(&self) -> Memory {
+        Memory {
+            segments: self.segments.clone(),
+            read_only: self.read_only.clone(),
+            mmio: Vec::new(),
+            guards: self.guards.clone(),
+            translator: None,
+            paging: self.paging.clone(),
+            limit: self.limit,
+            strict: self.strict,
+            arena: self.arena.clone(),
+            allocations: self.allocations.clone(),
+            initial_brk: self.initial_brk,
+            brk: self.brk,
+            dirty: self.dirty.clone(),
+            endianness: self.endianness,
+            poisoning: self.poisoning,
+            uninit_handler: None,
+            base_address: self.base_address,
+        }
+    }
+
+    /// Returns how many instances share the data of the segment holding an address, for tests of `fork`.
+    #[cfg(test)]
+    pub(crate) fn segment_share_count(&self, address: u64) -> Option<usize> {
+        let index = self.find_segment(address.checked_sub(self.base_address)?)?;
+        Some(Arc::strong_count(&self.segments[index].data))
+    }
+
+    /// Serializes the base address and RAM segments into a deterministic binary form.
+    ///
+    /// Every integer is a little-endian `u64`. The output holds the base address, the number of segments
+    /// and then, for each segment in ascending order of address, its start address (a guest address,
+    /// not relative to the base), its length in bytes and its data. Contiguous segments are always merged,
+    /// so the same contents always produce the same bytes. MMIO and guard regions, protections and all
+    /// other settings are not included.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(16 + self.segments.len() * 16 + self.mapped_size());
+        bytes.extend(self.base_address.to_le_bytes());
+        bytes.extend((self.segments.len() as u64).to_le_bytes());
+        for segment in &self.segments {
+            bytes.extend((segment.start_address + self.base_address).to_le_bytes());
+            bytes.extend((segment.data.len() as u64).to_le_bytes());
+            bytes.extend_from_slice(&segment.data);
+        }
+        bytes
+    }
+
+    /// Deserializes memory from the binary form produced by `to_bytes`.
     ///
     /// # Arguments
-    /// * `address` - The address from which to read the byte.
+    /// * `bytes` - The serialized memory.
     ///
     /// # Returns
-    /// The byte value at the given address, or 0 if the address is not mapped.
-    fn read_byte(&self, address: usize) -> u8 {
-        let real_address = address - self.base_address;
-        if let Some(index) = self.find_segment(real_address) {
-            self.segments[index].data[real_address - self.segments[index].start_address]
-        } else {
-            // return 0 if the address is not found
-            0
+    /// A lenient `Memory` holding the serialized segments, `DeserializeError::Truncated` if the input ends
+    /// early, `DeserializeError::InvalidSegment` if the segments are out of order, overlap or lie below
+    /// the base address, or `DeserializeError::InvalidLength` if bytes are left over.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Memory, DeserializeError> {
+        let mut offset = 0;
+        let base = take_u64(bytes, &mut offset)?;
+        let count = take_u64(bytes, &mut offset)?;
+        let mut memory = Memory::new(base);
+        let mut next_free = base;
+        for _ in 0..count {
+            let start = take_u64(bytes, &mut offset)?;
+            let length = take_u64(bytes, &mut offset)?;
+            let end = start.checked_add(length).ok_or(DeserializeError::InvalidSegment { address: start })?;
+            if start < next_free || length == 0 {
+                return Err(DeserializeError::InvalidSegment { address: start });
+            }
+            let data = take_bytes(bytes, &mut offset, length)?;
+            memory.segments.push(MemorySegment::new(start - base, data.to_vec()));
+            next_free = end;
         }
+        if offset != bytes.len() {
+            return Err(DeserializeError::InvalidLength { expected: offset, actual: bytes.len() });
+        }
+        memory.merge_segments();
+        Ok(memory)
     }
 
-    /// Writes a single byte to memory at a given address.
+    /// Exports the RAM as a portable memory dump.
     ///
-    /// Calculates the real address by subtracting the base address from the given address.
-    /// If a segment containing the address exists, updates the byte at the specific offset.
-    /// If no segment contains the address, a new segment is created and added to the memory.
-    /// Segments are automatically merged if they become contiguous after the write operation.
+    /// The dump starts with an 8-byte magic `CPULIBMD`, a little-endian `u32` format version (currently 1)
+    /// and the little-endian `u64` base address. One record per segment follows, in ascending order of
+    /// address, until the end of the dump: the little-endian `u64` start address (a guest address), the
+    /// little-endian `u64` length and then the bytes of the segment. Only RAM is included.
+    pub fn export_dump(&self) -> Vec<u8> {
+        let mut dump = Vec::with_capacity(20 + self.segments.len() * 16 + self.mapped_size());
+        dump.extend(DUMP_MAGIC);
+        dump.extend(DUMP_VERSION.to_le_bytes());
+        dump.extend(self.base_address.to_le_bytes());
+        for segment in &self.segments {
+            dump.extend((segment.start_address + self.base_address).to_le_bytes());
+            dump.extend((segment.data.len() as u64).to_le_bytes());
+            dump.extend_from_slice(&segment.data);
+        }
+        dump
+    }
+
+    /// Rebuilds memory from a dump produced by `export_dump`.
+    ///
+    /// Records may appear in any order but must not overlap.
     ///
     /// # Arguments
-    /// * `address` - The address at which to write the byte.
-    /// * `value` - The byte value to write.
-    fn write_byte(&mut self, address: usize, value: u8) {
-        let real_address = address - self.base_address;
-        if let Some(index) = self.find_segment(real_address) {
-            let start = self.segments[index].start_address;
-            self.segments[index].data[real_address - start] = value;
-        } else {
-            let adjusted_address = (real_address / DEFAULT_SIZE) * DEFAULT_SIZE;
-            let mut new_data = Vec::with_capacity(DEFAULT_SIZE);
-            new_data.resize(DEFAULT_SIZE, 0);
-            new_data[real_address - adjusted_address] = value;
-            let new_segment = MemorySegment {
-                start_address: adjusted_address,
-                data: new_data,
-            };
-            self.segments.push(new_segment);
-            // sort by address
-            self.segments.sort_by(|a, b| a.start_address.cmp(&b.start_address));
+    /// * `dump` - The memory dump.
+    ///
+    /// # Returns
+    /// A lenient `Memory` holding the dumped RAM, `MemoryError::InvalidDump` carrying the offset of the
+    /// offending field if the dump has a bad magic or version, is truncated or has a record below the base
+    /// address or past the end of the address space, or `MemoryError::Overlap` if two records overlap.
+    pub fn import_dump(dump: &[u8]) -> Result<Memory, MemoryError> {
+        let invalid = |error: DeserializeError| match error {
+            DeserializeError::Truncated { offset } => MemoryError::InvalidDump { offset },
+            _ => MemoryError::InvalidDump { offset: 0 },
+        };
+        if dump.get(..8) != Some(&DUMP_MAGIC[..]) {
+            return Err(MemoryError::InvalidDump { offset: 0 });
         }
-        // merge segments if they are contiguous
-        let mut i = 0;
-        while i + 1 < self.segments.len() {
-            if self.segments[i].start_address + self.segments[i].data.len() == self.segments[i + 1].start_address {
-                let next = self.segments.remove(i + 1);
-                self.segments[i].data.extend(next.data);
-            } else {
-                i += 1;
+        let mut offset = 8;
+        let version = take_bytes(dump, &mut offset, 4).map_err(invalid)?;
+        if version != DUMP_VERSION.to_le_bytes() {
+            return Err(MemoryError::InvalidDump { offset: 8 });
+        }
+        let base = take_u64(dump, &mut offset).map_err(invalid)?;
+        let mut memory = Memory::new(base);
+        while offset < dump.len() {
+            let record = offset;
+            let start = take_u64(dump, &mut offset).map_err(invalid)?;
+            let length = take_u64(dump, &mut offset).map_err(invalid)?;
+            if start < base || start.checked_add(length).is_none() {
+                return Err(MemoryError::InvalidDump { offset: record });
             }
+            let data = take_bytes(dump, &mut offset, length).map_err(invalid)?;
+            if !data.is_empty() {
+                memory.segments.push(MemorySegment::new(start - base, data.to_vec()));
+            }
+        }
+        memory.segments.sort_by_key(|segment| segment.start_address);
+        if let Some(pair) = memory.segments.windows(2).find(|pair| pair[0].end() > pair[1].start_address) {
+            return Err(MemoryError::Overlap { address: pair[1].start_address + base });
         }
+        memory.merge_segments();
+        Ok(memory)
     }
 
-    /// Reads a value of type `T` from memory starting at a given address.
-    ///
-    /// Reads bytes sequentially starting from the address and constructs a value of type `T` using `MemoryIO` trait.
-    /// The number of bytes read is determined by the size of type `T`.
-    ///
-    /// # Type Parameters
-    /// * `T` - The type implementing `MemoryIO` that determines how bytes are read and interpreted.
+    /// Returns the byte order of typed accesses.
+    pub fn endianness(&self) -> Endianness {
+        self.endianness
+    }
+
+    /// Converts the little-endian encoding of a `T` value to or from the byte order of the memory.
     ///
     /// # Arguments
-    /// * `address` - The starting address from which to read the bytes.
-    ///
-    /// # Returns
-    /// A value of type `T` constructed from the read bytes.
-    pub fn read<T: MemoryIO>(&self, address: usize) -> T {
-        let mut bytes = Vec::new();
-        for i in 0..T::size() {
-            bytes.push(self.read_byte(address + i));
+    /// * `bytes` - The encoded value, converted in place.
+    fn apply_endianness<T: MemoryIO>(&self, bytes: &mut [u8]) {
+        if self.endianness == Endianness::Big {
+            for unit in bytes.chunks_mut(T::unit_size()) {
+                unit.reverse();
+            }
         }
-        T::from_bytes(&bytes)
     }
 
-    /// Writes a value of type `T` to memory starting at a given address.
+    /// Enables or disables strict mode.
     ///
-    /// Converts the value to a byte array using `MemoryIO` trait and writes the bytes sequentially to memory.
-    /// Each byte is written to consecutive memory addresses starting from the specified address.
+    /// In strict mode `try_read` reports reads of unmapped bytes as errors instead of returning 0 for them.
     ///
-    /// # Type Parameters
-    /// * `T` - The type implementing `MemoryIO` that determines how the value is converted to bytes.
+    /// # Arguments
+    /// * `strict` - `true` to enable strict mode, `false` to restore the lenient default.
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    /// Returns whether strict mode is enabled.
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Enables or disables poisoning of uninitialized memory.
+    ///
+    /// While poisoning is enabled, the bytes of segments created implicitly by writes, and of gaps filled by
+    /// `defragment`, start out uninitialized; writing a byte initializes it. `map`, `reserve` and `fill`
+    /// initialize their whole range. Reading an uninitialized byte fails with `MemoryError::Uninitialized`
+    /// when the read is strict, and otherwise calls the handler installed with `set_uninit_handler`.
+    /// Disabling poisoning marks every byte initialized.
     ///
     /// # Arguments
-    /// * `address` - The starting address at which to write the bytes.
-    /// * `value` - The value of type `T` to write to memory.
-    pub fn write<T: MemoryIO>(&mut self, address: usize, value: T) {
-        let bytes = value.to_bytes();
-        for (i, byte) in bytes.iter().enumerate() {
-            self.write_byte(address + i, *byte);
+    /// * `enabled` - `true` to enable poisoning, `false` to disable it (the default).
+    pub fn set_poisoning(&mut self, enabled: bool) {
+        self.poisoning = enabled;
+        if !enabled {
+            for segment in &mut self.segments {
+                segment.uninit = None;
+            }
         }
     }
 
-    /// Reads a vector of values of type `T` from memory starting at a given address.
-    ///
-    /// Reads multiple values sequentially from memory. The number of values read is determined by `number_of_value`.
-    /// Each value is read from a block of memory with a size equal to `T::size()`.
+    /// Returns whether poisoning of uninitialized memory is enabled.
+    pub fn is_poisoning(&self) -> bool {
+        self.poisoning
+    }
+
+    /// Installs a handler called when a lenient read touches uninitialized memory.
     ///
-    /// # Type Parameters
-    /// * `T` - The type implementing `MemoryIO` that determines how bytes are read and interpreted.
+    /// The handler is called once per access, with the address of its first uninitialized byte.
     ///
     /// # Arguments
-    /// * `address` - The starting address from which to begin reading values.
-    /// * `number_of_value` - The number of values to read.
+    /// * `handler` - The function receiving the address.
+    pub fn set_uninit_handler(&mut self, handler: Box<UninitHandler>) {
+        self.uninit_handler = Some(handler);
+    }
+
+    /// Removes the handler installed with `set_uninit_handler`.
+    pub fn clear_uninit_handler(&mut self) {
+        self.uninit_handler = None;
+    }
+
+    /// Sets the maximum number of bytes of RAM that may be mapped.
     ///
-    /// # Returns
-    /// A vector of values of type `T`.
-    pub fn read_vec<T: MemoryIO>(&self, address: usize, number_of_value: usize) -> Vec<T> {
-        let mut result: Vec<T> = vec![];
-        for i in 0..number_of_value {
-            result.push(self.read(address + i * T::size()));
+    /// Writes that would need new segments beyond the limit fail with `MemoryError::LimitExceeded`.
+    /// Such writes are all-or-nothing: the limit is checked before any byte is stored, so a failing
+    /// write leaves memory exactly as it was. Memory already mapped is never released by lowering the limit.
+    ///
+    /// # Arguments
+    /// * `limit` - The maximum mapped size in bytes, or `None` for no limit (the default).
+    pub fn set_limit(&mut self, limit: Option<usize>) {
+        self.limit = limit;
+    }
+
+    /// Returns the configured limit on the mapped size, if any.
+    pub fn limit(&self) -> Option<usize> {
+        self.limit
+    }
+
+    /// Returns the total number of bytes of RAM currently mapped by segments.
+    pub fn mapped_size(&self) -> usize {
+        self.segments.iter().map(|segment| segment.data.len()).sum()
+    }
+
+    /// Reports how fragmented the RAM segments are.
+    pub fn fragmentation(&self) -> FragReport {
+        let gaps = self.segments.windows(2).map(|pair| pair[1].start_address - pair[0].end());
+        FragReport {
+            segment_count: self.segments.len(),
+            gap_count: gaps.clone().count(),
+            gap_bytes: gaps.sum(),
+            average_segment_size: self.mapped_size().checked_div(self.segments.len()).unwrap_or(0),
         }
-        result
     }
 
-    /// Writes a vector of values of type `T` to memory starting at a given address.
+    /// Coalesces segments separated by small gaps, making lookups cheaper.
     ///
-    /// Sequentially writes each value in the vector to memory. The address for each subsequent value
-    /// is offset by `T::size()` bytes from the previous one.
+    /// Every gap of at most `max_gap` bytes between two segments is mapped as zero-filled RAM and the
+    /// segments on either side are merged. The only observable change is that unmapped bytes in such
+    /// gaps become mapped zeros: they already read as 0 in lenient mode, but strict reads of them now
+    /// succeed and they count towards `mapped_size`. Gaps touching an MMIO or guard region are left alone,
+    /// as are gaps that would take the mapped size past the limit.
+    ///
+    /// # Arguments
+    /// * `max_gap` - The largest gap in bytes that is filled.
+    pub fn defragment(&mut self, max_gap: u64) {
+        let mut mapped = self.mapped_size();
+        let mut segments: Vec<MemorySegment> = Vec::with_capacity(self.segments.len());
+        for segment in std::mem::take(&mut self.segments) {
+            if let Some(last) = segments.last_mut() {
+                let gap = last.end()..segment.start_address;
+                let guest_gap = gap.start + self.base_address..gap.end + self.base_address;
+                let fits = self.limit.is_none_or(|limit| mapped + host_length(&gap) <= limit);
+                if gap.end - gap.start <= max_gap && fits && self.check_unused(&guest_gap).is_ok() {
+                    mapped += host_length(&gap);
+                    last.append(MemorySegment::zeroed(gap.start, host_length(&gap), self.poisoning));
+                    last.append(segment);
+                    continue;
+                }
+            }
+            segments.push(segment);
+        }
+        self.segments = segments;
+    }
+
+    /// Returns the fraction of the span of RAM that is not mapped.
+    ///
+    /// The span runs from the lowest to the highest mapped RAM address; the unmapped bytes are the gaps
+    /// between segments inside it. Returns 0.0 for perfectly compact memory, including memory with fewer
+    /// than two segments.
+    pub fn fragmentation_ratio(&self) -> f64 {
+        let (Some(first), Some(last)) = (self.segments.first(), self.segments.last()) else {
+            return 0.0;
+        };
+        self.fragmentation().gap_bytes as f64 / (last.end() - first.start_address) as f64
+    }
+
+    /// Merges all RAM into one continuous segment by zero-filling every gap between segments.
+    ///
+    /// Behaves like `defragment` with no bound on the gap size. Gaps touching an MMIO or guard region,
+    /// or that would exceed the limit, can't be filled; every other gap is still filled, and the first gap
+    /// left over is reported. `fragmentation_ratio` returns 0.0 afterwards exactly when `Ok(())` is returned.
+    ///
+    /// # Returns
+    /// `Ok(())` if all RAM now forms one segment, `MemoryError::Overlap` with the first MMIO or guard address
+    /// inside a gap that was left, or `MemoryError::LimitExceeded` with the start of a gap that would not fit.
+    pub fn compact(&mut self) -> Result<(), MemoryError> {
+        self.defragment(u64::MAX);
+        let Some(pair) = self.segments.windows(2).next() else {
+            return Ok(());
+        };
+        let gap = pair[0].end() + self.base_address..pair[1].start_address + self.base_address;
+        self.check_unused(&gap)?;
+        Err(MemoryError::LimitExceeded { address: gap.start, limit: self.limit.unwrap_or(0) })
+    }
+
+    /// Removes all RAM segments, keeping MMIO regions, guard regions and protections.
+    ///
+    /// Every block handed out by `alloc` is released and the program break returns to its initial
+    /// address, since the memory backing them is gone. The arena itself, the base address and the
+    /// capacity of the segment list are retained.
+    pub fn clear(&mut self) {
+        self.clear_with(ClearOptions::default());
+    }
+
+    /// Removes all RAM segments and, depending on `options`, other mappings.
+    ///
+    /// The allocator and the program break are reset as by `clear`.
+    ///
+    /// # Arguments
+    /// * `options` - Selects whether MMIO regions, protections and guard regions are dropped as well.
+    pub fn clear_with(&mut self, options: ClearOptions) {
+        self.segments.clear();
+        self.allocations.clear();
+        self.brk = self.initial_brk;
+        if options.mmio {
+            self.mmio.clear();
+        }
+        if options.protection {
+            self.read_only.clear();
+        }
+        if options.guards {
+            self.guards.clear();
+        }
+    }
+
+    /// Unmaps the RAM backing a range of addresses.
+    ///
+    /// Segments partially covered by the range are split so that only the bytes outside it remain mapped.
+    ///
+    /// # Arguments
+    /// * `range` - The half-open range of addresses to unmap.
+    pub fn unmap_range(&mut self, range: Range<u64>) {
+        let start = range.start.saturating_sub(self.base_address);
+        let end = range.end.saturating_sub(self.base_address);
+        if start >= end {
+            return;
+        }
+        let mut segments = Vec::with_capacity(self.segments.len() + 1);
+        for segment in self.segments.drain(..) {
+            let segment_end = segment.end();
+            if segment_end <= start || segment.start_address >= end {
+                segments.push(segment);
+                continue;
+            }
+            if segment.start_address < start {
+                segments.push(segment.slice(0..(start - segment.start_address) as usize));
+            }
+            if segment_end > end {
+                segments.push(segment.slice((end - segment.start_address) as usize..segment.data.len()));
+            }
+        }
+        self.segments = segments;
+    }
+
+    /// Returns whether an address is backed by RAM or an MMIO region.
+    ///
+    /// # Arguments
+    /// * `address` - The address to query. It is translated as a read when a translator is installed.
+    pub fn is_mapped(&self, address: u64) -> bool {
+        let Ok(address) = self.translate(address, AccessKind::Read) else {
+            return false;
+        };
+        if self.find_mmio(address, 1).is_some() {
+            return true;
+        }
+        match address.checked_sub(self.base_address) {
+            Some(real_address) => self.find_segment(real_address).is_some(),
+            None => false,
+        }
+    }
+
+    /// Starts tracking which pages are written.
+    ///
+    /// Every successful write afterwards marks each `page_size`-aligned page it touches as dirty,
+    /// independently of how memory is split into segments. Enabling tracking again starts over
+    /// with the new page size and no dirty pages.
+    ///
+    /// # Arguments
+    /// * `page_size` - The tracking granularity in bytes; must not be 0.
+    ///
+    /// # Panics
+    /// Panics if `page_size` is 0.
+    pub fn enable_dirty_tracking(&mut self, page_size: usize) {
+        assert!(page_size > 0, "page size must not be 0");
+        self.dirty = Some(DirtyPages { page_size: page_size as u64, pages: BTreeSet::new() });
+    }
+
+    /// Stops tracking written pages and forgets the dirty set.
+    pub fn disable_dirty_tracking(&mut self) {
+        self.dirty = None;
+    }
+
+    /// Returns the start addresses of the pages written since tracking was enabled or last cleared, in ascending order.
+    ///
+    /// Addresses are translated addresses when a translator is installed. Returns an empty vector if tracking is disabled.
+    pub fn dirty_pages(&self) -> Vec<u64> {
+        match &self.dirty {
+            Some(tracker) => tracker.pages.iter().copied().collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Marks every tracked page clean.
+    pub fn clear_dirty(&mut self) {
+        if let Some(tracker) = &mut self.dirty {
+            tracker.pages.clear();
+        }
+    }
+
+    /// Installs a hook translating every guest address before it is looked up.
+    ///
+    /// The translator is applied to each byte of an access, so multi-byte accesses crossing a page boundary
+    /// are translated correctly. Protections, MMIO regions and segments are all looked up by the translated
+    /// address. An error returned by the translator aborts the access and is reported to the caller.
+    ///
+    /// # Arguments
+    /// * `translator` - The function mapping a guest address and access kind to a translated address.
+    pub fn set_translator(&mut self, translator: Box<Translator>) {
+        self.translator = Some(translator);
+    }
+
+    /// Removes the translation hook, restoring identity addressing.
+    pub fn clear_translator(&mut self) {
+        self.translator = None;
+    }
+
+    /// Enables paging, translating every address through a page table.
+    ///
+    /// Page table translation happens before the hook installed with `set_translator`, if any.
+    /// Accesses to unmapped pages, or that the page permissions forbid, fail with `MemoryError::PageFault`.
+    ///
+    /// # Arguments
+    /// * `table` - The page table to translate through. It replaces any table enabled before.
+    pub fn enable_paging(&mut self, table: PageTable) {
+        self.paging = Some(table);
+    }
+
+    /// Disables paging, returning the page table that was in use.
+    pub fn disable_paging(&mut self) -> Option<PageTable> {
+        self.paging.take()
+    }
+
+    /// Returns the page table in use, allowing pages to be mapped while paging is enabled.
+    pub fn page_table_mut(&mut self) -> Option<&mut PageTable> {
+        self.paging.as_mut()
+    }
+
+    /// Returns whether addresses go through a page table or translator before being looked up.
+    fn is_translated(&self) -> bool {
+        self.paging.is_some() || self.translator.is_some()
+    }
+
+    /// Maps a range of addresses to a memory-mapped I/O handler.
+    ///
+    /// Reads and writes inside the range are dispatched to the handler before RAM is consulted.
+    /// The range must not overlap RAM that is already mapped or another MMIO region.
+    ///
+    /// # Arguments
+    /// * `range` - The half-open range of addresses served by the handler.
+    /// * `handler` - The device handling the accesses.
+    ///
+    /// # Returns
+    /// `Ok(())` on success, or `MemoryError::Overlap` if the range is already in use.
+    pub fn map_mmio(&mut self, range: Range<u64>, handler: Box<dyn MmioHandler>) -> Result<(), MemoryError> {
+        self.check_unused(&range)?;
+        self.mmio.push(MmioRegion {
+            range,
+            handler: RefCell::new(handler),
+        });
+        Ok(())
+    }
+
+    /// Maps a guard region that faults on every access.
+    ///
+    /// A guard region takes up address space like any other mapping, so nothing else can be mapped over it,
+    /// but every read, write or fetch touching it fails with `MemoryError::GuardHit`, whether or not strict
+    /// mode is enabled. Writes never create RAM inside a guard region.
+    ///
+    /// # Arguments
+    /// * `range` - The half-open range of addresses to guard.
+    ///
+    /// # Returns
+    /// `Ok(())` on success, or `MemoryError::Overlap` if the range is already in use.
+    pub fn map_guard(&mut self, range: Range<u64>) -> Result<(), MemoryError> {
+        if range.start >= range.end {
+            return Ok(());
+        }
+        self.check_unused(&range)?;
+        self.guards.push(range);
+        self.guards.sort_by_key(|guard| guard.start);
+        Ok(())
+    }
+
+    /// Removes guard regions from a range of addresses.
+    ///
+    /// Guard regions partially covered by the range are split so that only the addresses outside it stay guarded.
+    ///
+    /// # Arguments
+    /// * `range` - The half-open range of addresses to stop guarding.
+    pub fn unmap_guard(&mut self, range: Range<u64>) {
+        let mut guards = Vec::with_capacity(self.guards.len() + 1);
+        for guard in self.guards.drain(..) {
+            if guard.end <= range.start || guard.start >= range.end {
+                guards.push(guard);
+                continue;
+            }
+            if guard.start < range.start {
+                guards.push(guard.start..range.start);
+            }
+            if guard.end > range.end {
+                guards.push(range.end..guard.end);
+            }
+        }
+        self.guards = guards;
+    }
+
+    /// Returns the regions making up the address space, in ascending order of address.
+    ///
+    /// Contiguous RAM is reported as a single region; each MMIO and guard region is reported as mapped.
+    pub fn regions(&self) -> impl Iterator<Item = Region> + '_ {
+        let mut regions: Vec<Region> = self.segments.iter()
+            .map(|segment| Region {
+                range: segment.start_address + self.base_address..segment.end() + self.base_address,
+                kind: RegionKind::Ram,
+            })
+            .chain(self.mmio.iter().map(|region| Region { range: region.range.clone(), kind: RegionKind::Mmio }))
+            .chain(self.guards.iter().map(|guard| Region { range: guard.clone(), kind: RegionKind::Guard }))
+            .collect();
+        regions.sort_by_key(|region| region.range.start);
+        regions.into_iter()
+    }
+
+    /// Returns the contiguous ranges of mapped addresses, sorted by start address.
+    ///
+    /// RAM and MMIO regions count as mapped, and touching regions are merged into one range;
+    /// guard regions are left out.
+    pub fn mapped_regions(&self) -> Vec<Range<u64>> {
+        let mut ranges: Vec<Range<u64>> = Vec::new();
+        for region in self.regions().filter(|region| region.kind != RegionKind::Guard) {
+            match ranges.last_mut() {
+                Some(last) if last.end == region.range.start => last.end = region.range.end,
+                _ => ranges.push(region.range),
+            }
+        }
+        ranges
+    }
+
+    /// Returns the lowest mapped address, or `None` if nothing is mapped.
+    pub fn lowest_mapped_address(&self) -> Option<u64> {
+        self.mapped_regions().first().map(|range| range.start)
+    }
+
+    /// Returns the highest mapped address, or `None` if nothing is mapped.
+    ///
+    /// This is the last byte of the highest region, not the end of its half-open range.
+    pub fn highest_mapped_address(&self) -> Option<u64> {
+        self.mapped_regions().last().map(|range| range.end - 1)
+    }
+
+    /// Checks that no byte of an access lies inside a guard region.
+    ///
+    /// # Arguments
+    /// * `address` - The first translated address of the access.
+    /// * `length` - The number of bytes in the access.
+    ///
+    /// # Returns
+    /// `Ok(())` if the access is clear of every guard region, or `MemoryError::GuardHit` carrying
+    /// the lowest guarded address inside the access.
+    fn check_guard(&self, address: u64, length: usize) -> Result<(), MemoryError> {
+        let end = address.saturating_add(length as u64);
+        for guard in &self.guards {
+            if guard.start < end && guard.end > address {
+                return Err(MemoryError::GuardHit { address: guard.start.max(address) });
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that no RAM segment, MMIO region or guard region overlaps a range of addresses.
+    ///
+    /// # Arguments
+    /// * `range` - The half-open range of addresses to check.
+    ///
+    /// # Returns
+    /// `Ok(())` if the range is unused, or `MemoryError::Overlap` with the first conflicting address.
+    fn check_unused(&self, range: &Range<u64>) -> Result<(), MemoryError> {
+        for region in &self.mmio {
+            if region.range.start < range.end && range.start < region.range.end {
+                return Err(MemoryError::Overlap { address: region.range.start.max(range.start) });
+            }
+        }
+        for guard in &self.guards {
+            if guard.start < range.end && range.start < guard.end {
+                return Err(MemoryError::Overlap { address: guard.start.max(range.start) });
+            }
+        }
+        for segment in &self.segments {
+            let start = segment.start_address + self.base_address;
+            let end = start + segment.data.len() as u64;
+            if start < range.end && range.start < end {
+                return Err(MemoryError::Overlap { address: start.max(range.start) });
+            }
+        }
+        Ok(())
+    }
+
+    /// Maps a range of zero-filled RAM.
+    ///
+    /// # Arguments
+    /// * `range` - The half-open range of addresses to map.
+    ///
+    /// # Returns
+    /// `Ok(())` on success, `MemoryError::Overlap` if any part of the range is already mapped,
+    /// or the error reported while reserving or, when addresses are translated, filling the range.
+    pub fn map(&mut self, range: Range<u64>) -> Result<(), MemoryError> {
+        self.check_unused(&range)?;
+        if self.is_translated() {
+            return self.fill(range.start, host_length(&range), 0);
+        }
+        self.reserve(range)
+    }
+
+    /// Maps every unmapped byte of a range as zero-filled RAM without writing it.
+    ///
+    /// Each gap between the segments already in the range is backed by a single new segment, so reserving
+    /// a large range costs one allocation rather than one per `DEFAULT_SIZE` block, and later accesses to it
+    /// take the fast path. Bytes that are already mapped keep their contents, and no page is marked dirty.
+    /// The range is reserved as given, without translation.
+    ///
+    /// # Arguments
+    /// * `range` - The half-open range of addresses to reserve.
+    ///
+    /// # Returns
+    /// `Ok(())` on success, `MemoryError::Overlap` if the range touches an MMIO or guard region,
+    /// `MemoryError::Unmapped` if it starts below the base address, or `MemoryError::LimitExceeded` carrying
+    /// the first byte that would not fit. Nothing is mapped when an error is returned.
+    pub fn reserve(&mut self, range: Range<u64>) -> Result<(), MemoryError> {
+        if range.start >= range.end {
+            return Ok(());
+        }
+        for region in self.mmio.iter().map(|region| &region.range).chain(&self.guards) {
+            if region.start < range.end && range.start < region.end {
+                return Err(MemoryError::Overlap { address: region.start.max(range.start) });
+            }
+        }
+        let start = range.start.checked_sub(self.base_address).ok_or(MemoryError::Unmapped { address: range.start })?;
+        let end = range.end.checked_sub(self.base_address).ok_or(MemoryError::Unmapped { address: range.start })?;
+        let mut gaps = Vec::new();
+        let mut cursor = start;
+        for segment in &self.segments {
+            if segment.start_address >= end {
+                break;
+            }
+            if segment.start_address > cursor {
+                gaps.push(cursor..segment.start_address);
+            }
+            cursor = cursor.max(segment.end());
+        }
+        if cursor < end {
+            gaps.push(cursor..end);
+        }
+        if let Some(limit) = self.limit {
+            let mut mapped = self.mapped_size();
+            for gap in &gaps {
+                let room = limit.saturating_sub(mapped);
+                if host_length(gap) > room {
+                    return Err(MemoryError::LimitExceeded { address: gap.start + room as u64 + self.base_address, limit });
+                }
+                mapped += host_length(gap);
+            }
+        }
+        for gap in gaps {
+            self.segments.push(MemorySegment::new(gap.start, vec![0; host_length(&gap)]));
+        }
+        self.segments.sort_by_key(|segment| segment.start_address);
+        self.merge_segments();
+        for segment in &mut self.segments {
+            if segment.start_address < end && segment.end() > start {
+                let offset = start.saturating_sub(segment.start_address);
+                let run_end = end.min(segment.end()) - segment.start_address;
+                segment.set_initialized(offset as usize, (run_end - offset) as usize);
+            }
+        }
+        Ok(())
+    }
+
+    /// Maps a range of memory holding the contents of a file.
+    ///
+    /// The file is read once when it is mapped; the guest sees a private copy, so writes to a writable
+    /// mapping never reach the file. A read-only mapping is protected with `Protection::ReadOnly`.
+    ///
+    /// # Arguments
+    /// * `address` - The address the first mapped byte of the file appears at.
+    /// * `path` - The path of the file.
+    /// * `offset` - The offset in the file of the first mapped byte.
+    /// * `length` - The number of bytes to map.
+    /// * `writable` - Whether the guest may write to the mapping.
+    ///
+    /// # Returns
+    /// `Ok(())` on success, `MemoryError::AddressOverflow` if the range runs past the end of the address
+    /// space, `MemoryError::Overlap` if the range is already mapped, `MemoryError::FileError` if the file
+    /// can't be opened or holds fewer than `length` bytes past `offset`, or the error reported while
+    /// storing the contents.
+    pub fn map_file<P: AsRef<Path>>(&mut self, address: u64, path: P, offset: u64, length: usize, writable: bool) -> Result<(), MemoryError> {
+        let end = address.checked_add(length as u64).ok_or(MemoryError::AddressOverflow { address })?;
+        let range = address..end;
+        self.check_unused(&range)?;
+        let mut contents = vec![0; length];
+        File::open(path)
+            .and_then(|mut file| {
+                file.seek(SeekFrom::Start(offset))?;
+                file.read_exact(&mut contents)
+            })
+            .map_err(|error| MemoryError::FileError { kind: error.kind() })?;
+        self.store(address, &contents)?;
+        if !writable {
+            self.protect(range, Protection::ReadOnly);
+        }
+        Ok(())
+    }
+
+    /// Sets the range of addresses handed out by `alloc`.
+    ///
+    /// The arena must not overlap memory that is already mapped. Replacing the arena forgets
+    /// every outstanding allocation.
+    ///
+    /// # Arguments
+    /// * `arena` - The half-open range of addresses the allocator may use.
+    ///
+    /// # Returns
+    /// `Ok(())` on success, or `MemoryError::Overlap` if the arena is already in use.
+    pub fn set_arena(&mut self, arena: Range<u64>) -> Result<(), MemoryError> {
+        self.check_unused(&arena)?;
+        self.arena = Some(arena);
+        self.allocations.clear();
+        Ok(())
+    }
+
+    /// Allocates a block of guest memory from the arena.
+    ///
+    /// Blocks are placed first-fit between the outstanding allocations and never overlap each other
+    /// or any RAM, MMIO or guard region mapped inside the arena after it was set. The block is mapped as
+    /// zero-filled RAM when it is handed out. A zero-sized request still gets a unique address.
+    ///
+    /// # Arguments
+    /// * `size` - The number of bytes to allocate.
+    /// * `align` - The required alignment of the block's address; must not be 0.
+    ///
+    /// # Returns
+    /// The address of the block, `MemoryError::OutOfMemory` if no arena is set or no gap is large enough,
+    /// or the error reported while mapping the block.
+    ///
+    /// # Panics
+    /// Panics if `align` is 0.
+    pub fn alloc(&mut self, size: usize, align: usize) -> Result<u64, MemoryError> {
+        assert!(align > 0, "alignment must not be 0");
+        let size = size.max(1);
+        let Some(arena) = self.arena.clone() else {
+            return Err(MemoryError::OutOfMemory { size });
+        };
+        let align = align as u64;
+        let mut candidate = arena.start.next_multiple_of(align);
+        loop {
+            let end = match candidate.checked_add(size as u64) {
+                Some(end) if end <= arena.end => end,
+                _ => return Err(MemoryError::OutOfMemory { size }),
+            };
+            let base_address = self.base_address;
+            let conflict = self.allocations.iter()
+                .map(|block| block.range.clone())
+                .chain(self.mmio.iter().map(|region| region.range.clone()))
+                .chain(self.guards.iter().cloned())
+                .chain(self.segments.iter().map(|segment| segment.start_address + base_address..segment.end() + base_address))
+                .filter(|range| range.start < end && candidate < range.end)
+                .map(|range| range.end)
+                .max();
+            match conflict {
+                Some(conflict_end) => candidate = conflict_end.next_multiple_of(align),
+                None => break,
+            }
+        }
+        let range = candidate..candidate + size as u64;
+        self.reserve(range.clone())?;
+        let index = self.allocations.partition_point(|block| block.range.start < candidate);
+        self.allocations.insert(index, Allocation { range });
+        Ok(candidate)
+    }
+
+    /// Releases a block previously returned by `alloc`.
+    ///
+    /// The block is unmapped, so later accesses to it fault until it is handed out again.
+    ///
+    /// # Arguments
+    /// * `address` - The address returned by `alloc`.
+    ///
+    /// # Returns
+    /// `Ok(())` on success, or `MemoryError::InvalidFree` if no outstanding block starts at the address,
+    /// which includes freeing the same block twice.
+    pub fn free(&mut self, address: u64) -> Result<(), MemoryError> {
+        match self.allocations.binary_search_by_key(&address, |block| block.range.start) {
+            Ok(index) => {
+                let block = self.allocations.remove(index);
+                self.unmap_range(block.range);
+                Ok(())
+            }
+            Err(_) => Err(MemoryError::InvalidFree { address }),
+        }
+    }
+
+    /// Searches for the MMIO region containing a whole access.
+    ///
+    /// # Arguments
+    /// * `address` - The first address of the access.
+    /// * `length` - The number of bytes in the access.
+    ///
+    /// # Returns
+    /// The region serving every byte of the access, or `None` if no single region does.
+    fn find_mmio(&self, address: u64, length: usize) -> Option<&MmioRegion> {
+        self.mmio.iter().find(|region| address >= region.range.start && address + length as u64 <= region.range.end)
+    }
+
+    /// Sets the initial program break.
+    ///
+    /// The heap grows upwards from this address with `sbrk` and may never shrink below it.
+    ///
+    /// # Arguments
+    /// * `address` - The initial program break.
+    pub fn set_brk(&mut self, address: u64) {
+        self.initial_brk = address;
+        self.brk = address;
+    }
+
+    /// Returns the current program break.
+    pub fn brk(&self) -> u64 {
+        self.brk
+    }
+
+    /// Moves the program break by `delta` bytes.
+    ///
+    /// Growing the heap maps the new bytes and zero-fills them, subject to the memory limit and protections.
+    /// Shrinking the heap unmaps the released bytes.
+    ///
+    /// # Arguments
+    /// * `delta` - The signed number of bytes to grow (positive) or shrink (negative) the heap by.
+    ///
+    /// # Returns
+    /// The previous program break, or an error if the new break would be below the initial break
+    /// or the new bytes cannot be written; the break is unchanged on error.
+    pub fn sbrk(&mut self, delta: i64) -> Result<u64, MemoryError> {
+        let old = self.brk;
+        let new = old.checked_add_signed(delta)
+            .filter(|new| *new >= self.initial_brk)
+            .ok_or(MemoryError::InvalidBreak { address: old.wrapping_add_signed(delta) })?;
+        if new > old {
+            self.fill(old, host_length(&(old..new)), 0)?;
+        } else {
+            self.unmap_range(new..old);
+        }
+        self.brk = new;
+        Ok(old)
+    }
+
+    /// Changes the protection of a range of addresses.
+    ///
+    /// Protection is tracked independently of the memory segments, so it is kept intact when
+    /// segments are created, merged or split, and it may cover addresses that are not mapped yet.
+    /// Marking a range `ReadWrite` lifts any read-only protection from exactly that range.
+    ///
+    /// # Arguments
+    /// * `range` - The half-open range of addresses to update.
+    /// * `protection` - The protection to apply to the range.
+    pub fn protect(&mut self, range: Range<u64>, protection: Protection) {
+        if range.start >= range.end {
+            return;
+        }
+        // remove the range from the current read-only set
+        let mut ranges = Vec::with_capacity(self.read_only.len() + 2);
+        for r in self.read_only.drain(..) {
+            if r.end <= range.start || r.start >= range.end {
+                ranges.push(r);
+                continue;
+            }
+            if r.start < range.start {
+                ranges.push(r.start..range.start);
+            }
+            if r.end > range.end {
+                ranges.push(range.end..r.end);
+            }
+        }
+        if protection == Protection::ReadOnly {
+            ranges.push(range);
+        }
+        // sort by address and merge adjacent ranges
+        ranges.sort_by_key(|r| r.start);
+        for r in ranges {
+            match self.read_only.last_mut() {
+                Some(last) if last.end >= r.start => last.end = last.end.max(r.end),
+                _ => self.read_only.push(r),
+            }
+        }
+    }
+
+    /// Retrieves the protection of a single address.
+    ///
+    /// # Arguments
+    /// * `address` - The address to query.
+    ///
+    /// # Returns
+    /// The `Protection` currently applied to the address.
+    pub fn protection(&self, address: u64) -> Protection {
+        if self.read_only.iter().any(|r| r.contains(&address)) {
+            Protection::ReadOnly
+        } else {
+            Protection::ReadWrite
+        }
+    }
+
+    /// Checks that every byte of an access may be written.
+    ///
+    /// # Arguments
+    /// * `address` - The first address of the access.
+    /// * `length` - The number of bytes in the access.
+    ///
+    /// # Returns
+    /// `Ok(())` if the whole access is writable, or `MemoryError::WriteProtected` carrying
+    /// the lowest protected address inside the access.
+    fn check_writable(&self, address: u64, length: usize) -> Result<(), MemoryError> {
+        let end = address.saturating_add(length as u64);
+        for r in &self.read_only {
+            if r.start < end && r.end > address {
+                return Err(MemoryError::WriteProtected { address: r.start.max(address) });
+            }
+        }
+        Ok(())
+    }
+
+    /// Searches for a memory segment that contains a specified real address.
+    ///
+    /// Iterates through the memory segments to find a segment where the real address falls within
+    /// the segment's range (from its start address to the end of its data).
+    ///
+    /// # Arguments
+    /// * `real_address` - The real memory address to locate within the segments.
+    ///
+    /// # Returns
+    /// An `Option<usize>` representing the index of the found memory segment in the `segments` vector.
+    /// Returns `None` if no suitable segment is found.
+    fn find_segment(&self, real_address: u64) -> Option<usize> {
+        for (index, segment) in self.segments.iter().enumerate() {
+            if real_address >= segment.start_address && real_address < segment.end() {
+                return Some(index);
+            }
+        }
+        None
+    }
+
+    /// Translates a guest address through the page table and the installed translator.
+    ///
+    /// # Arguments
+    /// * `address` - The address to translate.
+    /// * `kind` - The kind of access being performed.
+    ///
+    /// # Returns
+    /// The translated address, or the address unchanged if neither paging nor a translator is enabled.
+    fn translate(&self, address: u64, kind: AccessKind) -> Result<u64, MemoryError> {
+        let address = match &self.paging {
+            Some(table) => table.translate_access(address, kind)?,
+            None => address,
+        };
+        match &self.translator {
+            Some(translator) => translator(address, kind),
+            None => Ok(address),
+        }
+    }
+
+    /// Reads a single byte from memory at a given translated address.
+    ///
+    /// Calculates the real address by subtracting the base address from the given address.
+    /// MMIO regions are consulted first; otherwise, if the address is within a memory segment,
+    /// returns the byte at the calculated offset within the segment.
+    ///
+    /// # Arguments
+    /// * `address` - The address from which to read the byte.
+    ///
+    /// # Returns
+    /// The byte value at the given address, or `None` if the address is not mapped.
+    fn read_byte(&self, address: u64) -> Option<u8> {
+        if let Some(region) = self.find_mmio(address, 1) {
+            return Some(region.handler.borrow_mut().read(address - region.range.start, 1) as u8);
+        }
+        let real_address = address.checked_sub(self.base_address)?;
+        self.find_segment(real_address)
+            .map(|index| self.segments[index].data[(real_address - self.segments[index].start_address) as usize])
+    }
+
+    /// Writes a single byte to memory at a given translated address.
+    ///
+    /// Calculates the real address by subtracting the base address from the given address.
+    /// MMIO regions are consulted first; otherwise, if a segment containing the address exists,
+    /// updates the byte at the specific offset.
+    /// If no segment contains the address, a new segment covering the free part of its `DEFAULT_SIZE` block
+    /// is created and added to the memory.
+    /// Segments are automatically merged if they become contiguous after the write operation.
+    /// Protection is not checked here; callers check the whole access up front.
+    ///
+    /// # Arguments
+    /// * `address` - The address at which to write the byte.
+    /// * `value` - The byte value to write.
+    fn write_byte(&mut self, address: u64, value: u8) {
+        if let Some(region) = self.find_mmio(address, 1) {
+            region.handler.borrow_mut().write(address - region.range.start, &[value]);
+            return;
+        }
+        // callers reject addresses below the base with `check_base` before writing
+        let Some(real_address) = address.checked_sub(self.base_address) else {
+            return;
+        };
+        if let Some(index) = self.find_segment(real_address) {
+            let offset = (real_address - self.segments[index].start_address) as usize;
+            self.segments[index].data_mut()[offset] = value;
+            self.segments[index].set_initialized(offset, 1);
+        } else {
+            let range = self.new_segment_range(real_address);
+            let offset = (real_address - range.start) as usize;
+            let mut new_segment = MemorySegment::zeroed(range.start, host_length(&range), self.poisoning);
+            new_segment.data_mut()[offset] = value;
+            new_segment.set_initialized(offset, 1);
+            self.segments.push(new_segment);
+            // sort by address
+            self.segments.sort_by_key(|segment| segment.start_address);
+            self.merge_segments();
+        }
+    }
+
+    /// Merges every pair of contiguous segments into one.
+    ///
+    /// The segments must already be sorted by address.
+    fn merge_segments(&mut self) {
+        let mut i = 0;
+        while i + 1 < self.segments.len() {
+            if self.segments[i].end() == self.segments[i + 1].start_address {
+                let next = self.segments.remove(i + 1);
+                self.segments[i].append(next);
+            } else {
+                i += 1;
+            }
+        }
+        debug_assert!(self.segments_disjoint(), "memory segments overlap");
+    }
+
+    /// Computes the range of real addresses covered by a new segment holding an unmapped real address.
+    ///
+    /// The segment covers the `DEFAULT_SIZE` block containing the address, clipped against the neighboring
+    /// segments so that it never overlaps them. Segments left unaligned by `unmap_range` can end or start
+    /// in the middle of a block, in which case only the uncovered gap is used.
+    ///
+    /// # Arguments
+    /// * `real_address` - The real address that is not held by any segment.
+    fn new_segment_range(&self, real_address: u64) -> Range<u64> {
+        let block = real_address / DEFAULT_SIZE as u64 * DEFAULT_SIZE as u64;
+        let mut start = block;
+        let mut end = block.saturating_add(DEFAULT_SIZE as u64);
+        for segment in &self.segments {
+            if segment.end() <= real_address {
+                start = start.max(segment.end());
+            } else if segment.start_address > real_address {
+                end = end.min(segment.start_address);
+            }
+        }
+        start..end
+    }
+
+    /// Returns whether the segments are sorted by address and no two of them overlap.
+    fn segments_disjoint(&self) -> bool {
+        self.segments.windows(2).all(|pair| pair[0].end() <= pair[1].start_address)
+    }
+
+    /// Reads a run of bytes starting at a given guest address.
+    ///
+    /// Every byte is translated individually, so accesses crossing a page boundary are handled correctly.
+    /// A run of up to 8 bytes that maps entirely into one MMIO region is passed to its handler as a single read.
+    ///
+    /// # Arguments
+    /// * `address` - The first guest address to read.
+    /// * `buffer` - The buffer receiving the bytes.
+    /// * `kind` - The kind of access being performed.
+    /// * `strict` - Whether unmapped bytes are reported as errors rather than read as 0.
+    ///
+    /// # Returns
+    /// `Ok(())` on success, or the first error met while translating or reading.
+    fn load(&self, address: u64, buffer: &mut [u8], kind: AccessKind, strict: bool) -> Result<(), MemoryError> {
+        let length = buffer.len();
+        if length == 0 {
+            return Ok(());
+        }
+        if address.checked_add(length as u64).is_none() {
+            return Err(MemoryError::AddressOverflow { address });
+        }
+        if !self.is_translated() {
+            self.check_guard(address, length)?;
+        }
+        if length <= 8 && !self.mmio.is_empty() {
+            let first = self.translate(address, kind)?;
+            if let Some(region) = self.find_mmio(first, length) {
+                let last = length as u64 - 1;
+                if length == 1 || self.translate(address.wrapping_add(last), kind)? == first.wrapping_add(last) {
+                    let value = region.handler.borrow_mut().read(first - region.range.start, length);
+                    buffer.copy_from_slice(&value.to_le_bytes()[..length]);
+                    return Ok(());
+                }
+            }
+        }
+        if !self.is_translated() {
+            if let Some((index, offset)) = self.find_run(address, length) {
+                buffer.copy_from_slice(&self.segments[index].data[offset..offset + length]);
+                return match self.segments[index].first_uninit(offset, length) {
+                    Some(uninit) => self.report_uninit(address + (uninit - offset) as u64, strict),
+                    None => Ok(()),
+                };
+            }
+        }
+        let mut first_uninit = None;
+        for (i, byte) in buffer.iter_mut().enumerate() {
+            let address = address.wrapping_add(i as u64);
+            let physical = self.translate(address, kind)?;
+            self.check_guard(physical, 1)?;
+            *byte = match self.read_byte(physical) {
+                Some(value) => value,
+                None if strict => return Err(MemoryError::Unmapped { address }),
+                None => 0,
+            };
+            if first_uninit.is_none() && self.is_uninit(physical) {
+                if strict {
+                    return Err(MemoryError::Uninitialized { address });
+                }
+                first_uninit = Some(address);
+            }
+        }
+        match first_uninit {
+            Some(address) => self.report_uninit(address, false),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns whether the byte at a translated address lies in RAM that has never been written while poisoning.
+    fn is_uninit(&self, address: u64) -> bool {
+        let Some(real_address) = address.checked_sub(self.base_address) else {
+            return false;
+        };
+        if self.find_mmio(address, 1).is_some() {
+            return false;
+        }
+        self.find_segment(real_address).is_some_and(|index| {
+            let segment = &self.segments[index];
+            segment.first_uninit((real_address - segment.start_address) as usize, 1).is_some()
+        })
+    }
+
+    /// Reports a read of an uninitialized byte.
+    ///
+    /// # Arguments
+    /// * `address` - The guest address of the first uninitialized byte of the access.
+    /// * `strict` - Whether the read fails instead of being passed to the uninitialized-read handler.
+    ///
+    /// # Returns
+    /// `MemoryError::Uninitialized` if `strict` is set, `Ok(())` otherwise.
+    fn report_uninit(&self, address: u64, strict: bool) -> Result<(), MemoryError> {
+        if strict {
+            return Err(MemoryError::Uninitialized { address });
+        }
+        if let Some(handler) = &self.uninit_handler {
+            handler(address);
+        }
+        Ok(())
+    }
+
+    /// Checks that a write does not reach below the base address, where no RAM can be mapped.
+    ///
+    /// # Arguments
+    /// * `address` - The guest address of the access, used to report the failing byte.
+    /// * `physical` - The translated address of each byte of the access, in order.
+    ///
+    /// # Returns
+    /// `Ok(())` if every byte lies at or above the base address or inside an MMIO region, or
+    /// `MemoryError::Unmapped` carrying the guest address of the first byte that does not.
+    fn check_base<I: Iterator<Item = u64>>(&self, address: u64, physical: I) -> Result<(), MemoryError> {
+        for (i, translated) in physical.enumerate() {
+            if translated < self.base_address && self.find_mmio(translated, 1).is_none() {
+                return Err(MemoryError::Unmapped { address: address.wrapping_add(i as u64) });
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes a run of bytes starting at a given guest address.
+    ///
+    /// Every byte is translated and checked against the protections before anything is written,
+    /// so a failing access leaves memory untouched.
+    /// A run that maps entirely into one MMIO region is passed to its handler as a single write.
+    ///
+    /// # Arguments
+    /// * `address` - The first guest address to write.
+    /// * `bytes` - The bytes to write.
+    ///
+    /// # Returns
+    /// `Ok(())` on success, or the first error met while translating or checking protections.
+    fn store(&mut self, address: u64, bytes: &[u8]) -> Result<(), MemoryError> {
+        if bytes.is_empty() {
+            return Ok(());
+        }
+        let end = address.checked_add(bytes.len() as u64).ok_or(MemoryError::AddressOverflow { address })?;
+        if !self.is_translated() {
+            self.check_guard(address, bytes.len())?;
+            self.check_writable(address, bytes.len())?;
+            if address < self.base_address {
+                self.check_base(address, address..end)?;
+            }
+            if self.find_run(address, bytes.len()).is_none() {
+                self.check_limit(address, address..end)?;
+            }
+            self.store_run(address, bytes);
+            self.mark_dirty(address..end);
+            return Ok(());
+        }
+        let mut physical = Vec::with_capacity(bytes.len());
+        for i in 0..bytes.len() {
+            let translated = self.translate(address.wrapping_add(i as u64), AccessKind::Write)?;
+            self.check_guard(translated, 1)?;
+            self.check_writable(translated, 1)?;
+            physical.push(translated);
+        }
+        self.check_base(address, physical.iter().copied())?;
+        self.check_limit(address, physical.iter().copied())?;
+        if physical.windows(2).all(|pair| pair[1] == pair[0] + 1) {
+            self.store_run(physical[0], bytes);
+            self.mark_dirty(physical[0]..physical[0] + bytes.len() as u64);
+        } else {
+            for (byte, address) in bytes.iter().zip(physical) {
+                self.write_byte(address, *byte);
+                self.mark_dirty(address..address + 1);
+            }
+        }
+        Ok(())
+    }
+
+    /// Marks every tracked page touched by a range of translated addresses as dirty.
+    ///
+    /// # Arguments
+    /// * `range` - The half-open range of translated addresses that was written.
+    fn mark_dirty(&mut self, range: Range<u64>) {
+        if let Some(tracker) = &mut self.dirty {
+            let first = range.start / tracker.page_size;
+            let last = (range.end - 1) / tracker.page_size;
+            tracker.pages.extend((first..=last).map(|page| page * tracker.page_size));
+        }
+    }
+
+    /// Checks that writing to a set of translated addresses keeps the mapped size within the limit.
+    ///
+    /// Every unmapped byte outside MMIO regions causes the free part of its `DEFAULT_SIZE` block to be created,
+    /// so each such new segment is counted once. Consecutive bytes falling in the same segment, new block or
+    /// MMIO region are skipped without another lookup, so the check is linear in the length of the access.
+    ///
+    /// # Arguments
+    /// * `address` - The guest address of the first byte, used to report the failure point.
+    /// * `physical` - The translated address of each byte of the access, in order.
+    ///
+    /// # Returns
+    /// `Ok(())` if the access fits, or `MemoryError::LimitExceeded` carrying the guest address of the
+    /// first byte whose block would not fit.
+    fn check_limit<I: Iterator<Item = u64>>(&self, address: u64, physical: I) -> Result<(), MemoryError> {
+        let Some(limit) = self.limit else {
+            return Ok(());
+        };
+        let mut mapped = self.mapped_size();
+        let mut created = BTreeSet::new();
+        let mut covered = 0..0;
+        for (i, translated) in physical.enumerate() {
+            if covered.contains(&translated) {
+                continue;
+            }
+            if let Some(region) = self.find_mmio(translated, 1) {
+                covered = region.range.clone();
+                continue;
+            }
+            let real_address = translated.checked_sub(self.base_address)
+                .ok_or(MemoryError::Unmapped { address: address.wrapping_add(i as u64) })?;
+            if let Some(index) = self.find_segment(real_address) {
+                let segment = &self.segments[index];
+                covered = segment.start_address + self.base_address..segment.end() + self.base_address;
+                continue;
+            }
+            let range = self.new_segment_range(real_address);
+            covered = range.start + self.base_address..range.end + self.base_address;
+            if !created.insert(range.start) {
+                continue;
+            }
+            mapped += host_length(&range);
+            if mapped > limit {
+                return Err(MemoryError::LimitExceeded { address: address.wrapping_add(i as u64), limit });
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes a run of bytes to consecutive translated addresses.
+    ///
+    /// A run inside one MMIO region goes to its handler as a single write, and a run inside one segment
+    /// is copied directly into the segment storage; anything else falls back to byte-wise writes.
+    ///
+    /// # Arguments
+    /// * `address` - The first translated address to write.
+    /// * `bytes` - The bytes to write.
+    fn store_run(&mut self, address: u64, bytes: &[u8]) {
+        if let Some(region) = self.find_mmio(address, bytes.len()) {
+            region.handler.borrow_mut().write(address - region.range.start, bytes);
+            return;
+        }
+        if let Some((index, offset)) = self.find_run(address, bytes.len()) {
+            self.segments[index].data_mut()[offset..offset + bytes.len()].copy_from_slice(bytes);
+            self.segments[index].set_initialized(offset, bytes.len());
+            return;
+        }
+        for (i, byte) in bytes.iter().enumerate() {
+            self.write_byte(address + i as u64, *byte);
+        }
+    }
+
+    /// Searches for a segment holding a whole run of translated addresses, clear of any MMIO region.
+    ///
+    /// # Arguments
+    /// * `address` - The first translated address of the run.
+    /// * `length` - The number of bytes in the run.
+    ///
+    /// # Returns
+    /// The index of the segment and the offset of the run inside its data, or `None` if no single segment holds the run.
+    fn find_run(&self, address: u64, length: usize) -> Option<(usize, usize)> {
+        let end = address.checked_add(length as u64)?;
+        if self.mmio.iter().any(|region| region.range.start < end && address < region.range.end) {
+            return None;
+        }
+        let real_address = address.checked_sub(self.base_address)?;
+        let index = self.find_segment(real_address)?;
+        let offset = (real_address - self.segments[index].start_address) as usize;
+        if offset + length <= self.segments[index].data.len() {
+            Some((index, offset))
+        } else {
+            None
+        }
+    }
+
+    /// Reads a value of type `T` from memory starting at a given address.
+    ///
+    /// Reads bytes sequentially starting from the address and constructs a value of type `T` using `MemoryIO` trait.
+    /// The number of bytes read is determined by the size of type `T`.
+    /// An access of up to 8 bytes that lies entirely inside an MMIO region is passed to its handler as a single read.
+    /// Unmapped bytes read as 0; if the translator faults, the whole value reads as 0. Use `try_read` to observe errors.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type implementing `MemoryIO` that determines how bytes are read and interpreted.
+    ///
+    /// # Arguments
+    /// * `address` - The starting address from which to read the bytes.
+    ///
+    /// # Returns
+    /// A value of type `T` constructed from the read bytes.
+    pub fn read<T: MemoryIO>(&self, address: u64) -> T {
+        let value = match self.load_value(address, AccessKind::Read, false) {
+            Ok(value) => value,
+            Err(_) => T::from_bytes(&vec![0; T::size()]),
+        };
+        trace_event!(function = "Memory::read", address, size = T::size(), value = ?value.to_bytes());
+        value
+    }
+
+    /// Reads a value of type `T` from memory, reporting errors.
+    ///
+    /// Behaves like `read`, except that translation faults are returned and, in strict mode,
+    /// an access touching an unmapped byte fails.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type implementing `MemoryIO` that determines how bytes are read and interpreted.
+    ///
+    /// # Arguments
+    /// * `address` - The starting address from which to read the bytes.
+    ///
+    /// # Returns
+    /// The value read, or the first error met while reading.
+    pub fn try_read<T: MemoryIO>(&self, address: u64) -> Result<T, MemoryError> {
+        self.load_value(address, AccessKind::Read, self.strict)
+    }
+
+    /// Fetches a value of type `T` for execution.
+    ///
+    /// Behaves like `try_read`, but the translator is told the access is an instruction fetch.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type implementing `MemoryIO` that determines how bytes are read and interpreted.
+    ///
+    /// # Arguments
+    /// * `address` - The starting address from which to fetch the bytes.
+    ///
+    /// # Returns
+    /// The value fetched, or the first error met while reading.
+    pub fn fetch<T: MemoryIO>(&self, address: u64) -> Result<T, MemoryError> {
+        self.load_value(address, AccessKind::Execute, self.strict)
+    }
+
+    /// Reads a single value of type `T`, gathering its bytes in a stack buffer.
+    ///
+    /// Types of up to `STACK_BUFFER_SIZE` bytes (everything up to `u512`) are read without any heap allocation.
+    ///
+    /// # Arguments
+    /// * `address` - The starting address from which to read the bytes.
+    /// * `kind` - The kind of access being performed.
+    /// * `strict` - Whether unmapped bytes are reported as errors rather than read as 0.
+    ///
+    /// # Returns
+    /// The value read, or the first error met while reading.
+    fn load_value<T: MemoryIO>(&self, address: u64, kind: AccessKind, strict: bool) -> Result<T, MemoryError> {
+        if T::size() <= STACK_BUFFER_SIZE {
+            let mut buffer = [0u8; STACK_BUFFER_SIZE];
+            let bytes = &mut buffer[..T::size()];
+            self.load(address, bytes, kind, strict)?;
+            self.apply_endianness::<T>(bytes);
+            Ok(T::from_bytes(bytes))
+        } else {
+            let mut bytes = vec![0; T::size()];
+            self.load(address, &mut bytes, kind, strict)?;
+            self.apply_endianness::<T>(&mut bytes);
+            Ok(T::from_bytes(&bytes))
+        }
+    }
+
+    /// Writes a value of type `T` to memory starting at a given address.
+    ///
+    /// Converts the value to a byte array using `MemoryIO` trait and writes the bytes sequentially to memory.
+    /// Each byte is written to consecutive memory addresses starting from the specified address.
+    /// An access that lies entirely inside an MMIO region is passed to its handler as a single write.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type implementing `MemoryIO` that determines how the value is converted to bytes.
+    ///
+    /// # Arguments
+    /// * `address` - The starting address at which to write the bytes.
+    /// * `value` - The value of type `T` to write to memory.
+    ///
+    /// # Returns
+    /// `Ok(())` on success, or an error if any byte of the access is read-only or fails to translate,
+    /// in which case nothing is written.
+    pub fn write<T: MemoryIO>(&mut self, address: u64, value: T) -> Result<(), MemoryError> {
+        let mut bytes = value.to_bytes();
+        trace_event!(function = "Memory::write", address, size = T::size(), value = ?bytes);
+        self.apply_endianness::<T>(&mut bytes);
+        self.store(address, &bytes)
+    }
+
+    /// Reads a value of type `T` from a naturally aligned address.
+    ///
+    /// Behaves like `read` once the address is known to be a multiple of `T::size()`.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type implementing `MemoryIO` that determines how bytes are read and interpreted.
+    ///
+    /// # Arguments
+    /// * `address` - The starting address from which to read the bytes.
+    ///
+    /// # Returns
+    /// The value read, or an `AlignmentError` if the address is misaligned.
+    pub fn read_aligned<T: MemoryIO>(&self, address: u64) -> Result<T, AlignmentError> {
+        Self::check_alignment::<T>(address)?;
+        Ok(self.read(address))
+    }
+
+    /// Writes a value of type `T` to a naturally aligned address.
+    ///
+    /// Behaves like `write` once the address is known to be a multiple of `T::size()`.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type implementing `MemoryIO` that determines how the value is converted to bytes.
+    ///
+    /// # Arguments
+    /// * `address` - The starting address at which to write the bytes.
+    /// * `value` - The value of type `T` to write to memory.
+    ///
+    /// # Returns
+    /// `Ok(())` on success, `MemoryError::Misaligned` if the address is misaligned, or the error reported by `write`.
+    pub fn write_aligned<T: MemoryIO>(&mut self, address: u64, value: T) -> Result<(), MemoryError> {
+        Self::check_alignment::<T>(address)?;
+        self.write(address, value)
+    }
+
+    /// Checks that an address is a multiple of `T::size()`.
+    fn check_alignment<T: MemoryIO>(address: u64) -> Result<(), AlignmentError> {
+        if !address.is_multiple_of(T::size() as u64) {
+            return Err(AlignmentError { address, required_alignment: T::size() });
+        }
+        Ok(())
+    }
+
+    /// Writes an `f32` value to memory as its IEEE 754 bit pattern.
+    ///
+    /// # Arguments
+    /// * `address` - The starting address at which to write the value.
+    /// * `value` - The `f32` value to write.
+    ///
+    /// # Returns
+    /// `Ok(())` on success, or the error reported by `write`.
+    pub fn write_f32(&mut self, address: u64, value: f32) -> Result<(), MemoryError> {
+        self.write::<u32>(address, value.to_bits())
+    }
+
+    /// Reads an `f32` value stored as its IEEE 754 bit pattern.
+    ///
+    /// # Arguments
+    /// * `address` - The starting address from which to read the value.
+    ///
+    /// # Returns
+    /// The `f32` value at the given address.
+    pub fn read_f32(&self, address: u64) -> f32 {
+        f32::from_bits(self.read::<u32>(address))
+    }
+
+    /// Writes an `f64` value to memory as its IEEE 754 bit pattern.
+    ///
+    /// # Arguments
+    /// * `address` - The starting address at which to write the value.
+    /// * `value` - The `f64` value to write.
+    ///
+    /// # Returns
+    /// `Ok(())` on success, or the error reported by `write`.
+    pub fn write_f64(&mut self, address: u64, value: f64) -> Result<(), MemoryError> {
+        self.write::<u64>(address, value.to_bits())
+    }
+
+    /// Reads an `f64` value stored as its IEEE 754 bit pattern.
+    ///
+    /// # Arguments
+    /// * `address` - The starting address from which to read the value.
+    ///
+    /// # Returns
+    /// The `f64` value at the given address.
+    pub fn read_f64(&self, address: u64) -> f64 {
+        f64::from_bits(self.read::<u64>(address))
+    }
+
+    /// Writes an `f64` value to memory in the x87 80-bit extended-precision format, as `FSTP m80fp` does.
+    ///
+    /// The 10 bytes hold the significand followed by the sign and exponent word, both little endian.
+    /// The widening is exact; see `Utilities::f64_to_f80_bits`.
+    ///
+    /// # Arguments
+    /// * `address` - The starting address at which to write the value.
+    /// * `value` - The `f64` value to write.
+    ///
+    /// # Returns
+    /// `Ok(())` on success, or the error reported by `write_bytes`, in which case nothing is written.
+    pub fn write_f80(&mut self, address: u64, value: f64) -> Result<(), MemoryError> {
+        let (mantissa, sign_exponent) = Utilities::f64_to_f80_bits(value);
+        let mut bytes = [0u8; 10];
+        bytes[..8].copy_from_slice(&mantissa.to_le_bytes());
+        bytes[8..].copy_from_slice(&sign_exponent.to_le_bytes());
+        self.write_bytes(address, &bytes)
+    }
+
+    /// Reads an x87 80-bit extended-precision value and narrows it to an `f64`, as `FLD m80fp` followed by `FSTP m64fp` would.
+    ///
+    /// The value is rounded to nearest, ties to even; see `Utilities::f80_bits_to_f64`. Like `write_f80`, the
+    /// bytes are always little-endian, whatever the byte order of the memory. Unmapped bytes read as 0.
+    ///
+    /// # Arguments
+    /// * `address` - The starting address from which to read the value.
+    ///
+    /// # Returns
+    /// The nearest `f64` to the value at the given address.
+    pub fn read_f80(&self, address: u64) -> f64 {
+        let mut bytes = [0u8; 10];
+        self.load_lenient(address, &mut bytes);
+        let mantissa = u64::from_le_bytes(bytes[..8].try_into().unwrap());
+        let sign_exponent = u16::from_le_bytes(bytes[8..].try_into().unwrap());
+        Utilities::f80_bits_to_f64(mantissa, sign_exponent)
+    }
+
+    /// Reads a vector of values of type `T` from memory starting at a given address.
+    ///
+    /// Reads multiple values sequentially from memory. The number of values read is determined by `number_of_value`.
+    /// Each value is read from a block of memory with a size equal to `T::size()`.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type implementing `MemoryIO` that determines how bytes are read and interpreted.
+    ///
+    /// # Arguments
+    /// * `address` - The starting address from which to begin reading values.
+    /// * `number_of_value` - The number of values to read.
+    ///
+    /// # Returns
+    /// A vector of values of type `T`.
+    pub fn read_vec<T: MemoryIO>(&self, address: u64, number_of_value: usize) -> Vec<T> {
+        self.load_values(address, number_of_value, false)
+            .map(|value| value.unwrap_or_else(|_| T::from_bytes(&vec![0; T::size()])))
+            .collect()
+    }
+
+    /// Reads values of type `T` into a caller-provided slice.
+    ///
+    /// Fills `out` with consecutive values starting at `address` without allocating, honoring strict mode.
+    /// On error, the values before the failing one have already been stored in `out`.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type implementing `MemoryIO` that determines how bytes are read and interpreted.
+    ///
+    /// # Arguments
+    /// * `address` - The starting address from which to begin reading values.
+    /// * `out` - The slice receiving `out.len()` values.
+    ///
+    /// # Returns
+    /// `Ok(())` on success, or the first error met while reading.
+    pub fn read_vec_into<T: MemoryIO>(&self, address: u64, out: &mut [T]) -> Result<(), MemoryError> {
+        let values = self.load_values(address, out.len(), self.strict);
+        for (slot, value) in out.iter_mut().zip(values) {
+            *slot = value?;
+        }
+        Ok(())
+    }
+
+    /// Reads consecutive values of type `T`, as shared by `read_vec` and `read_vec_into`.
+    ///
+    /// # Arguments
+    /// * `address` - The starting address from which to begin reading values.
+    /// * `count` - The number of values to read.
+    /// * `strict` - Whether unmapped bytes are reported as errors rather than read as 0.
+    ///
+    /// # Returns
+    /// An iterator yielding each value, or the error met while reading it.
+    fn load_values<T: MemoryIO>(&self, address: u64, count: usize, strict: bool) -> impl Iterator<Item = Result<T, MemoryError>> + '_ {
+        (0..count).map(move |i| self.load_value(address.wrapping_add((i * T::size()) as u64), AccessKind::Read, strict))
+    }
+
+    /// Writes a vector of values of type `T` to memory starting at a given address.
+    ///
+    /// Sequentially writes each value in the vector to memory. The address for each subsequent value
+    /// is offset by `T::size()` bytes from the previous one.
     ///
     /// # Type Parameters
-    /// * `T` - The type implementing `MemoryIO` and `Clone` that determines how values are converted to bytes.
+    /// * `T` - The type implementing `MemoryIO` that determines how values are converted to bytes.
     ///
     /// # Arguments
     /// * `address` - The starting address at which to begin writing values.
     /// * `values` - The vector of values to write to memory.
-    pub fn write_vec<T: MemoryIO + Clone>(&mut self, address: usize, values: Vec<T>) {
-        for (i, value) in values.iter().enumerate() {
-            self.write(address + i * T::size(), value.clone());
+    ///
+    /// # Returns
+    /// `Ok(())` on success, or an error if any byte of the whole range is read-only or fails to translate,
+    /// in which case nothing is written.
+    pub fn write_vec<T: MemoryIO>(&mut self, address: u64, values: Vec<T>) -> Result<(), MemoryError> {
+        self.write_slice(address, &values)
+    }
+
+    /// Writes a slice of values of type `T` to memory starting at a given address.
+    ///
+    /// Behaves like `write_vec` without requiring an owned vector or cloning the values.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type implementing `MemoryIO` that determines how values are converted to bytes.
+    ///
+    /// # Arguments
+    /// * `address` - The starting address at which to begin writing values.
+    /// * `values` - The values to write to memory.
+    ///
+    /// # Returns
+    /// `Ok(())` on success, or an error if any byte of the whole range is read-only or fails to translate,
+    /// in which case nothing is written.
+    pub fn write_slice<T: MemoryIO>(&mut self, address: u64, values: &[T]) -> Result<(), MemoryError> {
+        let mut bytes = Vec::with_capacity(values.len() * T::size());
+        for value in values {
+            bytes.extend(value.to_bytes());
+        }
+        self.apply_endianness::<T>(&mut bytes);
+        self.store(address, &bytes)
+    }
+
+    /// Writes raw bytes to memory starting at a given address.
+    ///
+    /// When the range lies inside a single segment the bytes are copied directly into its storage.
+    ///
+    /// # Arguments
+    /// * `address` - The starting address at which to write the bytes.
+    /// * `bytes` - The bytes to write.
+    ///
+    /// # Returns
+    /// `Ok(())` on success, or an error if any byte of the range is read-only or fails to translate,
+    /// in which case nothing is written.
+    pub fn write_bytes(&mut self, address: u64, bytes: &[u8]) -> Result<(), MemoryError> {
+        self.store(address, bytes)
+    }
+
+    /// Reads raw bytes from memory into a caller-provided buffer.
+    ///
+    /// Fills `buffer` with the bytes starting at `address` without allocating, honoring strict mode.
+    ///
+    /// # Arguments
+    /// * `address` - The starting address from which to read the bytes.
+    /// * `buffer` - The buffer receiving `buffer.len()` bytes.
+    ///
+    /// # Returns
+    /// `Ok(())` on success, or the first error met while reading.
+    pub fn read_exact(&self, address: u64, buffer: &mut [u8]) -> Result<(), MemoryError> {
+        self.load(address, buffer, AccessKind::Read, self.strict)
+    }
+
+    /// Borrows a range of guest memory directly from segment storage, without copying.
+    ///
+    /// # Arguments
+    /// * `range` - The half-open range of addresses to view.
+    ///
+    /// # Returns
+    /// The bytes of the range, or `None` if the range is not held by a single RAM segment, touches an
+    /// MMIO region, or paging or a translator is enabled; callers can then fall back to `read_exact`.
+    pub fn view(&self, range: Range<u64>) -> Option<&[u8]> {
+        let (index, offset) = self.find_view(&range)?;
+        Some(&self.segments[index].data[offset..offset + host_length(&range)])
+    }
+
+    /// Mutably borrows a range of guest memory directly from segment storage, without copying.
+    ///
+    /// Every tracked page of the range is marked dirty up front, since writes through the slice can't be observed.
+    ///
+    /// # Arguments
+    /// * `range` - The half-open range of addresses to view.
+    ///
+    /// # Returns
+    /// The bytes of the range, or `None` in the cases `view` returns `None` or if any byte of the range
+    /// is read-only; callers can then fall back to `write_bytes`.
+    pub fn view_mut(&mut self, range: Range<u64>) -> Option<&mut [u8]> {
+        let (index, offset) = self.find_view(&range)?;
+        if self.check_writable(range.start, host_length(&range)).is_err() {
+            return None;
+        }
+        if range.start < range.end {
+            self.mark_dirty(range.clone());
+        }
+        self.segments[index].set_initialized(offset, host_length(&range));
+        Some(&mut self.segments[index].data_mut()[offset..offset + host_length(&range)])
+    }
+
+    /// Locates the segment storage backing a range for `view` and `view_mut`.
+    ///
+    /// # Returns
+    /// The index of the segment and the offset of the range inside its data, or `None` if the range can't be viewed.
+    fn find_view(&self, range: &Range<u64>) -> Option<(usize, usize)> {
+        if range.start > range.end || self.is_translated() {
+            return None;
+        }
+        self.find_run(range.start, host_length(range))
+    }
+
+    /// Copies a range of memory from another `Memory` instance.
+    ///
+    /// Bytes are transferred by guest address, so the two instances may use different base addresses.
+    /// Unmapped source bytes copy as 0, or fail the copy if `src` is in strict mode. The source is not modified.
+    ///
+    /// # Arguments
+    /// * `src` - The memory to copy from.
+    /// * `src_range` - The half-open range of source addresses to copy.
+    /// * `dst_address` - The address in this memory receiving the first byte.
+    ///
+    /// # Returns
+    /// `Ok(())` on success, or the first error met while reading the source or writing the destination,
+    /// in which case nothing is written.
+    pub fn copy_from(&mut self, src: &Memory, src_range: Range<u64>, dst_address: u64) -> Result<(), MemoryError> {
+        let mut bytes = vec![0; host_length(&src_range)];
+        src.read_exact(src_range.start, &mut bytes)?;
+        self.store(dst_address, &bytes)
+    }
+
+    /// Copies bytes within this memory, as `memmove` does.
+    ///
+    /// The whole source range is read before anything is written, so the ranges may overlap in
+    /// either direction without corrupting any byte. Unmapped source bytes copy as 0, or fail
+    /// the copy in strict mode.
+    ///
+    /// # Arguments
+    /// * `dst` - The address receiving the first byte.
+    /// * `src` - The address of the first byte to copy.
+    /// * `length` - The number of bytes to copy.
+    ///
+    /// # Returns
+    /// `Ok(())` on success, or the first error met while reading the source or writing the destination,
+    /// in which case nothing is written.
+    pub fn memmove(&mut self, dst: u64, src: u64, length: usize) -> Result<(), MemoryError> {
+        if dst == src || length == 0 {
+            return Ok(());
+        }
+        let mut bytes = vec![0; length];
+        self.read_exact(src, &mut bytes)?;
+        self.store(dst, &bytes)
+    }
+
+    /// Reads raw bytes from memory, reporting which of them are backed by mapped memory.
+    ///
+    /// Unlike `read_exact`, unmapped bytes (and bytes whose translation faults) are returned as `None`
+    /// instead of being read as 0, so they can be told apart from mapped zeros.
+    ///
+    /// # Arguments
+    /// * `address` - The starting address from which to read the bytes.
+    /// * `length` - The number of bytes to read.
+    ///
+    /// # Returns
+    /// A vector of `length` entries holding `Some(byte)` for mapped bytes and `None` otherwise.
+    pub fn read_bytes_checked(&self, address: u64, length: usize) -> Vec<Option<u8>> {
+        (0..length)
+            .map(|i| {
+                self.translate(address.wrapping_add(i as u64), AccessKind::Read)
+                    .ok()
+                    .and_then(|physical| self.read_byte(physical))
+            })
+            .collect()
+    }
+
+    /// Formats a range of memory in the style of `hexdump -C`.
+    ///
+    /// Each line holds the address of its first byte, sixteen bytes in hex split into two groups
+    /// of eight, and the same bytes as ASCII between bars, with non-printable bytes shown as `.`.
+    /// Unmapped bytes are shown as `??` and `.`. The dump ends with a line holding the address
+    /// just past the range. Identical lines are not collapsed.
+    ///
+    /// Addresses are translated as reads, and MMIO handlers are consulted like any other read.
+    ///
+    /// # Arguments
+    /// * `start` - The address of the first byte to dump.
+    /// * `length` - The number of bytes to dump.
+    ///
+    /// # Returns
+    /// The formatted dump, one line per 16 bytes, each terminated by a newline.
+    pub fn hexdump(&self, start: u64, length: usize) -> String {
+        Self::format_hexdump(start, &self.read_bytes_checked(start, length))
+    }
+
+    /// Formats every RAM region in the style of `hexdump -C`.
+    ///
+    /// The regions are dumped one after another in ascending order of address, each as `hexdump`
+    /// would format it. Addresses are those of the regions themselves and are not translated.
+    /// MMIO and guard regions are skipped.
+    ///
+    /// # Returns
+    /// The concatenated dumps of all RAM regions, or an empty string if no RAM is mapped.
+    pub fn hexdump_all(&self) -> String {
+        self.segments.iter()
+            .map(|segment| {
+                let bytes: Vec<Option<u8>> = segment.data.iter().copied().map(Some).collect();
+                Self::format_hexdump(segment.start_address + self.base_address, &bytes)
+            })
+            .collect()
+    }
+
+    /// Formats bytes starting at a given address in the style of `hexdump -C`, `None` standing for unmapped bytes.
+    fn format_hexdump(start: u64, bytes: &[Option<u8>]) -> String {
+        let mut output = String::new();
+        for (line, chunk) in bytes.chunks(16).enumerate() {
+            let _ = write!(output, "{:08x} ", start.wrapping_add(line as u64 * 16));
+            for i in 0..16 {
+                if i % 8 == 0 {
+                    output.push(' ');
+                }
+                match chunk.get(i) {
+                    Some(Some(byte)) => { let _ = write!(output, "{:02x} ", byte); }
+                    Some(None) => output.push_str("?? "),
+                    None => output.push_str("   "),
+                }
+            }
+            output.push_str(" |");
+            output.extend(chunk.iter().map(|byte| match byte {
+                Some(byte) if byte.is_ascii_graphic() || *byte == b' ' => *byte as char,
+                _ => '.',
+            }));
+            output.push_str("|\n");
+        }
+        let _ = writeln!(output, "{:08x}", start.wrapping_add(bytes.len() as u64));
+        output
+    }
+
+    /// Reads a value of type `T` together with a per-byte validity mask.
+    ///
+    /// Unmapped bytes contribute 0 to the value and a cleared bit to the mask.
+    ///
+    /// # Type Parameters
+    /// * `T` - The type implementing `MemoryIO` that determines how bytes are read and interpreted.
+    ///
+    /// # Arguments
+    /// * `address` - The starting address from which to read the bytes.
+    ///
+    /// # Returns
+    /// The value read and a `BitVec` whose bit `i` is set if byte `i` of the value was mapped.
+    pub fn read_checked<T: MemoryIO>(&self, address: u64) -> (T, BitVec) {
+        let bytes = self.read_bytes_checked(address, T::size());
+        let mask = bytes.iter().map(Option::is_some).collect();
+        let mut value: Vec<u8> = bytes.iter().map(|byte| byte.unwrap_or(0)).collect();
+        self.apply_endianness::<T>(&mut value);
+        (T::from_bytes(&value), mask)
+    }
+
+    /// Fills a range of memory with a single byte value.
+    ///
+    /// # Arguments
+    /// * `address` - The starting address of the range.
+    /// * `length` - The number of bytes to fill.
+    /// * `value` - The byte value to store in every position of the range.
+    ///
+    /// # Returns
+    /// `Ok(())` on success, or an error if any byte of the range is read-only or fails to translate,
+    /// in which case nothing is written.
+    pub fn fill(&mut self, address: u64, length: usize, value: u8) -> Result<(), MemoryError> {
+        self.store(address, &vec![value; length])
+    }
+
+    /// Fills a range of memory by repeating a byte pattern.
+    ///
+    /// The pattern is tiled from the start of the range, so the last repetition is truncated when
+    /// `length` is not a multiple of the pattern length. The range is written through the bulk write path.
+    ///
+    /// # Arguments
+    /// * `address` - The starting address of the range.
+    /// * `length` - The number of bytes to fill.
+    /// * `pattern` - The bytes to repeat. An empty pattern leaves memory unchanged.
+    ///
+    /// # Returns
+    /// `Ok(())` on success, or an error if any byte of the range is read-only or fails to translate,
+    /// in which case nothing is written.
+    pub fn fill_pattern(&mut self, address: u64, length: usize, pattern: &[u8]) -> Result<(), MemoryError> {
+        if pattern.is_empty() {
+            return Ok(());
+        }
+        let bytes: Vec<u8> = pattern.iter().copied().cycle().take(length).collect();
+        self.store(address, &bytes)
+    }
+
+    /// Fills memory with repetitions of a 32-bit value.
+    ///
+    /// # Arguments
+    /// * `address` - The starting address of the range.
+    /// * `count` - The number of `u32` values to write.
+    /// * `value` - The value to repeat, stored little-endian.
+    ///
+    /// # Returns
+    /// `Ok(())` on success, or an error if any byte of the range is read-only or fails to translate,
+    /// in which case nothing is written.
+    pub fn fill_u32(&mut self, address: u64, count: usize, value: u32) -> Result<(), MemoryError> {
+        self.fill_pattern(address, count * 4, &value.to_le_bytes())
+    }
+
+    /// Iterates over the non-zero bytes of RAM in address order.
+    ///
+    /// The iterator borrows the segment storage directly and skips zero bytes and unmapped gaps.
+    /// MMIO regions are not visited.
+    ///
+    /// # Returns
+    /// An iterator of `(address, value)` pairs for every mapped byte whose value is not 0.
+    pub fn iter_nonzero(&self) -> impl Iterator<Item = (u64, u8)> + '_ {
+        let base = self.base_address;
+        self.segments.iter().flat_map(move |segment| {
+            segment.data.iter().enumerate()
+                .filter(|(_, value)| **value != 0)
+                .map(move |(offset, value)| (base + segment.start_address + offset as u64, *value))
+        })
+    }
+
+    /// Finds the first non-zero byte of RAM at or after a given address.
+    ///
+    /// # Arguments
+    /// * `address` - The address from which to start searching.
+    ///
+    /// # Returns
+    /// The address of the first mapped non-zero byte at or after `address`, or `None` if there is none.
+    pub fn first_nonzero_after(&self, address: u64) -> Option<u64> {
+        let real_address = address.saturating_sub(self.base_address);
+        for segment in &self.segments {
+            if segment.end() <= real_address {
+                continue;
+            }
+            let skip = real_address.saturating_sub(segment.start_address) as usize;
+            if let Some(offset) = segment.data[skip..].iter().position(|value| *value != 0) {
+                return Some(self.base_address + segment.start_address + (skip + offset) as u64);
+            }
+        }
+        None
+    }
+
+    /// Computes the 64-bit FNV-1a checksum of a range of memory.
+    ///
+    /// The checksum covers the bytes as seen by `read_exact`, so it depends only on the contents of the range
+    /// and not on how it is split into segments. Unmapped bytes count as 0, or fail the checksum in strict mode.
+    ///
+    /// # Arguments
+    /// * `range` - The half-open range of addresses to checksum.
+    ///
+    /// # Returns
+    /// The FNV-1a hash of the bytes (offset basis `0xcbf29ce484222325`, prime `0x100000001b3`),
+    /// or the first error met while reading.
+    pub fn checksum(&self, range: Range<u64>) -> Result<u64, MemoryError> {
+        let mut hash = FNV_OFFSET_BASIS;
+        self.for_each_chunk(range, |chunk| {
+            for byte in chunk {
+                hash = (hash ^ *byte as u64).wrapping_mul(FNV_PRIME);
+            }
+        })?;
+        Ok(hash)
+    }
+
+    /// Feeds the bytes of a range of memory into a `Hasher`.
+    ///
+    /// The bytes are passed with `Hasher::write` in chunks of unspecified size, as seen by `read_exact`.
+    ///
+    /// # Arguments
+    /// * `range` - The half-open range of addresses to hash.
+    /// * `hasher` - The hasher receiving the bytes.
+    ///
+    /// # Returns
+    /// `Ok(())` on success, or the first error met while reading.
+    pub fn hash_into<H: Hasher>(&self, range: Range<u64>, hasher: &mut H) -> Result<(), MemoryError> {
+        self.for_each_chunk(range, |chunk| hasher.write(chunk))
+    }
+
+    /// Reads a range of memory in fixed-size chunks, passing each to a callback.
+    fn for_each_chunk<F: FnMut(&[u8])>(&self, range: Range<u64>, mut f: F) -> Result<(), MemoryError> {
+        let mut buffer = [0u8; CHUNK_SIZE];
+        let mut address = range.start;
+        while address < range.end {
+            let length = (range.end - address).min(CHUNK_SIZE as u64) as usize;
+            self.read_exact(address, &mut buffer[..length])?;
+            f(&buffer[..length]);
+            address += length as u64;
+        }
+        Ok(())
+    }
+
+    /// Compares two ranges of memory lexicographically, as `memcmp` does.
+    ///
+    /// The ranges may overlap. Unmapped bytes compare as 0, and a chunk whose translation faults compares as all zeros.
+    ///
+    /// # Arguments
+    /// * `address_a` - The start of the first range.
+    /// * `address_b` - The start of the second range.
+    /// * `length` - The number of bytes to compare.
+    ///
+    /// # Returns
+    /// The ordering of the first range relative to the second.
+    pub fn compare(&self, address_a: u64, address_b: u64, length: usize) -> Ordering {
+        let mut buffer_a = [0u8; CHUNK_SIZE];
+        let mut buffer_b = [0u8; CHUNK_SIZE];
+        let mut offset = 0;
+        while offset < length {
+            let chunk = (length - offset).min(CHUNK_SIZE);
+            self.load_lenient(address_a.wrapping_add(offset as u64), &mut buffer_a[..chunk]);
+            self.load_lenient(address_b.wrapping_add(offset as u64), &mut buffer_b[..chunk]);
+            match buffer_a[..chunk].cmp(&buffer_b[..chunk]) {
+                Ordering::Equal => offset += chunk,
+                ordering => return ordering,
+            }
+        }
+        Ordering::Equal
+    }
+
+    /// Returns whether memory starting at an address holds exactly the expected bytes.
+    ///
+    /// Unmapped bytes compare as 0.
+    ///
+    /// # Arguments
+    /// * `address` - The start of the range to check.
+    /// * `expected` - The bytes the range should hold.
+    pub fn equal_to_slice(&self, address: u64, expected: &[u8]) -> bool {
+        self.first_mismatch(address, expected).is_none()
+    }
+
+    /// Finds the first byte where memory differs from the expected bytes.
+    ///
+    /// Unmapped bytes compare as 0.
+    ///
+    /// # Arguments
+    /// * `address` - The start of the range to check.
+    /// * `expected` - The bytes the range should hold.
+    ///
+    /// # Returns
+    /// The offset into `expected` of the first differing byte, or `None` if the range matches.
+    pub fn first_mismatch(&self, address: u64, expected: &[u8]) -> Option<usize> {
+        let mut buffer = [0u8; CHUNK_SIZE];
+        for (index, chunk) in expected.chunks(CHUNK_SIZE).enumerate() {
+            let offset = index * CHUNK_SIZE;
+            let actual = &mut buffer[..chunk.len()];
+            self.load_lenient(address.wrapping_add(offset as u64), actual);
+            if actual != chunk {
+                return actual.iter().zip(chunk).position(|(a, b)| a != b).map(|position| offset + position);
+            }
+        }
+        None
+    }
+
+    /// Compares memory against the expected bytes, failing on bytes that can't be read.
+    ///
+    /// Unlike `equal_to_slice` and `first_mismatch`, unmapped bytes do not compare as 0: every byte of the
+    /// range must be mapped, and bytes never written while poisoning was enabled are reported as well.
+    ///
+    /// # Arguments
+    /// * `address` - The start of the range to check.
+    /// * `expected` - The bytes the range should hold.
+    ///
+    /// # Returns
+    /// `Ok(None)` if the range matches, `Ok(Some(offset))` with the offset into `expected` of the first
+    /// differing byte, or the first error met while reading the range, such as `MemoryError::Unmapped`
+    /// or `MemoryError::Uninitialized`.
+    pub fn equal_to_slice_strict(&self, address: u64, expected: &[u8]) -> Result<Option<usize>, MemoryError> {
+        let mut buffer = [0u8; CHUNK_SIZE];
+        let mut mismatch = None;
+        for (index, chunk) in expected.chunks(CHUNK_SIZE).enumerate() {
+            let offset = index * CHUNK_SIZE;
+            let actual = &mut buffer[..chunk.len()];
+            self.load(address.wrapping_add(offset as u64), actual, AccessKind::Read, true)?;
+            if mismatch.is_none() {
+                mismatch = actual.iter().zip(chunk).position(|(a, b)| a != b).map(|position| offset + position);
+            }
+        }
+        Ok(mismatch)
+    }
+
+    /// Lists the ranges of RAM whose contents differ between two memories.
+    ///
+    /// An address differs if it is backed by RAM in only one of the memories, or in both with different
+    /// bytes. Addresses are compared as stored, without translation; MMIO and guard regions, protections
+    /// and whether bytes are initialized are not compared.
+    ///
+    /// # Arguments
+    /// * `other` - The memory to compare against.
+    ///
+    /// # Returns
+    /// The maximal half-open ranges of differing addresses in ascending order, or an empty vector if the
+    /// RAM of both memories is identical.
+    pub fn diff(&self, other: &Memory) -> Vec<Range<u64>> {
+        let mut bounds: Vec<u64> = self.ram_ranges().chain(other.ram_ranges())
+            .flat_map(|range| [range.start, range.end])
+            .collect();
+        bounds.sort_unstable();
+        bounds.dedup();
+        let mut ranges: Vec<Range<u64>> = Vec::new();
+        let mut push = |range: Range<u64>| match ranges.last_mut() {
+            Some(last) if last.end == range.start => last.end = range.end,
+            _ => ranges.push(range),
+        };
+        for pair in bounds.windows(2) {
+            let range = pair[0]..pair[1];
+            match (self.ram_slice(&range), other.ram_slice(&range)) {
+                (Some(ours), Some(theirs)) => {
+                    for (offset, _) in ours.iter().zip(theirs).enumerate().filter(|(_, (a, b))| a != b) {
+                        let address = range.start + offset as u64;
+                        push(address..address + 1);
+                    }
+                }
+                (None, None) => {}
+                _ => push(range),
+            }
+        }
+        ranges
+    }
+
+    /// Returns the guest address ranges held by each RAM segment, in ascending order.
+    fn ram_ranges(&self) -> impl Iterator<Item = Range<u64>> + '_ {
+        self.segments.iter().map(|segment| segment.start_address + self.base_address..segment.end() + self.base_address)
+    }
+
+    /// Borrows a range of RAM held by a single segment, without translation.
+    ///
+    /// # Returns
+    /// The bytes of the range, or `None` if its first byte is not backed by RAM. The caller must ensure
+    /// that the range does not cross a segment boundary.
+    fn ram_slice(&self, range: &Range<u64>) -> Option<&[u8]> {
+        let real_address = range.start.checked_sub(self.base_address)?;
+        let segment = &self.segments[self.find_segment(real_address)?];
+        let offset = (real_address - segment.start_address) as usize;
+        Some(&segment.data[offset..offset + host_length(range)])
+    }
+
+    /// Reads raw bytes like `read`, filling the whole buffer with zeros if the translator faults.
+    fn load_lenient(&self, address: u64, buffer: &mut [u8]) {
+        if self.load(address, buffer, AccessKind::Read, false).is_err() {
+            buffer.fill(0);
+        }
+    }
+
+    /// Searches RAM for the first occurrence of a byte pattern.
+    ///
+    /// Matches may straddle segment boundaries as long as the bytes are contiguous; an unmapped gap breaks a match.
+    ///
+    /// # Arguments
+    /// * `pattern` - The bytes to search for.
+    /// * `range` - The range of addresses the whole match must lie in, or `None` to search everywhere.
+    ///
+    /// # Returns
+    /// The address of the first match, or `None` if the pattern does not occur.
+    pub fn find(&self, pattern: &[u8], range: Option<Range<u64>>) -> Option<u64> {
+        self.find_masked(pattern, &vec![true; pattern.len()], range).into_iter().next()
+    }
+
+    /// Searches RAM for every occurrence of a byte pattern.
+    ///
+    /// # Arguments
+    /// * `pattern` - The bytes to search for.
+    /// * `range` - The range of addresses each match must lie in, or `None` to search everywhere.
+    ///
+    /// # Returns
+    /// The addresses of all matches in ascending order; overlapping matches are all reported.
+    pub fn find_all(&self, pattern: &[u8], range: Option<Range<u64>>) -> Vec<u64> {
+        self.find_masked(pattern, &vec![true; pattern.len()], range)
+    }
+
+    /// Searches RAM for every occurrence of a byte pattern containing wildcards.
+    ///
+    /// This is useful for signature scanning, where some bytes of the signature vary.
+    ///
+    /// # Arguments
+    /// * `pattern` - The bytes to search for.
+    /// * `mask` - For each pattern byte, `true` if it must match and `false` if it is a wildcard.
+    /// * `range` - The range of addresses each match must lie in, or `None` to search everywhere.
+    ///
+    /// # Returns
+    /// The addresses of all matches in ascending order. An empty pattern, or a mask whose length
+    /// differs from the pattern, matches nothing.
+    pub fn find_masked(&self, pattern: &[u8], mask: &[bool], range: Option<Range<u64>>) -> Vec<u64> {
+        let mut matches = Vec::new();
+        if pattern.is_empty() || mask.len() != pattern.len() {
+            return matches;
+        }
+        let range = range.unwrap_or(0..u64::MAX);
+        let mut i = 0;
+        while i < self.segments.len() {
+            // collect a run of contiguous segments
+            let start = self.base_address + self.segments[i].start_address;
+            let mut run: Vec<u8> = self.segments[i].data.to_vec();
+            i += 1;
+            while i < self.segments.len() && self.base_address + self.segments[i].start_address == start + run.len() as u64 {
+                run.extend_from_slice(&self.segments[i].data);
+                i += 1;
+            }
+            for (offset, window) in run.windows(pattern.len()).enumerate() {
+                let address = start + offset as u64;
+                if address < range.start || address + pattern.len() as u64 > range.end {
+                    continue;
+                }
+                if window.iter().zip(pattern).zip(mask).all(|((byte, expected), care)| !care || byte == expected) {
+                    matches.push(address);
+                }
+            }
         }
+        matches
+    }
+}
+
+impl Default for Memory {
+    /// Creates an empty memory with a base address of 0.
+    fn default() -> Self {
+        Memory::new(0)
+    }
+}
+
+/// Implements the `Debug` trait for `Memory`, showing the base address and the mapped regions.
+impl std::fmt::Debug for Memory {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Memory")
+            .field("base_address", &self.base_address)
+            .field("regions", &self.regions().collect::<Vec<_>>())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Generates memory based at 0 holding up to eight runs of random bytes at random addresses
+/// below 4 GiB, each up to 512 bytes long. Runs may overlap or share a segment.
+#[cfg(feature = "proptest")]
+impl proptest::arbitrary::Arbitrary for Memory {
+    type Parameters = ();
+    type Strategy = proptest::strategy::BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        use proptest::prelude::*;
+        proptest::collection::vec((0..1u64 << 32, proptest::collection::vec(any::<u8>(), 1..=DEFAULT_SIZE)), 0..8)
+            .prop_map(|runs| {
+                let mut memory = Memory::new(0);
+                for (address, bytes) in runs {
+                    memory.write_bytes(address, &bytes).unwrap();
+                }
+                memory
+            })
+            .boxed()
     }
 }
@@ -1,9 +1,10 @@
 extern crate bit_vec;
 extern crate regex;
 
-use std::fmt::{Display, Formatter};
+use std::fmt::{Display, Formatter, Write as _};
 use bit_vec::BitVec;
 use regex::Regex;
+use crate::{RegisterRef, Utilities};
 
 // trait alias and enum
 /// A trait alias representing a collection of traits necessary for section compatibility.
@@ -26,11 +27,35 @@ impl<T:
 ///
 /// This enum represents various SIMD registers, such as XMM, YMM, and ZMM, which are
 /// commonly used in advanced processor features for parallel data processing.
-#[derive(PartialEq, Eq, Hash, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
 pub enum VecRegName {
     XMM, YMM, ZMM
 }
 
+impl VecRegName {
+    /// Returns the width of the register in bits: 128 for XMM, 256 for YMM and 512 for ZMM.
+    pub fn width_bits(self) -> usize {
+        match self {
+            VecRegName::XMM => 128,
+            VecRegName::YMM => 256,
+            VecRegName::ZMM => 512,
+        }
+    }
+
+    /// Returns the width of the register in bytes.
+    pub fn width_bytes(self) -> usize {
+        self.width_bits() / 8
+    }
+
+    /// Returns the number of `T`-typed lanes that fit in the register.
+    ///
+    /// # Type Parameters
+    /// `T` - The type of the lanes.
+    pub fn lane_count<T: SectionCompatible>(self) -> usize {
+        Utilities::lane_count(&self, Utilities::element_bits::<T>())
+    }
+}
+
 /// Implements the `Display` trait for `VecRegName`.
 ///
 /// This implementation allows for the human-readable representation of the SIMD vector
@@ -50,7 +75,7 @@ impl Display for VecRegName {
 ///
 /// This enum includes register names for various sizes: 64-bit (RAX, RBX, ...),
 /// 32-bit (EAX, EBX, ...), 16-bit (AX, BX, ...), and 8-bit (AH, AL, ...).
-#[derive(PartialEq, Eq, Hash, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
 pub enum GPRName {
     // 64-bit registers
     RAX, RBX, RCX, RDX, RSI, RDI, RBP, RSP,
@@ -146,9 +171,82 @@ impl Display for GPRName {
     }
 }
 
+macro_rules! register_alias {
+    ($reg_name:expr; $( $r64:ident, $r32:ident, $r16:ident, $r8_l:ident, $r8_h:ident ),*; $( $r64_:ident, $r32_:ident, $r16_:ident, $r8_:ident ),* ) => {
+        match $reg_name {
+            $(
+                GPRName::$r64 => (GPRName::$r64, 0, 63),
+                GPRName::$r32 => (GPRName::$r64, 0, 31),
+                GPRName::$r16 => (GPRName::$r64, 0, 15),
+                GPRName::$r8_l => (GPRName::$r64, 0, 7),
+                GPRName::$r8_h => (GPRName::$r64, 8, 15),
+            )*
+            $(
+                GPRName::$r64_ => (GPRName::$r64_, 0, 63),
+                GPRName::$r32_ => (GPRName::$r64_, 0, 31),
+                GPRName::$r16_ => (GPRName::$r64_, 0, 15),
+                GPRName::$r8_ => (GPRName::$r64_, 0, 7),
+            )*
+        }
+    }
+}
+
+impl GPRName {
+    /// Every GPR name, in declaration order.
+    pub const ALL: [GPRName; 68] = [
+        GPRName::RAX, GPRName::RBX, GPRName::RCX, GPRName::RDX, GPRName::RSI, GPRName::RDI, GPRName::RBP, GPRName::RSP,
+        GPRName::R8, GPRName::R9, GPRName::R10, GPRName::R11, GPRName::R12, GPRName::R13, GPRName::R14, GPRName::R15,
+        GPRName::EAX, GPRName::EBX, GPRName::ECX, GPRName::EDX, GPRName::ESI, GPRName::EDI, GPRName::EBP, GPRName::ESP,
+        GPRName::R8D, GPRName::R9D, GPRName::R10D, GPRName::R11D, GPRName::R12D, GPRName::R13D, GPRName::R14D, GPRName::R15D,
+        GPRName::AX, GPRName::BX, GPRName::CX, GPRName::DX, GPRName::SI, GPRName::DI, GPRName::BP, GPRName::SP,
+        GPRName::R8W, GPRName::R9W, GPRName::R10W, GPRName::R11W, GPRName::R12W, GPRName::R13W, GPRName::R14W, GPRName::R15W,
+        GPRName::AH, GPRName::BH, GPRName::CH, GPRName::DH, GPRName::AL, GPRName::BL, GPRName::CL, GPRName::DL,
+        GPRName::SIL, GPRName::DIL, GPRName::BPL, GPRName::SPL,
+        GPRName::R8B, GPRName::R9B, GPRName::R10B, GPRName::R11B, GPRName::R12B, GPRName::R13B, GPRName::R14B, GPRName::R15B,
+    ];
+
+    /// Returns the 64-bit register holding this register, e.g. RAX for EAX, AX, AL and AH.
+    ///
+    /// 64-bit registers return themselves.
+    pub fn canonical(self) -> GPRName {
+        self.alias().0
+    }
+
+    /// Returns the `(low, high)` bit positions this register occupies within its 64-bit parent, both inclusive.
+    ///
+    /// For example AH occupies `(8, 15)` and EAX occupies `(0, 31)`.
+    pub fn bit_range(self) -> (usize, usize) {
+        let (_, low, high) = self.alias();
+        (low, high)
+    }
+
+    /// Returns the 64-bit parent register and the bit range this register occupies within it.
+    fn alias(self) -> (GPRName, usize, usize) {
+        register_alias!(self;
+            RAX, EAX, AX, AL, AH,
+            RBX, EBX, BX, BL, BH,
+            RCX, ECX, CX, CL, CH,
+            RDX, EDX, DX, DL, DH;
+            R8, R8D, R8W, R8B,
+            R9, R9D, R9W, R9B,
+            R10, R10D, R10W, R10B,
+            R11, R11D, R11W, R11B,
+            R12, R12D, R12W, R12B,
+            R13, R13D, R13W, R13B,
+            R14, R14D, R14W, R14B,
+            R15, R15D, R15W, R15B,
+            RSP, ESP, SP, SPL,
+            RBP, EBP, BP, BPL,
+            RSI, ESI, SI, SIL,
+            RDI, EDI, DI, DIL
+        )
+    }
+}
+
 /// An enumeration of flag register names for different bit sizes.
 ///
 /// Includes RFLAGS for 64-bit, EFLAGS for 32-bit, and FLAGS for 16-bit registers.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
 pub enum FLAGSName {
     // 64-bit registers
     RFLAGS,
@@ -161,6 +259,7 @@ pub enum FLAGSName {
 /// An enumeration of Instruction Pointer register names for various sizes.
 ///
 /// This enum includes RIP for 64-bit, EIP for 32-bit, and IP for 16-bit registers.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
 pub enum IPName {
     // 64-bit registers
     RIP,
@@ -170,6 +269,48 @@ pub enum IPName {
     IP
 }
 
+/// An enumeration of AVX-512 opmask register names.
+///
+/// The eight 64-bit opmask registers K0 to K7 hold per-lane predicates for masked SIMD operations.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub enum KMaskName {
+    K0, K1, K2, K3, K4, K5, K6, K7
+}
+
+impl KMaskName {
+    /// Every opmask register name, in declaration order.
+    pub const ALL: [KMaskName; 8] = [
+        KMaskName::K0, KMaskName::K1, KMaskName::K2, KMaskName::K3,
+        KMaskName::K4, KMaskName::K5, KMaskName::K6, KMaskName::K7,
+    ];
+}
+
+/// Implements the `Display` trait for `KMaskName`.
+impl Display for KMaskName {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "K{}", *self as usize)
+    }
+}
+
+/// An enumeration of the processor operating modes that affect register visibility.
+///
+/// In 32-bit mode only SIMD registers 0 to 7 can be encoded; 64-bit mode exposes all 32
+/// (16 to 31 through EVEX encodings).
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub enum CPUMode {
+    Mode32, Mode64
+}
+
+impl CPUMode {
+    /// Returns the number of SIMD registers accessible in this mode.
+    pub fn simd_register_count(self) -> usize {
+        match self {
+            CPUMode::Mode32 => 8,
+            CPUMode::Mode64 => 32,
+        }
+    }
+}
+
 /// Extracts two usize values from a string formatted as "[value1:value2]".
 ///
 /// This function uses regular expressions to parse a string and extract two numerical
@@ -192,10 +333,63 @@ fn extract_values(s: &str) -> Option<(usize, usize)> {
     })
 }
 
+/// The 64-bit GPRs in the order they are stored in `Registers`.
+pub(crate) const GPR64_NAMES: [GPRName; 16] = [
+    GPRName::RAX, GPRName::RBX, GPRName::RCX, GPRName::RDX,
+    GPRName::RSI, GPRName::RDI, GPRName::RBP, GPRName::RSP,
+    GPRName::R8, GPRName::R9, GPRName::R10, GPRName::R11,
+    GPRName::R12, GPRName::R13, GPRName::R14, GPRName::R15,
+];
+
+// RFLAGS status and control flag masks
+pub(crate) const CF: u64 = 1 << 0;
+pub(crate) const PF: u64 = 1 << 2;
+pub(crate) const AF: u64 = 1 << 4;
+pub(crate) const ZF: u64 = 1 << 6;
+pub(crate) const SF: u64 = 1 << 7;
+pub(crate) const TF: u64 = 1 << 8;
+pub(crate) const IF: u64 = 1 << 9;
+pub(crate) const DF: u64 = 1 << 10;
+pub(crate) const OF: u64 = 1 << 11;
+
+/// The masks and names of the RFLAGS status and control flags listed by `Registers::format_table`.
+const RFLAGS_NAMES: [(u64, &str); 9] = [
+    (CF, "CF"), (PF, "PF"), (AF, "AF"), (ZF, "ZF"), (SF, "SF"), (TF, "TF"), (IF, "IF"), (DF, "DF"), (OF, "OF"),
+];
+
+/// The length in bytes of the binary form produced by `Registers::to_bytes`.
+pub const REGISTERS_BYTES_LEN: usize = 32 * 64 + 16 * 8 + 8 + 8 + 8 * 8;
+
+/// An error reported when decoding a binary snapshot fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeserializeError {
+    /// The input does not have the length the format requires.
+    InvalidLength { expected: usize, actual: usize },
+    /// The input ends in the middle of the field starting at `offset`.
+    Truncated { offset: usize },
+    /// A memory segment lies below the base address, overlaps or precedes the previous segment,
+    /// or runs past the end of the address space.
+    InvalidSegment { address: u64 },
+}
+
+/// Implements the `Display` trait for `DeserializeError`.
+impl Display for DeserializeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DeserializeError::InvalidLength { expected, actual } => write!(f, "expected {} bytes, got {}", expected, actual),
+            DeserializeError::Truncated { offset } => write!(f, "input truncated at offset {}", offset),
+            DeserializeError::InvalidSegment { address } => write!(f, "invalid memory segment at {:#x}", address),
+        }
+    }
+}
+
+impl std::error::Error for DeserializeError {}
+
 /// Represents a SIMD register with a dynamic bit vector.
 ///
 /// This struct manages a SIMD register's state using a bit vector, providing methods
 /// to set and get individual bits, clear the register, and manipulate register sections.
+#[derive(Debug)]
 struct SIMDRegister {
     bits: BitVec,
 }
@@ -203,7 +397,8 @@ struct SIMDRegister {
 /// Represents a General Purpose Register (GPR) with a 64-bit value.
 ///
 /// This struct encapsulates a 64-bit GPR, providing methods to set and get its value.
-struct GPR {
+#[derive(Debug, Default, Clone, Copy)]
+struct Gpr {
     value: u64,
 }
 
@@ -211,11 +406,14 @@ struct GPR {
 ///
 /// This struct includes SIMD registers, general-purpose registers (GPRs), flag registers,
 /// and instruction pointers, along with methods to manipulate these registers.
+#[derive(Debug)]
 pub struct Registers {
     simd_registers: [SIMDRegister; 32],
-    gpr: [GPR; 16],
+    gpr: [Gpr; 16],
     rflags: u64,
     rip: u64,
+    kmask: [u64; 8],
+    mode: CPUMode,
 }
 
 impl SIMDRegister {
@@ -267,7 +465,7 @@ impl SIMDRegister {
     /// A vector of `T` elements representing sections of the register.
     fn get_sections<T: SectionCompatible>(&self) -> Vec<T> {
         let mut sections = Vec::new();
-        let type_bits = std::mem::size_of::<T>() * 8;
+        let type_bits = Utilities::element_bits::<T>();
         for i in (0..self.bits.len()).step_by(type_bits) {
             let mut section_value: T = T::from(0u8);
             for j in 0..type_bits {
@@ -295,7 +493,7 @@ impl SIMDRegister {
     /// `true` if the operation was successful, `false` otherwise.
     fn set_by_sections<T: SectionCompatible>(&mut self, sections: Vec<T>) -> bool {
         self.clear();
-        let type_bits = std::mem::size_of::<T>() * 8;
+        let type_bits = Utilities::element_bits::<T>();
         if type_bits * sections.len() != self.bits.len() {
             return false;
         }
@@ -327,7 +525,7 @@ impl SIMDRegister {
     /// A `T` value representing the bits from the specified range.
     fn get_by_index<T: SectionCompatible>(&self, start_index: usize, end_index: usize) -> T {
         let size = end_index - start_index + 1;
-        let type_bits = std::mem::size_of::<T>() * 8;
+        let type_bits = Utilities::element_bits::<T>();
         if type_bits < size {
             panic!("Invalid T size for getting value from {} to {}", start_index, end_index);
         }
@@ -353,7 +551,7 @@ impl SIMDRegister {
     /// * `end_index` - The ending index of the range.
     /// * `value` - The value to set in the specified range.
     fn set_by_index<T: SectionCompatible>(&mut self, start_index: usize, end_index: usize, value: T) {
-        let type_bits = std::mem::size_of::<T>() * 8;
+        let type_bits = Utilities::element_bits::<T>();
         for i in start_index..=end_index {
             if i >= self.bits.len() {
                 break;
@@ -369,14 +567,7 @@ impl SIMDRegister {
     }
 }
 
-impl GPR {
-    /// Creates a new General Purpose Register (GPR) initialized to 0.
-    fn new() -> Self {
-        GPR {
-            value: 0,
-        }
-    }
-
+impl Gpr {
     /// Sets the value of the GPR.
     ///
     /// # Arguments
@@ -394,16 +585,6 @@ impl GPR {
     }
 }
 
-impl Clone for GPR {
-    fn clone(&self) -> Self {
-        GPR {
-            value: self.value
-        }
-    }
-}
-
-impl Copy for GPR {}
-
 macro_rules! register_set {
     ($self:ident; $reg_name:expr; $value:expr; $( $r64:ident, $r32:ident, $r16:ident, $r8_l:ident, $r8_h:ident ),*; $( $r64_:ident, $r32_:ident, $r16_:ident, $r8_:ident ),* ) => {
         match $reg_name {
@@ -442,38 +623,53 @@ macro_rules! register_get {
     }
 }
 
+impl Default for SIMDRegister {
+    /// Creates a 512-bit register, wide enough for ZMM, with all bits cleared.
+    fn default() -> Self {
+        SIMDRegister::new(512)
+    }
+}
+
+impl Default for Registers {
+    /// Creates registers in their reset state, as `Registers::new` does.
+    fn default() -> Self {
+        Registers::new()
+    }
+}
+
 impl Registers {
     /// Creates a new Registers struct with initialized values.
     ///
     /// Initializes SIMD registers, GPRs, flag registers, and instruction pointers.
     pub fn new() -> Self {
         Registers {
-            simd_registers: [
-                SIMDRegister::new(512), SIMDRegister::new(512),
-                SIMDRegister::new(512), SIMDRegister::new(512),
-                SIMDRegister::new(512), SIMDRegister::new(512),
-                SIMDRegister::new(512), SIMDRegister::new(512),
-                SIMDRegister::new(512), SIMDRegister::new(512),
-                SIMDRegister::new(512), SIMDRegister::new(512),
-                SIMDRegister::new(512), SIMDRegister::new(512),
-                SIMDRegister::new(512), SIMDRegister::new(512),
-                SIMDRegister::new(512), SIMDRegister::new(512),
-                SIMDRegister::new(512), SIMDRegister::new(512),
-                SIMDRegister::new(512), SIMDRegister::new(512),
-                SIMDRegister::new(512), SIMDRegister::new(512),
-                SIMDRegister::new(512), SIMDRegister::new(512),
-                SIMDRegister::new(512), SIMDRegister::new(512),
-                SIMDRegister::new(512), SIMDRegister::new(512),
-                SIMDRegister::new(512), SIMDRegister::new(512),
-            ],
-            gpr: [
-                GPR::new(); 16
-            ],
+            simd_registers: std::array::from_fn(|_| SIMDRegister::default()),
+            gpr: [Gpr::default(); 16],
             rflags: 0u64,
             rip: 0u64,
+            kmask: [0u64; 8],
+            mode: CPUMode::Mode64,
         }
     }
 
+    /// Sets the operating mode, which decides how many SIMD registers are accessible.
+    pub fn set_mode(&mut self, mode: CPUMode) {
+        self.mode = mode;
+    }
+
+    /// Returns the operating mode.
+    pub fn mode(&self) -> CPUMode {
+        self.mode
+    }
+
+    /// Returns whether a SIMD register index is accessible in the current mode.
+    ///
+    /// # Arguments
+    /// * `reg_index` - The index of the register.
+    pub fn is_simd_accessible(&self, reg_index: usize) -> bool {
+        reg_index < self.mode.simd_register_count()
+    }
+
     /// Sets a specific bit in a specified SIMD register.
     ///
     /// # Arguments
@@ -482,6 +678,10 @@ impl Registers {
     /// * `bit_position` - The position of the bit to set.
     /// * `value` - The value to set the bit to.
     pub fn set_bit(&mut self, reg_type: VecRegName, reg_index: usize, bit_position: usize, value: bool) {
+        if !self.is_simd_accessible(reg_index) {
+            eprintln!("Invalid register index {} in {:?}", reg_index, self.mode);
+            return;
+        }
         match reg_type {
             VecRegName::XMM if bit_position < 128 => {
                 self.simd_registers[reg_index].set_bit(bit_position, value);
@@ -506,6 +706,9 @@ impl Registers {
     /// # Returns
     /// The value of the bit at the specified position, or `None` if invalid.
     pub fn get_bit(&self, reg_type: VecRegName, reg_index: usize, bit_position: usize) -> Option<bool> {
+        if !self.is_simd_accessible(reg_index) {
+            return None;
+        }
         match reg_type {
             VecRegName::XMM if bit_position < 128 => {
                 Some(self.simd_registers[reg_index].get_bit(bit_position))
@@ -525,9 +728,38 @@ impl Registers {
     /// # Arguments
     /// * `reg_index` - The index of the register to clear.
     pub fn clear(&mut self, reg_index: usize) {
+        if !self.is_simd_accessible(reg_index) {
+            return;
+        }
         self.simd_registers[reg_index].clear();
     }
 
+    /// Exchanges the full 512-bit contents of two ZMM registers without allocating.
+    ///
+    /// Nothing happens if `a` and `b` are equal or either register is not accessible.
+    ///
+    /// # Arguments
+    /// * `a` - The index of the first register.
+    /// * `b` - The index of the second register.
+    pub fn swap_simd(&mut self, a: usize, b: usize) {
+        if !self.is_simd_accessible(a) || !self.is_simd_accessible(b) {
+            return;
+        }
+        self.simd_registers.swap(a, b);
+    }
+
+    /// Packs the most significant bit of each byte of a SIMD register into a mask, as `PMOVMSKB` does.
+    ///
+    /// # Arguments
+    /// * `reg_type` - The type of SIMD register to read; it decides how many bytes contribute.
+    /// * `src` - The index of the register.
+    ///
+    /// # Returns
+    /// A mask whose bit `i` is bit 7 of byte `i` of the register, or `None` if the register is not accessible.
+    pub fn pmovmskb(&self, reg_type: VecRegName, src: usize) -> Option<u64> {
+        self.get_by_sections::<u8>(reg_type, src).map(|lanes| Utilities::movemask_u8(&lanes))
+    }
+
     /// Retrieves sections of a specified SIMD register as a vector of a specific type.
     ///
     /// # Type Parameters
@@ -540,24 +772,11 @@ impl Registers {
     /// # Returns
     /// A vector of `T` elements representing sections of the register, or `None` if invalid.
     pub fn get_by_sections<T: SectionCompatible>(&self, reg_type: VecRegName, reg_index: usize) -> Option<Vec<T>> {
-        let sections: Vec<T> = self.simd_registers[reg_index].get_sections();
-        match reg_type {
-            VecRegName::XMM => {
-                let n = 128 / (std::mem::size_of::<T>() * 8);
-                let slice = &sections[..n];
-                Some(slice.to_vec())
-            }
-            VecRegName::YMM => {
-                let n = 256 / (std::mem::size_of::<T>() * 8);
-                let slice = &sections[..n];
-                Some(slice.to_vec())
-            }
-            VecRegName::ZMM => {
-                let n = 512 / (std::mem::size_of::<T>() * 8);
-                let slice = &sections[..n];
-                Some(slice.to_vec())
-            }
+        if !self.is_simd_accessible(reg_index) {
+            return None;
         }
+        let sections: Vec<T> = self.simd_registers[reg_index].get_sections();
+        Some(sections[..reg_type.lane_count::<T>()].to_vec())
     }
 
     /// Sets sections of a specified SIMD register using a vector of a specific type.
@@ -573,38 +792,135 @@ impl Registers {
     /// # Returns
     /// `true` if the operation was successful, `false` otherwise.
     pub fn set_by_sections<T: SectionCompatible>(&mut self, reg_type: VecRegName, reg_index: usize, sections: Vec<T>) -> bool {
-        let type_bits = std::mem::size_of::<T>() * 8;
+        if !self.is_simd_accessible(reg_index) {
+            return false;
+        }
+        let type_bits = Utilities::element_bits::<T>();
         let register_bits = type_bits * sections.len();
-        let fill_sections = (512 - register_bits) / type_bits;
-        match reg_type {
-            VecRegName::XMM => {
-                if register_bits != 128 {
-                    return false;
-                }
-                let mut fill = sections;
-                fill.extend(std::iter::repeat(T::from(0u8)).take(fill_sections));
-                self.simd_registers[reg_index].set_by_sections(fill);
-                true
-            }
-            VecRegName::YMM => {
-                if register_bits != 256 {
-                    return false;
-                }
-                let mut fill = sections;
-                fill.extend(std::iter::repeat(T::from(0u8)).take(fill_sections));
-                self.simd_registers[reg_index].set_by_sections(fill);
-                true
-            }
-            VecRegName::ZMM => {
-                if register_bits != 512 {
-                    return false;
-                }
-                let mut fill = sections;
-                fill.extend(std::iter::repeat(T::from(0u8)).take(fill_sections));
-                self.simd_registers[reg_index].set_by_sections(fill);
-                true
+        if register_bits != Utilities::get_vec_reg_size(&reg_type) {
+            return false;
+        }
+        let fill_sections = (Utilities::get_vec_reg_size(&VecRegName::ZMM) - register_bits) / type_bits;
+        trace_event!(function = "Registers::set_by_sections", register = %reg_type, index = reg_index, lanes = sections.len(), lane_bits = type_bits);
+        let mut fill = sections;
+        fill.extend(std::iter::repeat_n(T::from(0u8), fill_sections));
+        self.simd_registers[reg_index].set_by_sections(fill);
+        true
+    }
+
+    /// Retrieves the raw bytes of a ZMM register.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the register.
+    ///
+    /// # Returns
+    /// The 64 bytes of the register in little endian order, byte 0 holding bits 0 to 7,
+    /// or all zeros if the register is not accessible.
+    pub fn get_zmm_bytes(&self, index: usize) -> [u8; 64] {
+        self.get_raw_bytes(index)
+    }
+
+    /// Sets the raw bytes of a ZMM register.
+    ///
+    /// Nothing happens if the register is not accessible.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the register.
+    /// * `bytes` - The 64 bytes to store, in little endian order.
+    pub fn set_zmm_bytes(&mut self, index: usize, bytes: [u8; 64]) {
+        self.set_raw_bytes(index, &bytes);
+    }
+
+    /// Retrieves the raw bytes of a YMM register.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the register.
+    ///
+    /// # Returns
+    /// The 32 bytes of the register in little endian order,
+    /// or all zeros if the register is not accessible.
+    pub fn get_ymm_bytes(&self, index: usize) -> [u8; 32] {
+        self.get_raw_bytes(index)
+    }
+
+    /// Sets the raw bytes of a YMM register, clearing the upper bits of the underlying ZMM register.
+    ///
+    /// Nothing happens if the register is not accessible.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the register.
+    /// * `bytes` - The 32 bytes to store, in little endian order.
+    pub fn set_ymm_bytes(&mut self, index: usize, bytes: [u8; 32]) {
+        self.set_raw_bytes(index, &bytes);
+    }
+
+    /// Retrieves the raw bytes of an XMM register.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the register.
+    ///
+    /// # Returns
+    /// The 16 bytes of the register in little endian order,
+    /// or all zeros if the register is not accessible.
+    pub fn get_xmm_bytes(&self, index: usize) -> [u8; 16] {
+        self.get_raw_bytes(index)
+    }
+
+    /// Sets the raw bytes of an XMM register, clearing the upper bits of the underlying ZMM register.
+    ///
+    /// Nothing happens if the register is not accessible.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the register.
+    /// * `bytes` - The 16 bytes to store, in little endian order.
+    pub fn set_xmm_bytes(&mut self, index: usize, bytes: [u8; 16]) {
+        self.set_raw_bytes(index, &bytes);
+    }
+
+    /// Retrieves the low `N` bytes of a SIMD register as an array, or all zeros if it is not accessible.
+    fn get_raw_bytes<const N: usize>(&self, index: usize) -> [u8; N] {
+        let mut bytes = [0u8; N];
+        if self.is_simd_accessible(index) {
+            // `BitVec` packs bit 0 into the most significant bit of each byte
+            let packed = self.simd_registers[index].bits.to_bytes();
+            for (byte, packed) in bytes.iter_mut().zip(packed) {
+                *byte = packed.reverse_bits();
             }
         }
+        bytes
+    }
+
+    /// Replaces a SIMD register with the given low bytes, clearing the bytes above them.
+    ///
+    /// Nothing happens if the register is not accessible.
+    fn set_raw_bytes(&mut self, index: usize, bytes: &[u8]) {
+        if !self.is_simd_accessible(index) {
+            return;
+        }
+        let mut packed = [0u8; 64];
+        for (packed, byte) in packed.iter_mut().zip(bytes) {
+            *packed = byte.reverse_bits();
+        }
+        self.simd_registers[index].bits = BitVec::from_bytes(&packed);
+    }
+
+    /// Fills every lane of a specified SIMD register with the same value.
+    ///
+    /// Matches `VBROADCASTSS`, `VBROADCASTSD`, `VPBROADCASTD` and friends: the register is cleared,
+    /// so bits beyond the width of `reg_type` are zeroed.
+    ///
+    /// # Type Parameters
+    /// `T` - The type of the lanes, which decides how many there are.
+    ///
+    /// # Arguments
+    /// * `reg_type` - The type of SIMD register to operate on.
+    /// * `dst` - The index of the register.
+    /// * `value` - The value to store in every lane.
+    ///
+    /// # Returns
+    /// `true` if the operation was successful, `false` otherwise.
+    pub fn broadcast_scalar_to_simd<T: SectionCompatible>(&mut self, reg_type: VecRegName, dst: usize, value: T) -> bool {
+        self.set_by_sections(reg_type, dst, vec![value; reg_type.lane_count::<T>()])
     }
 
     /// Retrieves a value from a specified SIMD register based on a selector string.
@@ -620,6 +936,9 @@ impl Registers {
     /// # Returns
     /// The value of the specified type from the register, or `None` if invalid.
     pub fn get_by_selector<T: SectionCompatible>(&self, _reg_type: VecRegName, reg_index: usize, selector: &str) -> Option<T> {
+        if !self.is_simd_accessible(reg_index) {
+            return None;
+        }
         if let Some((a, b)) = extract_values(selector) {
             Some(self.simd_registers[reg_index].get_by_index(b, a))
         } else {
@@ -641,6 +960,9 @@ impl Registers {
     /// # Returns
     /// `true` if the operation was successful, `false` otherwise.
     pub fn set_by_selector<T: SectionCompatible>(&mut self, _reg_type: VecRegName, reg_index: usize, selector: &str, value: T) -> bool {
+        if !self.is_simd_accessible(reg_index) {
+            return false;
+        }
         if let Some((a, b)) = extract_values(selector) {
             self.simd_registers[reg_index].set_by_index(b, a, value);
             true
@@ -649,6 +971,26 @@ impl Registers {
         }
     }
 
+    /// Extracts an arbitrary contiguous range of bits from a SIMD register.
+    ///
+    /// The range may start and end at any bit of the 512-bit register storage, independently of lane
+    /// or type boundaries, which suits operands with unusual widths such as 48 or 80 bits.
+    ///
+    /// # Arguments
+    /// * `reg_index` - The index of the register.
+    /// * `start_bit` - The lowest bit of the range.
+    /// * `end_bit` - The highest bit of the range (inclusive).
+    ///
+    /// # Returns
+    /// The bits of the range, with `start_bit` in bit 0, or `None` if the register is not accessible,
+    /// `end_bit` is below `start_bit` or past bit 511, or the range is wider than 128 bits.
+    pub fn extract_bit_range(&self, reg_index: usize, start_bit: usize, end_bit: usize) -> Option<u128> {
+        if !self.is_simd_accessible(reg_index) || end_bit < start_bit || end_bit >= 512 || end_bit - start_bit >= 128 {
+            return None;
+        }
+        Some(self.simd_registers[reg_index].get_by_index(start_bit, end_bit))
+    }
+
     /// Sets the value of a specified general-purpose register.
     ///
     /// Handles specific bits based on the register's type and size.
@@ -657,6 +999,7 @@ impl Registers {
     /// * `reg_name` - The name of the general-purpose register.
     /// * `value` - The value to set the register to.
     pub fn set_gpr_value(&mut self, reg_name: GPRName, value: u64) {
+        trace_event!(function = "Registers::set_gpr_value", register = %reg_name, value);
         register_set!(self; reg_name; value;
             RAX, EAX, AX, AL, AH,
             RBX, EBX, BX, BL, BH,
@@ -705,6 +1048,47 @@ impl Registers {
         )
     }
 
+    /// Retrieves the value of a specified general-purpose register reinterpreted as a signed integer.
+    ///
+    /// The register value is read as by `get_gpr_value` and cast to `i64`, so values of registers
+    /// narrower than 64 bits are not sign-extended.
+    ///
+    /// # Arguments
+    /// * `reg_name` - The name of the general-purpose register.
+    ///
+    /// # Returns
+    /// The current value of the specified register as an `i64`.
+    pub fn get_gpr_as_i64(&self, reg_name: GPRName) -> i64 {
+        self.get_gpr_value(reg_name) as i64
+    }
+
+    /// Sets the value of a specified general-purpose register from a signed integer.
+    ///
+    /// The value is stored as its two's complement bit pattern, as by `set_gpr_value(reg_name, value as u64)`.
+    ///
+    /// # Arguments
+    /// * `reg_name` - The name of the general-purpose register.
+    /// * `value` - The signed value to set the register to.
+    pub fn set_gpr_i64(&mut self, reg_name: GPRName, value: i64) {
+        self.set_gpr_value(reg_name, value as u64);
+    }
+
+    /// Exchanges the values of two GPRs, as `XCHG` does.
+    ///
+    /// Each register is read and written at the width of its name, so swapping 32-bit names clears
+    /// the upper halves of both registers just as any 32-bit write does. Swapping a register with
+    /// itself leaves it unchanged.
+    ///
+    /// # Arguments
+    /// * `a` - The name of the first register.
+    /// * `b` - The name of the second register.
+    pub fn swap_gpr(&mut self, a: GPRName, b: GPRName) {
+        let value_a = self.get_gpr_value(a);
+        let value_b = self.get_gpr_value(b);
+        self.set_gpr_value(a, value_b);
+        self.set_gpr_value(b, value_a);
+    }
+
     /// Sets the value of a specified flags register.
     ///
     /// # Arguments
@@ -790,4 +1174,193 @@ impl Registers {
             }
         }
     }
+
+    /// Sets the value of a specified opmask register.
+    ///
+    /// # Arguments
+    /// * `reg_name` - The name of the opmask register.
+    /// * `value` - The value to set the register to; bit `i` masks lane `i`.
+    pub fn set_kmask_value(&mut self, reg_name: KMaskName, value: u64) {
+        self.kmask[reg_name as usize] = value;
+    }
+
+    /// Retrieves the value of a specified opmask register.
+    ///
+    /// # Arguments
+    /// * `reg_name` - The name of the opmask register.
+    ///
+    /// # Returns
+    /// The current value of the specified register.
+    pub fn get_kmask_value(&self, reg_name: KMaskName) -> u64 {
+        self.kmask[reg_name as usize]
+    }
+
+    /// Formats every register as a multiline table, in the spirit of GDB's `info registers`.
+    ///
+    /// The table lists, separated by blank lines:
+    /// * the 64-bit GPRs, one per line, as `RAX: 0x0000000000000000`;
+    /// * ZMM0 to ZMM31, one per line, as four 16-byte chunks in hex, most significant chunk first;
+    /// * RFLAGS in hex followed by the names of its set status and control flags, e.g. `[ CF ZF ]`,
+    ///   and RIP in hex.
+    ///
+    /// # Returns
+    /// The formatted table, each line terminated by a newline.
+    pub fn format_table(&self) -> String {
+        let mut table = String::new();
+        for (name, gpr) in GPR64_NAMES.iter().zip(&self.gpr) {
+            let _ = writeln!(table, "{}: 0x{:016x}", name, gpr.get_value());
+        }
+        table.push('\n');
+        for (index, register) in self.simd_registers.iter().enumerate() {
+            let _ = write!(table, "ZMM{}:", index);
+            for chunk in register.get_sections::<u128>().iter().rev() {
+                let _ = write!(table, " 0x{:032x}", chunk);
+            }
+            table.push('\n');
+        }
+        table.push('\n');
+        let _ = write!(table, "RFLAGS: 0x{:016x} [", self.rflags);
+        for (mask, name) in RFLAGS_NAMES {
+            if self.rflags & mask != 0 {
+                let _ = write!(table, " {}", name);
+            }
+        }
+        let _ = writeln!(table, " ]");
+        let _ = writeln!(table, "RIP: 0x{:016x}", self.rip);
+        table
+    }
+
+    /// Serializes every register into a fixed-length binary form.
+    ///
+    /// The output is always `REGISTERS_BYTES_LEN` (2256) bytes, with every multi-byte value little endian:
+    ///
+    /// | Offset | Length  | Contents                                                                    |
+    /// |--------|---------|-----------------------------------------------------------------------------|
+    /// | 0      | 32 × 64 | ZMM0 to ZMM31; byte 0 of each holds bits 0 to 7                             |
+    /// | 2048   | 16 × 8  | RAX, RBX, RCX, RDX, RSI, RDI, RBP, RSP, R8 to R15                           |
+    /// | 2176   | 8       | RFLAGS                                                                      |
+    /// | 2184   | 8       | RIP                                                                         |
+    /// | 2192   | 8 × 8   | K0 to K7                                                                    |
+    ///
+    /// The CPU mode is not part of the snapshot, and all 32 vector registers are included in either mode.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(REGISTERS_BYTES_LEN);
+        for register in &self.simd_registers {
+            bytes.extend(register.get_sections::<u8>());
+        }
+        for gpr in &self.gpr {
+            bytes.extend(gpr.get_value().to_le_bytes());
+        }
+        bytes.extend(self.rflags.to_le_bytes());
+        bytes.extend(self.rip.to_le_bytes());
+        for kmask in &self.kmask {
+            bytes.extend(kmask.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Deserializes registers from the binary form produced by `to_bytes`.
+    ///
+    /// The returned registers are in 64-bit mode.
+    ///
+    /// # Arguments
+    /// * `bytes` - Exactly `REGISTERS_BYTES_LEN` bytes in the format described by `to_bytes`.
+    ///
+    /// # Returns
+    /// The decoded registers, or `DeserializeError::InvalidLength` if `bytes` has the wrong length.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Registers, DeserializeError> {
+        if bytes.len() != REGISTERS_BYTES_LEN {
+            return Err(DeserializeError::InvalidLength { expected: REGISTERS_BYTES_LEN, actual: bytes.len() });
+        }
+        let (simd, rest) = bytes.split_at(32 * 64);
+        let (gpr, rest) = rest.split_at(16 * 8);
+        let (rflags, rest) = rest.split_at(8);
+        let (rip, kmask) = rest.split_at(8);
+        let read_u64 = |chunk: &[u8]| u64::from_le_bytes(chunk.try_into().unwrap());
+        let mut registers = Registers::new();
+        for (register, chunk) in registers.simd_registers.iter_mut().zip(simd.chunks_exact(64)) {
+            register.set_by_sections(chunk.to_vec());
+        }
+        for (register, chunk) in registers.gpr.iter_mut().zip(gpr.chunks_exact(8)) {
+            register.set_value(read_u64(chunk));
+        }
+        registers.rflags = read_u64(rflags);
+        registers.rip = read_u64(rip);
+        for (register, chunk) in registers.kmask.iter_mut().zip(kmask.chunks_exact(8)) {
+            *register = read_u64(chunk);
+        }
+        Ok(registers)
+    }
+
+    /// Lists the registers whose values differ between two register files.
+    ///
+    /// Each register is reported once, by its full-width name: ZMM0 to ZMM31 as `RegisterRef::Vec` with
+    /// `VecRegName::ZMM`, the 64-bit GPRs, RFLAGS, RIP and K0 to K7, in the order used by `to_bytes`.
+    /// The CPU mode is not compared.
+    ///
+    /// # Arguments
+    /// * `other` - The registers to compare against.
+    ///
+    /// # Returns
+    /// The registers holding different values in `self` and `other`, or an empty vector if they are equal.
+    pub fn diff(&self, other: &Registers) -> Vec<RegisterRef> {
+        let mut changed = Vec::new();
+        for (index, (a, b)) in self.simd_registers.iter().zip(&other.simd_registers).enumerate() {
+            if a.bits != b.bits {
+                changed.push(RegisterRef::Vec(VecRegName::ZMM, index));
+            }
+        }
+        for (name, (a, b)) in GPR64_NAMES.iter().zip(self.gpr.iter().zip(&other.gpr)) {
+            if a.get_value() != b.get_value() {
+                changed.push(RegisterRef::Gpr(*name));
+            }
+        }
+        if self.rflags != other.rflags {
+            changed.push(RegisterRef::Flags(FLAGSName::RFLAGS));
+        }
+        if self.rip != other.rip {
+            changed.push(RegisterRef::Ip(IPName::RIP));
+        }
+        for (name, (a, b)) in KMaskName::ALL.iter().zip(self.kmask.iter().zip(&other.kmask)) {
+            if a != b {
+                changed.push(RegisterRef::KMask(*name));
+            }
+        }
+        changed
+    }
+}
+
+/// Generates registers in 64-bit mode with uniformly random GPRs, RIP and opmasks, random SIMD
+/// bit patterns, and an RFLAGS holding a random subset of the status and control flags with the
+/// reserved bit 1 set.
+#[cfg(feature = "proptest")]
+impl proptest::arbitrary::Arbitrary for Registers {
+    type Parameters = ();
+    type Strategy = proptest::strategy::BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        use proptest::prelude::*;
+        let flags = RFLAGS_NAMES.iter().fold(0, |flags, (mask, _)| flags | mask);
+        (
+            proptest::collection::vec(any::<u8>(), 32 * 64),
+            any::<[u64; 16]>(),
+            any::<u64>(),
+            any::<u64>(),
+            any::<[u64; 8]>(),
+        )
+            .prop_map(move |(simd, gpr, rflags, rip, kmask)| {
+                let mut registers = Registers::new();
+                for (register, chunk) in registers.simd_registers.iter_mut().zip(simd.chunks_exact(64)) {
+                    register.set_by_sections(chunk.to_vec());
+                }
+                for (register, value) in registers.gpr.iter_mut().zip(gpr) {
+                    register.set_value(value);
+                }
+                registers.rflags = rflags & flags | 1 << 1;
+                registers.rip = rip;
+                registers.kmask = kmask;
+                registers
+            })
+            .boxed()
+    }
 }
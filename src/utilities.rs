@@ -1,4 +1,286 @@
 use super::*;
+use crate::registers::{CF, PF, AF, ZF, SF, TF, IF, DF, OF, GPR64_NAMES};
+
+use std::fmt::{Display, Formatter, LowerHex};
+use std::ops::Range;
+
+/// Error returned when two operand slices that must be lane-aligned differ in length.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct LengthMismatch {
+    pub left: usize,
+    pub right: usize,
+}
+
+/// Implements the `Display` trait for `LengthMismatch`.
+impl Display for LengthMismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "operand lengths differ: {} vs {}", self.left, self.right)
+    }
+}
+
+impl std::error::Error for LengthMismatch {}
+
+/// Error returned when a byte slice does not split evenly into lanes.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct ByteLengthError {
+    pub length: usize,
+    pub lane_size: usize,
+}
+
+/// Implements the `Display` trait for `ByteLengthError`.
+impl Display for ByteLengthError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "byte length {} is not a multiple of the lane size {}", self.length, self.lane_size)
+    }
+}
+
+impl std::error::Error for ByteLengthError {}
+
+/// Error returned when parsing a hexadecimal integer fails.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum ParseHexError {
+    /// The input holds no digits.
+    Empty,
+    /// The input holds a character that is neither a hex digit nor `_`.
+    InvalidDigit(char),
+    /// The value does not fit in the destination type.
+    Overflow,
+}
+
+/// Implements the `Display` trait for `ParseHexError`.
+impl Display for ParseHexError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseHexError::Empty => write!(f, "no hex digits"),
+            ParseHexError::InvalidDigit(c) => write!(f, "invalid hex digit {:?}", c),
+            ParseHexError::Overflow => write!(f, "hex value too large"),
+        }
+    }
+}
+
+impl std::error::Error for ParseHexError {}
+
+/// Formats little endian 64-bit words as `0x` followed by every digit, most significant first.
+fn words_to_hex(words: &[u64]) -> String {
+    words.iter().rev().fold(String::from("0x"), |hex, word| hex + &format!("{:016x}", word))
+}
+
+/// Parses hex text into `N` little endian 64-bit words, accepting an optional `0x` prefix and `_` separators.
+fn parse_hex_words<const N: usize>(text: &str) -> Result<[u64; N], ParseHexError> {
+    let digits = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")).unwrap_or(text);
+    let mut words = [0u64; N];
+    let mut significant = 0;
+    let mut empty = true;
+    for c in digits.chars().filter(|c| *c != '_') {
+        let digit = c.to_digit(16).ok_or(ParseHexError::InvalidDigit(c))? as u64;
+        empty = false;
+        if significant == 0 && digit == 0 {
+            continue;
+        }
+        significant += 1;
+        if significant > N * 16 {
+            return Err(ParseHexError::Overflow);
+        }
+        for i in (1..N).rev() {
+            words[i] = words[i] << 4 | words[i - 1] >> 60;
+        }
+        words[0] = words[0] << 4 | digit;
+    }
+    if empty {
+        return Err(ParseHexError::Empty);
+    }
+    Ok(words)
+}
+
+/// How the `Utilities::narrow_*` converters handle lanes that do not fit the narrower type.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Narrowing {
+    /// Keeps the low bits of each lane, as `VPMOVQD` does.
+    Truncate,
+    /// Clamps each lane to the range of the narrower type, as `VPMOVSQD` and `VPMOVUSQD` do.
+    Saturate,
+}
+
+/// The rounding applied by `Utilities::cvt_*_rounded`, mirroring the `MXCSR.RC` field.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub enum RoundingMode {
+    /// Rounds to the nearest integer, ties to even (`RC = 00`).
+    NearestEven,
+    /// Rounds toward negative infinity (`RC = 01`).
+    Down,
+    /// Rounds toward positive infinity (`RC = 10`).
+    Up,
+    /// Rounds toward zero (`RC = 11`), as the truncating `CVTT*` forms always do.
+    TowardZero,
+}
+
+/// Defines a widening converter and its narrowing counterpart for a pair of adjacent lane types.
+macro_rules! lane_width_converters {
+    ($( $narrow:ident, $wide:ident, $widen_fn:ident, $narrow_fn:ident, $extension:literal, $widen_instr:literal );* $(;)?) => {
+        impl Utilities {
+            $(
+                #[doc = concat!("Widens `", stringify!($narrow), "` lanes to `", stringify!($wide), "` by ", $extension, ", as `", $widen_instr, "` does.")]
+                ///
+                /// # Arguments
+                /// * `v` - The source lanes.
+                ///
+                /// # Returns
+                #[doc = concat!("A `Vec<", stringify!($wide), ">` holding each lane of `v`, in the same order.")]
+                pub fn $widen_fn(v: Vec<$narrow>) -> Vec<$wide> {
+                    v.into_iter().map(<$wide>::from).collect()
+                }
+
+                #[doc = concat!("Narrows `", stringify!($wide), "` lanes to `", stringify!($narrow), "`, truncating or saturating each lane.")]
+                ///
+                /// # Arguments
+                /// * `v` - The source lanes.
+                /// * `mode` - Whether out-of-range lanes keep their low bits or are clamped to the nearest bound.
+                ///
+                /// # Returns
+                #[doc = concat!("A `Vec<", stringify!($narrow), ">` holding each lane of `v`, in the same order.")]
+                pub fn $narrow_fn(v: Vec<$wide>, mode: Narrowing) -> Vec<$narrow> {
+                    match mode {
+                        Narrowing::Truncate => v.into_iter().map(|x| x as $narrow).collect(),
+                        Narrowing::Saturate => v.into_iter()
+                            .map(|x| x.clamp(<$narrow>::MIN as $wide, <$narrow>::MAX as $wide) as $narrow)
+                            .collect(),
+                    }
+                }
+            )*
+        }
+    };
+}
+
+lane_width_converters!(
+    u8, u16, widen_u8_to_u16, narrow_u16_to_u8, "zero extension", "PMOVZXBW";
+    u16, u32, widen_u16_to_u32, narrow_u32_to_u16, "zero extension", "PMOVZXWD";
+    u32, u64, widen_u32_to_u64, narrow_u64_to_u32, "zero extension", "PMOVZXDQ";
+    i8, i16, widen_i8_to_i16, narrow_i16_to_i8, "sign extension", "PMOVSXBW";
+    i16, i32, widen_i16_to_i32, narrow_i32_to_i16, "sign extension", "PMOVSXWD";
+    i32, i64, widen_i32_to_i64, narrow_i64_to_i32, "sign extension", "PMOVSXDQ";
+);
+
+/// A reference to any register, as produced by `Utilities::parse_register`.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub enum RegisterRef {
+    Gpr(GPRName),
+    /// A vector register and its index, e.g. `(VecRegName::YMM, 3)` for YMM3.
+    Vec(VecRegName, usize),
+    Flags(FLAGSName),
+    Ip(IPName),
+    KMask(KMaskName),
+}
+
+/// The arithmetic flags produced by an ALU operation, as computed by `Utilities::flags_after_add`,
+/// `Utilities::flags_after_sub`, `Utilities::flags_after_logic` and the bit-count helpers.
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+pub struct FlagsDelta {
+    pub cf: bool,
+    pub pf: bool,
+    pub af: bool,
+    pub zf: bool,
+    pub sf: bool,
+    pub of: bool,
+}
+
+impl FlagsDelta {
+    /// Writes the six arithmetic flags into RFLAGS, leaving every other bit unchanged.
+    ///
+    /// # Arguments
+    /// * `registers` - The registers whose RFLAGS is updated.
+    pub fn apply_to(&self, registers: &mut Registers) {
+        let mut rflags = registers.get_flags_value(FLAGSName::RFLAGS) & !(CF | PF | AF | ZF | SF | OF);
+        for (set, mask) in [(self.cf, CF), (self.pf, PF), (self.af, AF), (self.zf, ZF), (self.sf, SF), (self.of, OF)] {
+            if set {
+                rflags |= mask;
+            }
+        }
+        registers.set_flags_value(FLAGSName::RFLAGS, rflags);
+    }
+
+    /// Computes ZF, SF and PF from a result, leaving the other flags clear.
+    fn from_result(result: u64, width_bits: usize) -> Self {
+        FlagsDelta {
+            pf: Utilities::parity_byte(result),
+            zf: result == 0,
+            sf: result >> (width_bits - 1) & 1 != 0,
+            ..FlagsDelta::default()
+        }
+    }
+}
+
+/// The Castagnoli polynomial, bit-reflected.
+const CRC32C_POLY: u32 = 0x82F6_3B78;
+
+/// Accumulates the low `bits` bits of `data` into a CRC32C, least significant bit first.
+fn crc32c_bits(crc: u32, data: u64, bits: u32) -> u32 {
+    (0..bits).fold(crc, |crc, i| {
+        let feedback = (crc ^ (data >> i) as u32) & 1;
+        crc >> 1 ^ if feedback != 0 { CRC32C_POLY } else { 0 }
+    })
+}
+
+/// Selects how `Utilities::randomize_registers_with` and `Utilities::randomize_memory_with` pick values.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct RandomizeOptions {
+    /// Replaces about a quarter of the generated quadwords with edge cases: 0, 1, -1, the signed
+    /// and unsigned bounds, and NaN, infinity and negative zero bit patterns for `f32` and `f64` lanes.
+    pub interesting_values: bool,
+}
+
+/// The edge cases mixed in when `RandomizeOptions::interesting_values` is set.
+const INTERESTING_QUADS: [u64; 16] = [
+    0, 1, u64::MAX, i64::MIN as u64, i64::MAX as u64,
+    0x7FFF_FFFF, 0x8000_0000, 0xFFFF_FFFF,
+    // f64 quiet NaN, signaling NaN, +infinity and -infinity
+    0x7FF8_0000_0000_0000, 0x7FF0_0000_0000_0001, 0x7FF0_0000_0000_0000, 0xFFF0_0000_0000_0000,
+    // pairs of f32 lanes: quiet NaNs, signaling NaN and -infinity, -0.0 and +infinity, 1.0 and -1.0
+    0x7FC0_0000_7FC0_0000, 0x7F80_0001_FF80_0000, 0x8000_0000_7F80_0000, 0x3F80_0000_BF80_0000,
+];
+
+/// MXCSR.DAZ, treating subnormal SSE operands as zero.
+const MXCSR_DAZ: u32 = 1 << 6;
+/// MXCSR.UM, masking the underflow exception.
+const MXCSR_UM: u32 = 1 << 11;
+/// MXCSR.FTZ, flushing subnormal SSE results to zero while underflow is masked.
+const MXCSR_FTZ: u32 = 1 << 15;
+
+/// The exponent bias of the x87 80-bit extended-precision format.
+const F80_BIAS: u16 = 16383;
+
+/// Shifts `value` right by `shift` bits, rounding to nearest with ties to even.
+fn round_shift_right(value: u128, shift: u32) -> u64 {
+    let kept = value >> shift;
+    let rest = value & ((1 << shift) - 1);
+    let half = 1 << (shift - 1);
+    let round_up = rest > half || rest == half && kept & 1 != 0;
+    (kept + round_up as u128) as u64
+}
+
+/// The SplitMix64 generator behind the `Utilities::randomize_*` functions.
+///
+/// It is small, fast and fully determined by its seed, which is all reproducible test states need.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ z >> 30).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ z >> 27).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ z >> 31
+    }
+
+    /// Returns the next quadword, possibly replaced by an edge case as `options` requests.
+    fn next_quad(&mut self, options: RandomizeOptions) -> u64 {
+        let value = self.next();
+        if options.interesting_values && value & 3 == 0 {
+            INTERESTING_QUADS[(value >> 2) as usize % INTERESTING_QUADS.len()]
+        } else {
+            value
+        }
+    }
+}
 
 /// Utilities structure.
 pub struct Utilities {}
@@ -6,9 +288,7 @@ pub struct Utilities {}
 impl Utilities {
     /// Converts a 32-bit floating point number (`f32`) to a 32-bit unsigned integer (`u32`).
     ///
-    /// # Safety
-    /// This function uses `unsafe` code to directly transmute the bits of the input `f32` into a `u32`.
-    /// The caller must ensure that this operation is safe in the context of their application.
+    /// The bits of the input `f32` are reinterpreted as a `u32` without any numeric conversion.
     ///
     /// # Arguments
     /// * `f` - The `f32` value to be converted.
@@ -16,14 +296,12 @@ impl Utilities {
     /// # Returns
     /// A `u32` value representing the bit pattern of the input `f32` value.
     pub fn f32_to_u32(f: f32) -> u32 {
-        unsafe { std::mem::transmute::<f32, u32>(f) }
+        f.to_bits()
     }
 
     /// Converts a 64-bit floating point number (`f64`) to a 64-bit unsigned integer (`u64`).
     ///
-    /// # Safety
-    /// This function employs `unsafe` code to directly transmute the bits of the input `f64` into a `u64`.
-    /// It is the responsibility of the caller to ensure this operation is safe in their specific context.
+    /// The bits of the input `f64` are reinterpreted as a `u64` without any numeric conversion.
     ///
     /// # Arguments
     /// * `f` - The `f64` value to be converted.
@@ -31,14 +309,12 @@ impl Utilities {
     /// # Returns
     /// A `u64` value representing the bit pattern of the input `f64` value.
     pub fn f64_to_u64(f: f64) -> u64 {
-        unsafe { std::mem::transmute::<f64, u64>(f) }
+        f.to_bits()
     }
 
     /// Converts a 32-bit unsigned integer (`u32`) to a 32-bit floating point number (`f32`).
     ///
-    /// # Safety
-    /// This function uses `unsafe` code to directly transmute the bits of the input `u32` into a `f32`.
-    /// The caller must ensure that this operation is safe in the context of their application.
+    /// The bits of the input `u32` are reinterpreted as a `f32` without any numeric conversion.
     ///
     /// # Arguments
     /// * `u` - The `u32` value to be converted.
@@ -46,14 +322,12 @@ impl Utilities {
     /// # Returns
     /// A `f32` value representing the bit pattern of the input `u32` value.
     pub fn u32_to_f32(u: u32) -> f32 {
-        unsafe { std::mem::transmute::<u32, f32>(u) }
+        f32::from_bits(u)
     }
 
     /// Converts a 64-bit unsigned integer (`u64`) to a 64-bit floating point number (`f64`).
     ///
-    /// # Safety
-    /// This function employs `unsafe` code to directly transmute the bits of the input `u64` into a `f64`.
-    /// It is the responsibility of the caller to ensure this operation is safe in their specific context.
+    /// The bits of the input `u64` are reinterpreted as a `f64` without any numeric conversion.
     ///
     /// # Arguments
     /// * `u` - The `u64` value to be converted.
@@ -61,7 +335,7 @@ impl Utilities {
     /// # Returns
     /// A `f64` value representing the bit pattern of the input `u64` value.
     pub fn u64_to_f64(u: u64) -> f64 {
-        unsafe { std::mem::transmute::<u64, f64>(u) }
+        f64::from_bits(u)
     }
 
     /// Converts a vector of 32-bit floating point numbers (`Vec<f32>`) to a vector of 32-bit unsigned integers (`Vec<u32>`).
@@ -72,7 +346,7 @@ impl Utilities {
     /// # Returns
     /// A `Vec<u32>` where each element is the converted `u32` representation of the corresponding element in the input `Vec<f32>`.
     pub fn f32vec_to_u32vec(f: Vec<f32>) -> Vec<u32> {
-        f.into_iter().map(|x| Self::f32_to_u32(x)).collect()
+        f.into_iter().map(Self::f32_to_u32).collect()
     }
 
     /// Converts a vector of 64-bit floating point numbers (`Vec<f64>`) to a vector of 64-bit unsigned integers (`Vec<u64>`).
@@ -83,7 +357,7 @@ impl Utilities {
     /// # Returns
     /// A `Vec<u64>` where each element is the converted `u64` representation of the corresponding element in the input `Vec<f64>`.
     pub fn f64vec_to_u64vec(f: Vec<f64>) -> Vec<u64> {
-        f.into_iter().map(|x| Self::f64_to_u64(x)).collect()
+        f.into_iter().map(Self::f64_to_u64).collect()
     }
 
     /// Converts a vector of 32-bit unsigned integers (`Vec<u32>`) to a vector of 32-bit floating point numbers (`Vec<f32>`).
@@ -94,7 +368,7 @@ impl Utilities {
     /// # Returns
     /// A `Vec<f32>` where each element is the converted `f32` representation of the corresponding element in the input `Vec<u32>`.
     pub fn u32vec_to_f32vec(u: Vec<u32>) -> Vec<f32> {
-        u.into_iter().map(|x| Self::u32_to_f32(x)).collect()
+        u.into_iter().map(Self::u32_to_f32).collect()
     }
 
     /// Converts a vector of 64-bit unsigned integers (`Vec<u64>`) to a vector of 64-bit floating point numbers (`Vec<f64>`).
@@ -105,7 +379,290 @@ impl Utilities {
     /// # Returns
     /// A `Vec<f64>` where each element is the converted `f64` representation of the corresponding element in the input `Vec<u64>`.
     pub fn u64vec_to_f64vec(u: Vec<u64>) -> Vec<f64> {
-        u.into_iter().map(|x| Self::u64_to_f64(x)).collect()
+        u.into_iter().map(Self::u64_to_f64).collect()
+    }
+
+    /// Converts a vector of `f32` values to their bytes, each lane little endian.
+    ///
+    /// The bit pattern of every lane is kept exactly, NaN payloads included.
+    ///
+    /// # Arguments
+    /// * `f` - The vector of `f32` values to be converted.
+    ///
+    /// # Returns
+    /// A `Vec<u8>` of `4 * f.len()` bytes, ready for `Memory::write_bytes` or a register byte setter.
+    pub fn f32vec_to_bytes(f: Vec<f32>) -> Vec<u8> {
+        f.into_iter().flat_map(|v| v.to_le_bytes()).collect()
+    }
+
+    /// Converts a vector of `f64` values to their bytes, each lane little endian.
+    ///
+    /// The bit pattern of every lane is kept exactly, NaN payloads included.
+    ///
+    /// # Arguments
+    /// * `f` - The vector of `f64` values to be converted.
+    ///
+    /// # Returns
+    /// A `Vec<u8>` of `8 * f.len()` bytes, ready for `Memory::write_bytes` or a register byte setter.
+    pub fn f64vec_to_bytes(f: Vec<f64>) -> Vec<u8> {
+        f.into_iter().flat_map(|v| v.to_le_bytes()).collect()
+    }
+
+    /// Converts little-endian bytes to a vector of `f32` values, the inverse of `f32vec_to_bytes`.
+    ///
+    /// # Arguments
+    /// * `bytes` - The bytes to be converted; their length must be a multiple of 4.
+    ///
+    /// # Returns
+    /// A `Vec<f32>` with one lane per 4 bytes, or `ByteLengthError` if the bytes do not split evenly.
+    pub fn bytes_to_f32vec(bytes: &[u8]) -> Result<Vec<f32>, ByteLengthError> {
+        if !bytes.len().is_multiple_of(4) {
+            return Err(ByteLengthError { length: bytes.len(), lane_size: 4 });
+        }
+        Ok(bytes.chunks_exact(4).map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap())).collect())
+    }
+
+    /// Converts little-endian bytes to a vector of `f64` values, the inverse of `f64vec_to_bytes`.
+    ///
+    /// # Arguments
+    /// * `bytes` - The bytes to be converted; their length must be a multiple of 8.
+    ///
+    /// # Returns
+    /// A `Vec<f64>` with one lane per 8 bytes, or `ByteLengthError` if the bytes do not split evenly.
+    pub fn bytes_to_f64vec(bytes: &[u8]) -> Result<Vec<f64>, ByteLengthError> {
+        if !bytes.len().is_multiple_of(8) {
+            return Err(ByteLengthError { length: bytes.len(), lane_size: 8 });
+        }
+        Ok(bytes.chunks_exact(8).map(|chunk| f64::from_le_bytes(chunk.try_into().unwrap())).collect())
+    }
+
+    /// Splits a `u256` into 4 `u64` lanes, lane 0 holding the least significant bits.
+    ///
+    /// The lane order matches `Registers::get_by_sections::<u64>` on a 256-bit register.
+    ///
+    /// # Arguments
+    /// * `value` - The value to split.
+    ///
+    /// # Returns
+    /// An array of the lanes of `value`.
+    pub fn u256_to_u64_lanes(value: u256) -> [u64; 4] {
+        value.0
+    }
+
+    /// Joins 4 `u64` lanes into a `u256`, lane 0 holding the least significant bits.
+    ///
+    /// # Arguments
+    /// * `lanes` - The lanes to join.
+    ///
+    /// # Returns
+    /// A `u256` holding the lanes in little endian order.
+    pub fn u64_lanes_to_u256(lanes: [u64; 4]) -> u256 {
+        u256(lanes)
+    }
+
+    /// Splits a `u256` into 2 `u128` lanes, lane 0 holding the least significant bits.
+    ///
+    /// The lane order matches `Registers::get_by_sections::<u128>` on a 256-bit register.
+    ///
+    /// # Arguments
+    /// * `value` - The value to split.
+    ///
+    /// # Returns
+    /// An array of the lanes of `value`.
+    pub fn u256_to_u128_lanes(value: u256) -> [u128; 2] {
+        std::array::from_fn(|i| value.0[2 * i] as u128 | (value.0[2 * i + 1] as u128) << 64)
+    }
+
+    /// Joins 2 `u128` lanes into a `u256`, lane 0 holding the least significant bits.
+    ///
+    /// # Arguments
+    /// * `lanes` - The lanes to join.
+    ///
+    /// # Returns
+    /// A `u256` holding the lanes in little endian order.
+    pub fn u128_lanes_to_u256(lanes: [u128; 2]) -> u256 {
+        u256(std::array::from_fn(|i| (lanes[i / 2] >> (64 * (i % 2))) as u64))
+    }
+
+    /// Splits a `u256` into 8 `f32` lanes, lane 0 holding the least significant bits.
+    ///
+    /// The lane order matches `Registers::get_by_sections::<u32>` on a 256-bit register.
+    ///
+    /// # Arguments
+    /// * `value` - The value to split.
+    ///
+    /// # Returns
+    /// An array of the lanes of `value`, each reinterpreted bit for bit.
+    pub fn u256_to_f32_lanes(value: u256) -> [f32; 8] {
+        std::array::from_fn(|i| f32::from_bits((value.0[i / 2] >> (32 * (i % 2))) as u32))
+    }
+
+    /// Joins 8 `f32` lanes into a `u256`, lane 0 holding the least significant bits.
+    ///
+    /// # Arguments
+    /// * `lanes` - The lanes to join, each reinterpreted bit for bit.
+    ///
+    /// # Returns
+    /// A `u256` holding the lanes in little endian order.
+    pub fn f32_lanes_to_u256(lanes: [f32; 8]) -> u256 {
+        u256(std::array::from_fn(|i| lanes[2 * i].to_bits() as u64 | (lanes[2 * i + 1].to_bits() as u64) << 32))
+    }
+
+    /// Splits a `u256` into 4 `f64` lanes, lane 0 holding the least significant bits.
+    ///
+    /// The lane order matches `Registers::get_by_sections::<u64>` on a 256-bit register.
+    ///
+    /// # Arguments
+    /// * `value` - The value to split.
+    ///
+    /// # Returns
+    /// An array of the lanes of `value`, each reinterpreted bit for bit.
+    pub fn u256_to_f64_lanes(value: u256) -> [f64; 4] {
+        value.0.map(f64::from_bits)
+    }
+
+    /// Joins 4 `f64` lanes into a `u256`, lane 0 holding the least significant bits.
+    ///
+    /// # Arguments
+    /// * `lanes` - The lanes to join, each reinterpreted bit for bit.
+    ///
+    /// # Returns
+    /// A `u256` holding the lanes in little endian order.
+    pub fn f64_lanes_to_u256(lanes: [f64; 4]) -> u256 {
+        u256(lanes.map(f64::to_bits))
+    }
+
+    /// Splits a `u512` into 8 `u64` lanes, lane 0 holding the least significant bits.
+    ///
+    /// The lane order matches `Registers::get_by_sections::<u64>` on a 512-bit register.
+    ///
+    /// # Arguments
+    /// * `value` - The value to split.
+    ///
+    /// # Returns
+    /// An array of the lanes of `value`.
+    pub fn u512_to_u64_lanes(value: u512) -> [u64; 8] {
+        value.0
+    }
+
+    /// Joins 8 `u64` lanes into a `u512`, lane 0 holding the least significant bits.
+    ///
+    /// # Arguments
+    /// * `lanes` - The lanes to join.
+    ///
+    /// # Returns
+    /// A `u512` holding the lanes in little endian order.
+    pub fn u64_lanes_to_u512(lanes: [u64; 8]) -> u512 {
+        u512(lanes)
+    }
+
+    /// Splits a `u512` into 4 `u128` lanes, lane 0 holding the least significant bits.
+    ///
+    /// The lane order matches `Registers::get_by_sections::<u128>` on a 512-bit register.
+    ///
+    /// # Arguments
+    /// * `value` - The value to split.
+    ///
+    /// # Returns
+    /// An array of the lanes of `value`.
+    pub fn u512_to_u128_lanes(value: u512) -> [u128; 4] {
+        std::array::from_fn(|i| value.0[2 * i] as u128 | (value.0[2 * i + 1] as u128) << 64)
+    }
+
+    /// Joins 4 `u128` lanes into a `u512`, lane 0 holding the least significant bits.
+    ///
+    /// # Arguments
+    /// * `lanes` - The lanes to join.
+    ///
+    /// # Returns
+    /// A `u512` holding the lanes in little endian order.
+    pub fn u128_lanes_to_u512(lanes: [u128; 4]) -> u512 {
+        u512(std::array::from_fn(|i| (lanes[i / 2] >> (64 * (i % 2))) as u64))
+    }
+
+    /// Splits a `u512` into 16 `f32` lanes, lane 0 holding the least significant bits.
+    ///
+    /// The lane order matches `Registers::get_by_sections::<u32>` on a 512-bit register.
+    ///
+    /// # Arguments
+    /// * `value` - The value to split.
+    ///
+    /// # Returns
+    /// An array of the lanes of `value`, each reinterpreted bit for bit.
+    pub fn u512_to_f32_lanes(value: u512) -> [f32; 16] {
+        std::array::from_fn(|i| f32::from_bits((value.0[i / 2] >> (32 * (i % 2))) as u32))
+    }
+
+    /// Joins 16 `f32` lanes into a `u512`, lane 0 holding the least significant bits.
+    ///
+    /// # Arguments
+    /// * `lanes` - The lanes to join, each reinterpreted bit for bit.
+    ///
+    /// # Returns
+    /// A `u512` holding the lanes in little endian order.
+    pub fn f32_lanes_to_u512(lanes: [f32; 16]) -> u512 {
+        u512(std::array::from_fn(|i| lanes[2 * i].to_bits() as u64 | (lanes[2 * i + 1].to_bits() as u64) << 32))
+    }
+
+    /// Splits a `u512` into 8 `f64` lanes, lane 0 holding the least significant bits.
+    ///
+    /// The lane order matches `Registers::get_by_sections::<u64>` on a 512-bit register.
+    ///
+    /// # Arguments
+    /// * `value` - The value to split.
+    ///
+    /// # Returns
+    /// An array of the lanes of `value`, each reinterpreted bit for bit.
+    pub fn u512_to_f64_lanes(value: u512) -> [f64; 8] {
+        value.0.map(f64::from_bits)
+    }
+
+    /// Joins 8 `f64` lanes into a `u512`, lane 0 holding the least significant bits.
+    ///
+    /// # Arguments
+    /// * `lanes` - The lanes to join, each reinterpreted bit for bit.
+    ///
+    /// # Returns
+    /// A `u512` holding the lanes in little endian order.
+    pub fn f64_lanes_to_u512(lanes: [f64; 8]) -> u512 {
+        u512(lanes.map(f64::to_bits))
+    }
+
+    /// Returns the size in bits of a given vector register as defined in `VecRegName`.
+    ///
+    /// # Arguments
+    /// * `reg` - A reference to a `VecRegName` enum value representing the register.
+    ///
+    /// # Returns
+    /// 128 for `XMM`, 256 for `YMM` and 512 for `ZMM`.
+    pub fn get_vec_reg_size(reg: &VecRegName) -> usize {
+        reg.width_bits()
+    }
+
+    /// Returns the number of elements of a given width that fit in a vector register.
+    ///
+    /// # Arguments
+    /// * `reg` - A reference to a `VecRegName` enum value representing the register.
+    /// * `element_bits` - The width of each element in bits.
+    ///
+    /// # Returns
+    /// The number of whole elements in the register, which is 0 if an element is wider than the register.
+    ///
+    /// # Panics
+    /// Panics if `element_bits` is 0.
+    pub fn lane_count(reg: &VecRegName, element_bits: usize) -> usize {
+        Self::get_vec_reg_size(reg) / element_bits
+    }
+
+    /// Returns the width in bits of a section type.
+    ///
+    /// # Type Parameters
+    /// * `T` - The section type, e.g. `u8` or `u512`.
+    ///
+    /// # Returns
+    /// The number of bits in a `T`, e.g. 32 for `u32`.
+    pub fn element_bits<T: SectionCompatible>() -> usize {
+        std::mem::size_of::<T>() * 8
     }
 
     /// Returns the size in bits of a given general-purpose register (GPR) as defined in `GPRName`.
@@ -137,4 +694,2557 @@ impl Utilities {
             }
         }
     }
+
+    /// Counts the number of leading zero bits in an 8-bit unsigned integer (`u8`).
+    ///
+    /// # Arguments
+    /// * `v` - The `u8` value to be inspected.
+    ///
+    /// # Returns
+    /// A `u32` value holding the number of leading zeros, or 8 if `v` is 0.
+    pub fn clz_u8(v: u8) -> u32 {
+        v.leading_zeros()
+    }
+
+    /// Counts the number of leading zero bits in a 16-bit unsigned integer (`u16`).
+    ///
+    /// # Arguments
+    /// * `v` - The `u16` value to be inspected.
+    ///
+    /// # Returns
+    /// A `u32` value holding the number of leading zeros, or 16 if `v` is 0.
+    pub fn clz_u16(v: u16) -> u32 {
+        v.leading_zeros()
+    }
+
+    /// Counts the number of leading zero bits in a 32-bit unsigned integer (`u32`).
+    ///
+    /// # Arguments
+    /// * `v` - The `u32` value to be inspected.
+    ///
+    /// # Returns
+    /// A `u32` value holding the number of leading zeros, or 32 if `v` is 0.
+    pub fn clz_u32(v: u32) -> u32 {
+        v.leading_zeros()
+    }
+
+    /// Counts the number of leading zero bits in a 64-bit unsigned integer (`u64`).
+    ///
+    /// # Arguments
+    /// * `v` - The `u64` value to be inspected.
+    ///
+    /// # Returns
+    /// A `u32` value holding the number of leading zeros, or 64 if `v` is 0.
+    pub fn clz_u64(v: u64) -> u32 {
+        v.leading_zeros()
+    }
+
+    /// Counts the number of leading zero bits in a 128-bit unsigned integer (`u128`).
+    ///
+    /// # Arguments
+    /// * `v` - The `u128` value to be inspected.
+    ///
+    /// # Returns
+    /// A `u32` value holding the number of leading zeros, or 128 if `v` is 0.
+    pub fn clz_u128(v: u128) -> u32 {
+        v.leading_zeros()
+    }
+
+    /// Counts the number of trailing zero bits in an 8-bit unsigned integer (`u8`).
+    ///
+    /// # Arguments
+    /// * `v` - The `u8` value to be inspected.
+    ///
+    /// # Returns
+    /// A `u32` value holding the number of trailing zeros, or 8 if `v` is 0.
+    pub fn ctz_u8(v: u8) -> u32 {
+        v.trailing_zeros()
+    }
+
+    /// Counts the number of trailing zero bits in a 16-bit unsigned integer (`u16`).
+    ///
+    /// # Arguments
+    /// * `v` - The `u16` value to be inspected.
+    ///
+    /// # Returns
+    /// A `u32` value holding the number of trailing zeros, or 16 if `v` is 0.
+    pub fn ctz_u16(v: u16) -> u32 {
+        v.trailing_zeros()
+    }
+
+    /// Counts the number of trailing zero bits in a 32-bit unsigned integer (`u32`).
+    ///
+    /// # Arguments
+    /// * `v` - The `u32` value to be inspected.
+    ///
+    /// # Returns
+    /// A `u32` value holding the number of trailing zeros, or 32 if `v` is 0.
+    pub fn ctz_u32(v: u32) -> u32 {
+        v.trailing_zeros()
+    }
+
+    /// Counts the number of trailing zero bits in a 64-bit unsigned integer (`u64`).
+    ///
+    /// # Arguments
+    /// * `v` - The `u64` value to be inspected.
+    ///
+    /// # Returns
+    /// A `u32` value holding the number of trailing zeros, or 64 if `v` is 0.
+    pub fn ctz_u64(v: u64) -> u32 {
+        v.trailing_zeros()
+    }
+
+    /// Counts the number of trailing zero bits in a 128-bit unsigned integer (`u128`).
+    ///
+    /// # Arguments
+    /// * `v` - The `u128` value to be inspected.
+    ///
+    /// # Returns
+    /// A `u32` value holding the number of trailing zeros, or 128 if `v` is 0.
+    pub fn ctz_u128(v: u128) -> u32 {
+        v.trailing_zeros()
+    }
+
+    /// Reverses the bit order of an 8-bit unsigned integer (`u8`).
+    ///
+    /// # Arguments
+    /// * `v` - The `u8` value to be reversed.
+    ///
+    /// # Returns
+    /// A `u8` value whose bit `i` equals bit `7 - i` of the input.
+    pub fn bit_reverse_u8(v: u8) -> u8 {
+        v.reverse_bits()
+    }
+
+    /// Reverses the bit order of a 16-bit unsigned integer (`u16`).
+    ///
+    /// # Arguments
+    /// * `v` - The `u16` value to be reversed.
+    ///
+    /// # Returns
+    /// A `u16` value whose bit `i` equals bit `15 - i` of the input.
+    pub fn bit_reverse_u16(v: u16) -> u16 {
+        v.reverse_bits()
+    }
+
+    /// Reverses the bit order of a 32-bit unsigned integer (`u32`).
+    ///
+    /// # Arguments
+    /// * `v` - The `u32` value to be reversed.
+    ///
+    /// # Returns
+    /// A `u32` value whose bit `i` equals bit `31 - i` of the input.
+    pub fn bit_reverse_u32(v: u32) -> u32 {
+        v.reverse_bits()
+    }
+
+    /// Reverses the bit order of a 64-bit unsigned integer (`u64`).
+    ///
+    /// # Arguments
+    /// * `v` - The `u64` value to be reversed.
+    ///
+    /// # Returns
+    /// A `u64` value whose bit `i` equals bit `63 - i` of the input.
+    pub fn bit_reverse_u64(v: u64) -> u64 {
+        v.reverse_bits()
+    }
+
+    /// Rotates a 64-bit unsigned integer (`u64`) to the left.
+    ///
+    /// # Arguments
+    /// * `v` - The `u64` value to be rotated.
+    /// * `n` - The number of bit positions to rotate by, taken modulo 64.
+    ///
+    /// # Returns
+    /// A `u64` value with the bits shifted out on the left wrapped around to the right.
+    pub fn rotate_left_u64(v: u64, n: u32) -> u64 {
+        v.rotate_left(n)
+    }
+
+    /// Rotates a 64-bit unsigned integer (`u64`) to the right.
+    ///
+    /// # Arguments
+    /// * `v` - The `u64` value to be rotated.
+    /// * `n` - The number of bit positions to rotate by, taken modulo 64.
+    ///
+    /// # Returns
+    /// A `u64` value with the bits shifted out on the right wrapped around to the left.
+    pub fn rotate_right_u64(v: u64, n: u32) -> u64 {
+        v.rotate_right(n)
+    }
+
+    /// Reverses the byte order of a 16-bit unsigned integer (`u16`).
+    ///
+    /// # Arguments
+    /// * `v` - The `u16` value to byte-swap.
+    ///
+    /// # Returns
+    /// A `u16` value whose first byte is the last byte of `v` and so on.
+    pub fn bswap16(v: u16) -> u16 {
+        v.swap_bytes()
+    }
+
+    /// Reverses the byte order of a 32-bit unsigned integer (`u32`), matching `BSWAP`.
+    ///
+    /// # Arguments
+    /// * `v` - The `u32` value to byte-swap.
+    ///
+    /// # Returns
+    /// A `u32` value whose first byte is the last byte of `v` and so on.
+    pub fn bswap32(v: u32) -> u32 {
+        v.swap_bytes()
+    }
+
+    /// Reverses the byte order of a 64-bit unsigned integer (`u64`), matching `BSWAP`.
+    ///
+    /// # Arguments
+    /// * `v` - The `u64` value to byte-swap.
+    ///
+    /// # Returns
+    /// A `u64` value whose first byte is the last byte of `v` and so on.
+    pub fn bswap64(v: u64) -> u64 {
+        v.swap_bytes()
+    }
+
+    /// Reverses the byte order of a 128-bit unsigned integer (`u128`).
+    ///
+    /// # Arguments
+    /// * `v` - The `u128` value to byte-swap.
+    ///
+    /// # Returns
+    /// A `u128` value whose first byte is the last byte of `v` and so on.
+    pub fn bswap128(v: u128) -> u128 {
+        v.swap_bytes()
+    }
+
+    /// Reverses the byte order of a 256-bit unsigned integer (`u256`) across its full width.
+    ///
+    /// # Arguments
+    /// * `v` - The `u256` value to byte-swap.
+    ///
+    /// # Returns
+    /// A `u256` value whose little endian encoding is the big endian encoding of `v`.
+    pub fn bswap_u256(v: u256) -> u256 {
+        let mut bytes = [0u8; 32];
+        v.to_big_endian(&mut bytes);
+        u256::from_little_endian(&bytes)
+    }
+
+    /// Reverses the byte order of a 512-bit unsigned integer (`u512`) across its full width.
+    ///
+    /// # Arguments
+    /// * `v` - The `u512` value to byte-swap.
+    ///
+    /// # Returns
+    /// A `u512` value whose little endian encoding is the big endian encoding of `v`.
+    pub fn bswap_u512(v: u512) -> u512 {
+        let mut bytes = [0u8; 64];
+        v.to_big_endian(&mut bytes);
+        u512::from_little_endian(&bytes)
+    }
+
+    /// Reverses the byte order of every lane of a vector, as `MOVBE` does for each element.
+    ///
+    /// # Type Parameters
+    /// * `T` - The lane type; its `MemoryIO` encoding decides which bytes are reversed.
+    ///
+    /// # Arguments
+    /// * `lanes` - The lanes to byte-swap.
+    ///
+    /// # Returns
+    /// A `Vec<T>` holding each lane of `lanes` with its bytes reversed, in the original lane order.
+    pub fn bswap_lanes<T: MemoryIO>(lanes: Vec<T>) -> Vec<T> {
+        lanes.into_iter()
+            .map(|lane| {
+                let mut bytes = lane.to_bytes();
+                bytes.reverse();
+                T::from_bytes(&bytes)
+            })
+            .collect()
+    }
+
+    /// Sign-extends the low `from_bits` bits of a value to a 64-bit signed integer (`i64`).
+    ///
+    /// The low `from_bits` bits of `value` are treated as a two's-complement signed integer,
+    /// so `sign_extend(0xFF, 8)` yields `-1`. Bits above `from_bits` are ignored. For the result as a
+    /// `u64` register value, cast it with `as u64`, or use the fixed-width `sext*` helpers such as `sext8_64`.
+    ///
+    /// # Arguments
+    /// * `value` - The value holding the signed integer in its low bits.
+    /// * `from_bits` - The width of the signed integer, between 1 and 64.
+    ///
+    /// # Returns
+    /// An `i64` value holding the sign-extended integer.
+    ///
+    /// # Panics
+    /// Panics if `from_bits` is 0 or greater than 64.
+    pub fn sign_extend(value: u64, from_bits: usize) -> i64 {
+        if from_bits == 0 || from_bits > 64 {
+            panic!("Invalid width {} for sign extension", from_bits);
+        }
+        let shift = 64 - from_bits;
+        ((value << shift) as i64) >> shift
+    }
+
+    /// Zero-extends the low `from_bits` bits of a value to a 64-bit unsigned integer (`u64`).
+    ///
+    /// # Arguments
+    /// * `value` - The value holding the unsigned integer in its low bits.
+    /// * `from_bits` - The width of the unsigned integer, between 1 and 64.
+    ///
+    /// # Returns
+    /// A `u64` value holding only the low `from_bits` bits of `value`.
+    ///
+    /// # Panics
+    /// Panics if `from_bits` is 0 or greater than 64.
+    pub fn zero_extend(value: u64, from_bits: usize) -> u64 {
+        if from_bits == 0 || from_bits > 64 {
+            panic!("Invalid width {} for zero extension", from_bits);
+        }
+        value & (u64::MAX >> (64 - from_bits))
+    }
+
+    /// Sign-extends a 8-bit value to 16 bits, matching `CBW` and `MOVSX r16, r/m8`.
+    ///
+    /// # Arguments
+    /// * `v` - The value to extend, read as a two's-complement signed integer.
+    ///
+    /// # Returns
+    /// A `u16` value whose upper 8 bits are copies of bit 7 of `v`.
+    pub fn sext8_16(v: u8) -> u16 {
+        v as i8 as i16 as u16
+    }
+
+    /// Sign-extends a 8-bit value to 32 bits, matching `MOVSX r32, r/m8`.
+    ///
+    /// # Arguments
+    /// * `v` - The value to extend, read as a two's-complement signed integer.
+    ///
+    /// # Returns
+    /// A `u32` value whose upper 24 bits are copies of bit 7 of `v`.
+    pub fn sext8_32(v: u8) -> u32 {
+        v as i8 as i32 as u32
+    }
+
+    /// Sign-extends a 8-bit value to 64 bits, matching `MOVSX r64, r/m8`.
+    ///
+    /// # Arguments
+    /// * `v` - The value to extend, read as a two's-complement signed integer.
+    ///
+    /// # Returns
+    /// A `u64` value whose upper 56 bits are copies of bit 7 of `v`.
+    pub fn sext8_64(v: u8) -> u64 {
+        v as i8 as i64 as u64
+    }
+
+    /// Sign-extends a 16-bit value to 32 bits, matching `CWDE` and `MOVSX r32, r/m16`.
+    ///
+    /// # Arguments
+    /// * `v` - The value to extend, read as a two's-complement signed integer.
+    ///
+    /// # Returns
+    /// A `u32` value whose upper 16 bits are copies of bit 15 of `v`.
+    pub fn sext16_32(v: u16) -> u32 {
+        v as i16 as i32 as u32
+    }
+
+    /// Sign-extends a 16-bit value to 64 bits, matching `MOVSX r64, r/m16`.
+    ///
+    /// # Arguments
+    /// * `v` - The value to extend, read as a two's-complement signed integer.
+    ///
+    /// # Returns
+    /// A `u64` value whose upper 48 bits are copies of bit 15 of `v`.
+    pub fn sext16_64(v: u16) -> u64 {
+        v as i16 as i64 as u64
+    }
+
+    /// Sign-extends a 32-bit value to 64 bits, matching `CDQE` and `MOVSXD`.
+    ///
+    /// # Arguments
+    /// * `v` - The value to extend, read as a two's-complement signed integer.
+    ///
+    /// # Returns
+    /// A `u64` value whose upper 32 bits are copies of bit 31 of `v`.
+    pub fn sext32_64(v: u32) -> u64 {
+        v as i32 as i64 as u64
+    }
+
+    /// Computes the flags set by `ADD` or, with a carry in, `ADC`.
+    ///
+    /// Only the low `width_bits` bits of each operand are used. CF reports an unsigned carry out,
+    /// OF a signed overflow and AF a carry out of bit 3; PF reflects the low byte of the result.
+    ///
+    /// # Arguments
+    /// * `a` - The first operand.
+    /// * `b` - The second operand.
+    /// * `carry_in` - Whether an extra 1 is added, as `ADC` does with CF set.
+    /// * `width_bits` - The operand width: 8, 16, 32 or 64.
+    ///
+    /// # Returns
+    /// The six arithmetic flags of `a + b + carry_in`.
+    ///
+    /// # Panics
+    /// Panics if `width_bits` is not 8, 16, 32 or 64.
+    pub fn flags_after_add(a: u64, b: u64, carry_in: bool, width_bits: usize) -> FlagsDelta {
+        let mask = Self::flag_width_mask(width_bits);
+        let (a, b) = (a & mask, b & mask);
+        let sum = a as u128 + b as u128 + carry_in as u128;
+        let result = sum as u64 & mask;
+        FlagsDelta {
+            cf: sum > mask as u128,
+            af: (a ^ b ^ result) & 0x10 != 0,
+            of: ((a ^ result) & (b ^ result)) >> (width_bits - 1) & 1 != 0,
+            ..FlagsDelta::from_result(result, width_bits)
+        }
+    }
+
+    /// Computes the flags set by `SUB` and `CMP` or, with a borrow in, `SBB`.
+    ///
+    /// Only the low `width_bits` bits of each operand are used. CF reports an unsigned borrow,
+    /// OF a signed overflow and AF a borrow into bit 3; PF reflects the low byte of the result.
+    ///
+    /// # Arguments
+    /// * `a` - The operand subtracted from.
+    /// * `b` - The operand subtracted.
+    /// * `borrow_in` - Whether an extra 1 is subtracted, as `SBB` does with CF set.
+    /// * `width_bits` - The operand width: 8, 16, 32 or 64.
+    ///
+    /// # Returns
+    /// The six arithmetic flags of `a - b - borrow_in`.
+    ///
+    /// # Panics
+    /// Panics if `width_bits` is not 8, 16, 32 or 64.
+    pub fn flags_after_sub(a: u64, b: u64, borrow_in: bool, width_bits: usize) -> FlagsDelta {
+        let mask = Self::flag_width_mask(width_bits);
+        let (a, b) = (a & mask, b & mask);
+        let result = a.wrapping_sub(b).wrapping_sub(borrow_in as u64) & mask;
+        FlagsDelta {
+            cf: (b as u128 + borrow_in as u128) > a as u128,
+            af: (a ^ b ^ result) & 0x10 != 0,
+            of: ((a ^ b) & (a ^ result)) >> (width_bits - 1) & 1 != 0,
+            ..FlagsDelta::from_result(result, width_bits)
+        }
+    }
+
+    /// Computes the flags set by `AND`, `OR`, `XOR` and `TEST`.
+    ///
+    /// CF and OF are cleared. AF is architecturally undefined after these instructions and is cleared as well.
+    ///
+    /// # Arguments
+    /// * `result` - The result of the operation; only its low `width_bits` bits are used.
+    /// * `width_bits` - The operand width: 8, 16, 32 or 64.
+    ///
+    /// # Returns
+    /// The six arithmetic flags of `result`.
+    ///
+    /// # Panics
+    /// Panics if `width_bits` is not 8, 16, 32 or 64.
+    pub fn flags_after_logic(result: u64, width_bits: usize) -> FlagsDelta {
+        FlagsDelta::from_result(result & Self::flag_width_mask(width_bits), width_bits)
+    }
+
+    /// Counts the leading zero bits of an operand, as `LZCNT` does.
+    ///
+    /// Unlike `BSR`, a zero operand is well defined and yields `width_bits`. `LZCNT` sets CF if the
+    /// operand is zero and ZF if the count is zero; OF, SF, AF and PF are architecturally undefined
+    /// and are cleared.
+    ///
+    /// # Arguments
+    /// * `value` - The operand; only its low `width_bits` bits are used.
+    /// * `width_bits` - The operand width: 16, 32 or 64 for the instruction, 8 is also accepted.
+    ///
+    /// # Returns
+    /// The count and the flags `LZCNT` would produce.
+    ///
+    /// # Panics
+    /// Panics if `width_bits` is not 8, 16, 32 or 64.
+    pub fn lzcnt(value: u64, width_bits: usize) -> (u32, FlagsDelta) {
+        let value = value & Self::flag_width_mask(width_bits);
+        let count = value.leading_zeros() - (64 - width_bits as u32);
+        (count, FlagsDelta { cf: value == 0, zf: count == 0, ..FlagsDelta::default() })
+    }
+
+    /// Counts the trailing zero bits of an operand, as `TZCNT` does.
+    ///
+    /// Unlike `BSF`, a zero operand is well defined and yields `width_bits`. `TZCNT` sets CF if the
+    /// operand is zero and ZF if the count is zero; OF, SF, AF and PF are architecturally undefined
+    /// and are cleared.
+    ///
+    /// # Arguments
+    /// * `value` - The operand; only its low `width_bits` bits are used.
+    /// * `width_bits` - The operand width: 16, 32 or 64 for the instruction, 8 is also accepted.
+    ///
+    /// # Returns
+    /// The count and the flags `TZCNT` would produce.
+    ///
+    /// # Panics
+    /// Panics if `width_bits` is not 8, 16, 32 or 64.
+    pub fn tzcnt(value: u64, width_bits: usize) -> (u32, FlagsDelta) {
+        let value = value & Self::flag_width_mask(width_bits);
+        let count = value.trailing_zeros().min(width_bits as u32);
+        (count, FlagsDelta { cf: value == 0, zf: count == 0, ..FlagsDelta::default() })
+    }
+
+    /// Finds the index of the lowest set bit of an operand, as `BSF` does.
+    ///
+    /// `BSF` sets ZF if the operand is zero and clears it otherwise; in the zero case the
+    /// destination is left unchanged (Intel documents it as undefined). CF, OF, SF, AF and PF are
+    /// undefined.
+    ///
+    /// # Arguments
+    /// * `value` - The operand; only its low `width_bits` bits are used.
+    /// * `width_bits` - The operand width: 16, 32 or 64 for the instruction, 8 is also accepted.
+    ///
+    /// # Returns
+    /// The bit index, or `None` if the operand is zero, which is when `BSF` sets ZF.
+    ///
+    /// # Panics
+    /// Panics if `width_bits` is not 8, 16, 32 or 64.
+    pub fn bsf(value: u64, width_bits: usize) -> Option<u32> {
+        let value = value & Self::flag_width_mask(width_bits);
+        (value != 0).then(|| value.trailing_zeros())
+    }
+
+    /// Finds the index of the highest set bit of an operand, as `BSR` does.
+    ///
+    /// `BSR` sets ZF if the operand is zero and clears it otherwise; in the zero case the
+    /// destination is left unchanged (Intel documents it as undefined). CF, OF, SF, AF and PF are
+    /// undefined.
+    ///
+    /// # Arguments
+    /// * `value` - The operand; only its low `width_bits` bits are used.
+    /// * `width_bits` - The operand width: 16, 32 or 64 for the instruction, 8 is also accepted.
+    ///
+    /// # Returns
+    /// The bit index, or `None` if the operand is zero, which is when `BSR` sets ZF.
+    ///
+    /// # Panics
+    /// Panics if `width_bits` is not 8, 16, 32 or 64.
+    pub fn bsr(value: u64, width_bits: usize) -> Option<u32> {
+        let value = value & Self::flag_width_mask(width_bits);
+        (value != 0).then(|| 63 - value.leading_zeros())
+    }
+
+    /// Counts the set bits of an operand, as `POPCNT` does.
+    ///
+    /// `POPCNT` sets ZF if the operand is zero and clears CF, OF, SF, AF and PF.
+    ///
+    /// # Arguments
+    /// * `value` - The operand; only its low `width_bits` bits are used.
+    /// * `width_bits` - The operand width: 16, 32 or 64 for the instruction, 8 is also accepted.
+    ///
+    /// # Returns
+    /// The count and the flags `POPCNT` would produce.
+    ///
+    /// # Panics
+    /// Panics if `width_bits` is not 8, 16, 32 or 64.
+    pub fn popcnt(value: u64, width_bits: usize) -> (u32, FlagsDelta) {
+        let value = value & Self::flag_width_mask(width_bits);
+        (value.count_ones(), FlagsDelta { zf: value == 0, ..FlagsDelta::default() })
+    }
+
+    /// Multiplies two 64-bit polynomials over GF(2), the carry-less product `PCLMULQDQ` computes.
+    ///
+    /// # Arguments
+    /// * `a` - The first factor, bit `i` being the coefficient of `x^i`.
+    /// * `b` - The second factor, in the same representation.
+    ///
+    /// # Returns
+    /// The 127-bit product; bit 127 is always clear.
+    pub fn clmul64(a: u64, b: u64) -> u128 {
+        (0..64).filter(|i| b >> i & 1 != 0).fold(0, |product, i| product ^ (a as u128) << i)
+    }
+
+    /// Computes `PCLMULQDQ`, multiplying the quadwords of two 128-bit operands selected by an immediate.
+    ///
+    /// # Arguments
+    /// * `a` - The first operand (`xmm1`).
+    /// * `b` - The second operand (`xmm2/m128`).
+    /// * `imm` - The immediate; bit 0 selects the high quadword of `a` and bit 4 the high quadword
+    ///   of `b`. The other bits are ignored.
+    ///
+    /// # Returns
+    /// The carry-less product of the selected quadwords.
+    pub fn clmul128(a: u128, b: u128, imm: u8) -> u128 {
+        let a = if imm & 0x01 != 0 { a >> 64 } else { a };
+        let b = if imm & 0x10 != 0 { b >> 64 } else { b };
+        Self::clmul64(a as u64, b as u64)
+    }
+
+    /// Accumulates a byte into a CRC32C, as `CRC32 r32, r/m8` does.
+    ///
+    /// The `CRC32` instruction uses the Castagnoli polynomial (`0x1EDC6F41`) in its bit-reflected
+    /// form and performs no initial or final inversion; a standard CRC32C starts from `0xFFFFFFFF`
+    /// and inverts the final value.
+    ///
+    /// # Arguments
+    /// * `crc` - The running CRC.
+    /// * `data` - The byte to accumulate.
+    ///
+    /// # Returns
+    /// The updated CRC.
+    pub fn crc32c_u8(crc: u32, data: u8) -> u32 {
+        crc32c_bits(crc, data as u64, 8)
+    }
+
+    /// Accumulates a word into a CRC32C, as `CRC32 r32, r/m16` does.
+    ///
+    /// # Arguments
+    /// * `crc` - The running CRC.
+    /// * `data` - The word to accumulate, taken in little-endian byte order.
+    ///
+    /// # Returns
+    /// The updated CRC.
+    pub fn crc32c_u16(crc: u32, data: u16) -> u32 {
+        crc32c_bits(crc, data as u64, 16)
+    }
+
+    /// Accumulates a doubleword into a CRC32C, as `CRC32 r32, r/m32` does.
+    ///
+    /// # Arguments
+    /// * `crc` - The running CRC.
+    /// * `data` - The doubleword to accumulate, taken in little-endian byte order.
+    ///
+    /// # Returns
+    /// The updated CRC.
+    pub fn crc32c_u32(crc: u32, data: u32) -> u32 {
+        crc32c_bits(crc, data as u64, 32)
+    }
+
+    /// Accumulates a quadword into a CRC32C, as `CRC32 r64, r/m64` does.
+    ///
+    /// # Arguments
+    /// * `crc` - The running CRC.
+    /// * `data` - The quadword to accumulate, taken in little-endian byte order.
+    ///
+    /// # Returns
+    /// The updated CRC; the instruction zero-extends it into the 64-bit destination.
+    pub fn crc32c_u64(crc: u32, data: u64) -> u32 {
+        crc32c_bits(crc, data, 64)
+    }
+
+    /// Accumulates a byte slice into a CRC32C, one `crc32c_u8` step per byte.
+    ///
+    /// Like the instruction, no inversion is applied, so the standard checksum of `data` is
+    /// `!Utilities::crc32c_slice(!0, data)`.
+    ///
+    /// # Arguments
+    /// * `crc` - The running CRC.
+    /// * `data` - The bytes to accumulate, in order.
+    ///
+    /// # Returns
+    /// The updated CRC.
+    pub fn crc32c_slice(crc: u32, data: &[u8]) -> u32 {
+        data.iter().fold(crc, |crc, byte| Self::crc32c_u8(crc, *byte))
+    }
+
+    /// Fills every register with pseudo-random contents determined by a seed.
+    ///
+    /// Equivalent to `randomize_registers_with` with the default options.
+    ///
+    /// # Arguments
+    /// * `registers` - The registers to overwrite.
+    /// * `seed` - The seed; the same seed always produces the same state.
+    pub fn randomize_registers(registers: &mut Registers, seed: u64) {
+        Self::randomize_registers_with(registers, seed, RandomizeOptions::default());
+    }
+
+    /// Fills every register with pseudo-random contents determined by a seed.
+    ///
+    /// The 16 GPRs, the SIMD registers accessible in the current mode, the opmask registers and RIP
+    /// are overwritten. RFLAGS gets random status and control flags with the reserved bit 1 set.
+    ///
+    /// # Arguments
+    /// * `registers` - The registers to overwrite.
+    /// * `seed` - The seed; the same seed and options always produce the same state.
+    /// * `options` - Whether to bias the values toward edge cases.
+    pub fn randomize_registers_with(registers: &mut Registers, seed: u64, options: RandomizeOptions) {
+        let mut rng = SplitMix64(seed);
+        for name in GPR64_NAMES {
+            registers.set_gpr_value(name, rng.next_quad(options));
+        }
+        for index in 0..registers.mode().simd_register_count() {
+            let lanes: Vec<u64> = (0..8).map(|_| rng.next_quad(options)).collect();
+            registers.set_by_sections(VecRegName::ZMM, index, lanes);
+        }
+        for name in [KMaskName::K0, KMaskName::K1, KMaskName::K2, KMaskName::K3,
+                     KMaskName::K4, KMaskName::K5, KMaskName::K6, KMaskName::K7] {
+            registers.set_kmask_value(name, rng.next_quad(options));
+        }
+        let flags = rng.next() & (CF | PF | AF | ZF | SF | TF | IF | DF | OF) | 1 << 1;
+        registers.set_flags_value(FLAGSName::RFLAGS, flags);
+        registers.set_ip_value(IPName::RIP, rng.next_quad(options));
+    }
+
+    /// Fills a range of memory with pseudo-random bytes determined by a seed.
+    ///
+    /// Equivalent to `randomize_memory_with` with the default options.
+    ///
+    /// # Arguments
+    /// * `memory` - The memory to write to.
+    /// * `range` - The half-open range of addresses to fill.
+    /// * `seed` - The seed; the same seed always produces the same contents.
+    ///
+    /// # Returns
+    /// `Ok(())` on success, or the error met while writing, in which case nothing is written.
+    pub fn randomize_memory(memory: &mut Memory, range: Range<u64>, seed: u64) -> Result<(), MemoryError> {
+        Self::randomize_memory_with(memory, range, seed, RandomizeOptions::default())
+    }
+
+    /// Fills a range of memory with pseudo-random bytes determined by a seed.
+    ///
+    /// The bytes are generated a quadword at a time from `range.start`, so edge cases requested by
+    /// `options` land on 8-byte boundaries relative to the start of the range.
+    ///
+    /// # Arguments
+    /// * `memory` - The memory to write to.
+    /// * `range` - The half-open range of addresses to fill.
+    /// * `seed` - The seed; the same seed and options always produce the same contents.
+    /// * `options` - Whether to bias the values toward edge cases.
+    ///
+    /// # Returns
+    /// `Ok(())` on success, or the error met while writing, in which case nothing is written.
+    pub fn randomize_memory_with(memory: &mut Memory, range: Range<u64>, seed: u64, options: RandomizeOptions) -> Result<(), MemoryError> {
+        let mut rng = SplitMix64(seed);
+        let length = range.end.saturating_sub(range.start) as usize;
+        let mut bytes: Vec<u8> = (0..length.div_ceil(8)).flat_map(|_| rng.next_quad(options).to_le_bytes()).collect();
+        bytes.truncate(length);
+        memory.write_bytes(range.start, &bytes)
+    }
+
+    /// Formats a `u256` as `0x` followed by all 64 hex digits, leading zeros included.
+    ///
+    /// # Arguments
+    /// * `value` - The value to format.
+    ///
+    /// # Returns
+    /// A 66-character lowercase string such as `0x00...0ff`.
+    pub fn to_hex_u256(value: &u256) -> String {
+        words_to_hex(&value.0)
+    }
+
+    /// Formats a `u512` as `0x` followed by all 128 hex digits, leading zeros included.
+    ///
+    /// # Arguments
+    /// * `value` - The value to format.
+    ///
+    /// # Returns
+    /// A 130-character lowercase string.
+    pub fn to_hex_u512(value: &u512) -> String {
+        words_to_hex(&value.0)
+    }
+
+    /// Parses a hexadecimal `u256`.
+    ///
+    /// The text may start with `0x` or `0X`, digits may be in either case and `_` may separate
+    /// them anywhere, so `0xdead_BEEF` is accepted. Any number of leading zeros is allowed.
+    ///
+    /// # Arguments
+    /// * `text` - The text to parse.
+    ///
+    /// # Returns
+    /// The parsed value, or the reason the text is not a valid `u256`.
+    pub fn parse_hex_u256(text: &str) -> Result<u256, ParseHexError> {
+        parse_hex_words(text).map(u256)
+    }
+
+    /// Parses a hexadecimal `u512`, accepting the same syntax as `parse_hex_u256`.
+    ///
+    /// # Arguments
+    /// * `text` - The text to parse.
+    ///
+    /// # Returns
+    /// The parsed value, or the reason the text is not a valid `u512`.
+    pub fn parse_hex_u512(text: &str) -> Result<u512, ParseHexError> {
+        parse_hex_words(text).map(u512)
+    }
+
+    /// Formats SIMD lanes in hex, lane 0 first.
+    ///
+    /// Each lane is zero-padded to the full width of `T`, so the output only depends on the lane
+    /// values: `lanes_to_hex(&[1u16, 0xabc], "0x", " ")` yields `0x0001 0x0abc`.
+    ///
+    /// # Arguments
+    /// * `lanes` - The lanes to format.
+    /// * `prefix` - The text written before each lane, e.g. `"0x"` or `""`.
+    /// * `separator` - The text written between lanes, e.g. `" "` or `"_"`.
+    ///
+    /// # Returns
+    /// The formatted lanes, or an empty string if there are none.
+    pub fn lanes_to_hex<T: SectionCompatible + LowerHex>(lanes: &[T], prefix: &str, separator: &str) -> String {
+        let width = std::mem::size_of::<T>() * 2;
+        lanes.iter()
+            .map(|lane| format!("{}{:0width$x}", prefix, lane, width = width))
+            .collect::<Vec<_>>()
+            .join(separator)
+    }
+
+    /// Parses a register name, ignoring case.
+    ///
+    /// Accepts every name the `Display` implementations of `GPRName`, `FLAGSName`, `IPName` and
+    /// `KMaskName` produce, and vector registers written as `XMM`, `YMM` or `ZMM` followed by a
+    /// decimal index without leading zeros.
+    ///
+    /// # Arguments
+    /// * `name` - The register name, e.g. `"eax"`, `"RFLAGS"` or `"ymm12"`.
+    /// * `register_count` - The number of vector registers configured, e.g. 16 for SSE in 64-bit mode
+    ///   or `CPUMode::simd_register_count` for the registers accessible in a mode.
+    ///
+    /// # Returns
+    /// The register named, or `None` if the name is unknown or the vector index is not below `register_count`.
+    pub fn parse_register(name: &str, register_count: usize) -> Option<RegisterRef> {
+        let name = name.to_ascii_uppercase();
+        if let Some(gpr) = GPRName::ALL.iter().find(|gpr| gpr.to_string() == name) {
+            return Some(RegisterRef::Gpr(*gpr));
+        }
+        match name.as_str() {
+            "RFLAGS" => return Some(RegisterRef::Flags(FLAGSName::RFLAGS)),
+            "EFLAGS" => return Some(RegisterRef::Flags(FLAGSName::EFLAGS)),
+            "FLAGS" => return Some(RegisterRef::Flags(FLAGSName::FLAGS)),
+            "RIP" => return Some(RegisterRef::Ip(IPName::RIP)),
+            "EIP" => return Some(RegisterRef::Ip(IPName::EIP)),
+            "IP" => return Some(RegisterRef::Ip(IPName::IP)),
+            _ => {}
+        }
+        if let Some(kmask) = KMaskName::ALL.iter().find(|kmask| kmask.to_string() == name) {
+            return Some(RegisterRef::KMask(*kmask));
+        }
+        let (reg_type, digits) = [VecRegName::XMM, VecRegName::YMM, VecRegName::ZMM].into_iter()
+            .find_map(|reg_type| Some((reg_type, name.strip_prefix(&reg_type.to_string())?)))?;
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) || (digits.len() > 1 && digits.starts_with('0')) {
+            return None;
+        }
+        let index = digits.parse::<usize>().ok().filter(|index| *index < register_count)?;
+        Some(RegisterRef::Vec(reg_type, index))
+    }
+
+    /// Adds two slices of 8-bit signed integers (`i8`) element-wise with saturation, matching `PADDSB`.
+    ///
+    /// # Arguments
+    /// * `a` - The first operand lanes.
+    /// * `b` - The second operand lanes.
+    ///
+    /// # Returns
+    /// A `Vec<i8>` holding each sum clamped to the range of `i8`, or `LengthMismatch` if the slices differ in length.
+    pub fn saturating_add_i8_vec(a: &[i8], b: &[i8]) -> Result<Vec<i8>, LengthMismatch> {
+        Self::check_lengths(a.len(), b.len())?;
+        Ok(a.iter().zip(b).map(|(x, y)| x.saturating_add(*y)).collect())
+    }
+
+    /// Adds two slices of 8-bit unsigned integers (`u8`) element-wise with saturation, matching `PADDUSB`.
+    ///
+    /// # Arguments
+    /// * `a` - The first operand lanes.
+    /// * `b` - The second operand lanes.
+    ///
+    /// # Returns
+    /// A `Vec<u8>` holding each sum clamped to the range of `u8`, or `LengthMismatch` if the slices differ in length.
+    pub fn saturating_add_u8_vec(a: &[u8], b: &[u8]) -> Result<Vec<u8>, LengthMismatch> {
+        Self::check_lengths(a.len(), b.len())?;
+        Ok(a.iter().zip(b).map(|(x, y)| x.saturating_add(*y)).collect())
+    }
+
+    /// Adds two slices of 16-bit signed integers (`i16`) element-wise with saturation, matching `PADDSW`.
+    ///
+    /// # Arguments
+    /// * `a` - The first operand lanes.
+    /// * `b` - The second operand lanes.
+    ///
+    /// # Returns
+    /// A `Vec<i16>` holding each sum clamped to the range of `i16`, or `LengthMismatch` if the slices differ in length.
+    pub fn saturating_add_i16_vec(a: &[i16], b: &[i16]) -> Result<Vec<i16>, LengthMismatch> {
+        Self::check_lengths(a.len(), b.len())?;
+        Ok(a.iter().zip(b).map(|(x, y)| x.saturating_add(*y)).collect())
+    }
+
+    /// Adds two slices of 16-bit unsigned integers (`u16`) element-wise with saturation, matching `PADDUSW`.
+    ///
+    /// # Arguments
+    /// * `a` - The first operand lanes.
+    /// * `b` - The second operand lanes.
+    ///
+    /// # Returns
+    /// A `Vec<u16>` holding each sum clamped to the range of `u16`, or `LengthMismatch` if the slices differ in length.
+    pub fn saturating_add_u16_vec(a: &[u16], b: &[u16]) -> Result<Vec<u16>, LengthMismatch> {
+        Self::check_lengths(a.len(), b.len())?;
+        Ok(a.iter().zip(b).map(|(x, y)| x.saturating_add(*y)).collect())
+    }
+
+    /// Subtracts two slices of 8-bit signed integers (`i8`) element-wise with saturation, matching `PSUBSB`.
+    ///
+    /// # Arguments
+    /// * `a` - The first operand lanes.
+    /// * `b` - The second operand lanes, subtracted from `a`.
+    ///
+    /// # Returns
+    /// A `Vec<i8>` holding each difference clamped to the range of `i8`, or `LengthMismatch` if the slices differ in length.
+    pub fn saturating_sub_i8_vec(a: &[i8], b: &[i8]) -> Result<Vec<i8>, LengthMismatch> {
+        Self::check_lengths(a.len(), b.len())?;
+        Ok(a.iter().zip(b).map(|(x, y)| x.saturating_sub(*y)).collect())
+    }
+
+    /// Subtracts two slices of 8-bit unsigned integers (`u8`) element-wise with saturation, matching `PSUBUSB`.
+    ///
+    /// # Arguments
+    /// * `a` - The first operand lanes.
+    /// * `b` - The second operand lanes, subtracted from `a`.
+    ///
+    /// # Returns
+    /// A `Vec<u8>` holding each difference clamped to the range of `u8`, or `LengthMismatch` if the slices differ in length.
+    pub fn saturating_sub_u8_vec(a: &[u8], b: &[u8]) -> Result<Vec<u8>, LengthMismatch> {
+        Self::check_lengths(a.len(), b.len())?;
+        Ok(a.iter().zip(b).map(|(x, y)| x.saturating_sub(*y)).collect())
+    }
+
+    /// Subtracts two slices of 16-bit signed integers (`i16`) element-wise with saturation, matching `PSUBSW`.
+    ///
+    /// # Arguments
+    /// * `a` - The first operand lanes.
+    /// * `b` - The second operand lanes, subtracted from `a`.
+    ///
+    /// # Returns
+    /// A `Vec<i16>` holding each difference clamped to the range of `i16`, or `LengthMismatch` if the slices differ in length.
+    pub fn saturating_sub_i16_vec(a: &[i16], b: &[i16]) -> Result<Vec<i16>, LengthMismatch> {
+        Self::check_lengths(a.len(), b.len())?;
+        Ok(a.iter().zip(b).map(|(x, y)| x.saturating_sub(*y)).collect())
+    }
+
+    /// Subtracts two slices of 16-bit unsigned integers (`u16`) element-wise with saturation, matching `PSUBUSW`.
+    ///
+    /// # Arguments
+    /// * `a` - The first operand lanes.
+    /// * `b` - The second operand lanes, subtracted from `a`.
+    ///
+    /// # Returns
+    /// A `Vec<u16>` holding each difference clamped to the range of `u16`, or `LengthMismatch` if the slices differ in length.
+    pub fn saturating_sub_u16_vec(a: &[u16], b: &[u16]) -> Result<Vec<u16>, LengthMismatch> {
+        Self::check_lengths(a.len(), b.len())?;
+        Ok(a.iter().zip(b).map(|(x, y)| x.saturating_sub(*y)).collect())
+    }
+
+    /// Clamps a signed value to the range of a `bits`-bit signed integer.
+    ///
+    /// # Arguments
+    /// * `value` - The value to clamp.
+    /// * `bits` - The width of the destination, between 1 and 64.
+    ///
+    /// # Returns
+    /// `value` if it fits in `bits` bits as a two's-complement integer, otherwise the nearest bound,
+    /// so `saturate_signed(128, 8)` yields `127` and `saturate_signed(-129, 8)` yields `-128`.
+    ///
+    /// # Panics
+    /// Panics if `bits` is 0 or greater than 64.
+    pub fn saturate_signed(value: i64, bits: usize) -> i64 {
+        if bits == 0 || bits > 64 {
+            panic!("Invalid width {} for signed saturation", bits);
+        }
+        let max = i64::MAX >> (64 - bits);
+        value.clamp(!max, max)
+    }
+
+    /// Clamps a signed value to the range of a `bits`-bit unsigned integer.
+    ///
+    /// # Arguments
+    /// * `value` - The value to clamp.
+    /// * `bits` - The width of the destination, between 1 and 64.
+    ///
+    /// # Returns
+    /// `value` if it fits in `bits` bits as an unsigned integer, `0` if it is negative,
+    /// or the largest `bits`-bit value otherwise.
+    ///
+    /// # Panics
+    /// Panics if `bits` is 0 or greater than 64.
+    pub fn saturate_unsigned(value: i64, bits: usize) -> u64 {
+        if bits == 0 || bits > 64 {
+            panic!("Invalid width {} for unsigned saturation", bits);
+        }
+        (value.max(0) as u64).min(u64::MAX >> (64 - bits))
+    }
+
+    /// Converts a `i16` to a `i8` with signed saturation, as `PACKSSWB` does for each lane.
+    ///
+    /// # Arguments
+    /// * `v` - The value to convert.
+    ///
+    /// # Returns
+    /// `v` clamped to the range of `i8`.
+    pub fn saturate_i16_to_i8(v: i16) -> i8 {
+        v.clamp(i8::MIN as i16, i8::MAX as i16) as i8
+    }
+
+    /// Converts a `i16` to a `u8` with unsigned saturation, as `PACKUSWB` does for each lane.
+    ///
+    /// # Arguments
+    /// * `v` - The value to convert.
+    ///
+    /// # Returns
+    /// `v` clamped to the range of `u8`.
+    pub fn saturate_i16_to_u8(v: i16) -> u8 {
+        v.clamp(u8::MIN as i16, u8::MAX as i16) as u8
+    }
+
+    /// Converts a `i32` to a `i16` with signed saturation, as `PACKSSDW` does for each lane.
+    ///
+    /// # Arguments
+    /// * `v` - The value to convert.
+    ///
+    /// # Returns
+    /// `v` clamped to the range of `i16`.
+    pub fn saturate_i32_to_i16(v: i32) -> i16 {
+        v.clamp(i16::MIN as i32, i16::MAX as i32) as i16
+    }
+
+    /// Converts a `i32` to a `u16` with unsigned saturation, as `PACKUSDW` does for each lane.
+    ///
+    /// # Arguments
+    /// * `v` - The value to convert.
+    ///
+    /// # Returns
+    /// `v` clamped to the range of `u16`.
+    pub fn saturate_i32_to_u16(v: i32) -> u16 {
+        v.clamp(u16::MIN as i32, u16::MAX as i32) as u16
+    }
+
+    /// Converts a slice of `i16` to `i8` lanes with saturation, the per-source step of `PACKSSWB`.
+    ///
+    /// # Arguments
+    /// * `v` - The source lanes.
+    ///
+    /// # Returns
+    /// A `Vec<i8>` holding each lane of `v` clamped to the range of `i8`.
+    pub fn saturate_i16_to_i8_vec(v: &[i16]) -> Vec<i8> {
+        v.iter().map(|x| Self::saturate_i16_to_i8(*x)).collect()
+    }
+
+    /// Converts a slice of `i16` to `u8` lanes with saturation, the per-source step of `PACKUSWB`.
+    ///
+    /// # Arguments
+    /// * `v` - The source lanes.
+    ///
+    /// # Returns
+    /// A `Vec<u8>` holding each lane of `v` clamped to the range of `u8`.
+    pub fn saturate_i16_to_u8_vec(v: &[i16]) -> Vec<u8> {
+        v.iter().map(|x| Self::saturate_i16_to_u8(*x)).collect()
+    }
+
+    /// Converts a slice of `i32` to `i16` lanes with saturation, the per-source step of `PACKSSDW`.
+    ///
+    /// # Arguments
+    /// * `v` - The source lanes.
+    ///
+    /// # Returns
+    /// A `Vec<i16>` holding each lane of `v` clamped to the range of `i16`.
+    pub fn saturate_i32_to_i16_vec(v: &[i32]) -> Vec<i16> {
+        v.iter().map(|x| Self::saturate_i32_to_i16(*x)).collect()
+    }
+
+    /// Converts a slice of `i32` to `u16` lanes with saturation, the per-source step of `PACKUSDW`.
+    ///
+    /// # Arguments
+    /// * `v` - The source lanes.
+    ///
+    /// # Returns
+    /// A `Vec<u16>` holding each lane of `v` clamped to the range of `u16`.
+    pub fn saturate_i32_to_u16_vec(v: &[i32]) -> Vec<u16> {
+        v.iter().map(|x| Self::saturate_i32_to_u16(*x)).collect()
+    }
+
+    /// Converts a `f32` to a `i32` with truncation, as `CVTTSS2SI` does.
+    ///
+    /// Unlike an `as` cast, which saturates, out-of-range inputs, NaN and infinities yield the
+    /// integer indefinite value `i32::MIN` (`0x80000000`).
+    ///
+    /// # Arguments
+    /// * `f` - The value to convert.
+    ///
+    /// # Returns
+    /// `f` rounded toward zero, or `i32::MIN` if the result does not fit in an `i32`.
+    pub fn cvtt_f32_to_i32(f: f32) -> i32 {
+        if !(-2147483648.0..2147483648.0).contains(&f) {
+            return i32::MIN;
+        }
+        f as i32
+    }
+
+    /// Converts a `f64` to a `i64` with truncation, as `CVTTSD2SI` with a 64-bit destination does.
+    ///
+    /// Out-of-range inputs, NaN and infinities yield the integer indefinite value `i64::MIN`
+    /// (`0x8000000000000000`).
+    ///
+    /// # Arguments
+    /// * `f` - The value to convert.
+    ///
+    /// # Returns
+    /// `f` rounded toward zero, or `i64::MIN` if the result does not fit in an `i64`.
+    pub fn cvtt_f64_to_i64(f: f64) -> i64 {
+        if !(-9223372036854775808.0..9223372036854775808.0).contains(&f) {
+            return i64::MIN;
+        }
+        f as i64
+    }
+
+    /// Converts a `f32` to a `i32` under the given rounding mode, as `CVTSS2SI` does.
+    ///
+    /// # Arguments
+    /// * `f` - The value to convert.
+    /// * `mode` - The rounding to apply, standing in for `MXCSR.RC`.
+    ///
+    /// # Returns
+    /// `f` rounded as `mode` specifies, or `i32::MIN` if the result does not fit in an `i32`.
+    pub fn cvt_f32_to_i32_rounded(f: f32, mode: RoundingMode) -> i32 {
+        Self::cvtt_f32_to_i32(match mode {
+            RoundingMode::NearestEven => f.round_ties_even(),
+            RoundingMode::Down => f.floor(),
+            RoundingMode::Up => f.ceil(),
+            RoundingMode::TowardZero => f.trunc(),
+        })
+    }
+
+    /// Converts a `f64` to a `i64` under the given rounding mode, as `CVTSD2SI` with a 64-bit destination does.
+    ///
+    /// # Arguments
+    /// * `f` - The value to convert.
+    /// * `mode` - The rounding to apply, standing in for `MXCSR.RC`.
+    ///
+    /// # Returns
+    /// `f` rounded as `mode` specifies, or `i64::MIN` if the result does not fit in an `i64`.
+    pub fn cvt_f64_to_i64_rounded(f: f64, mode: RoundingMode) -> i64 {
+        Self::cvtt_f64_to_i64(match mode {
+            RoundingMode::NearestEven => f.round_ties_even(),
+            RoundingMode::Down => f.floor(),
+            RoundingMode::Up => f.ceil(),
+            RoundingMode::TowardZero => f.trunc(),
+        })
+    }
+
+    /// Converts a slice of `f32` to `i32` lanes with truncation, as `CVTTPS2DQ` does.
+    ///
+    /// # Arguments
+    /// * `v` - The source lanes.
+    ///
+    /// # Returns
+    /// A `Vec<i32>` holding each lane of `v` converted by `cvtt_f32_to_i32`.
+    pub fn cvtt_f32_to_i32_vec(v: &[f32]) -> Vec<i32> {
+        v.iter().map(|x| Self::cvtt_f32_to_i32(*x)).collect()
+    }
+
+    /// Converts a slice of `f64` to `i64` lanes with truncation, as `VCVTTPD2QQ` does.
+    ///
+    /// # Arguments
+    /// * `v` - The source lanes.
+    ///
+    /// # Returns
+    /// A `Vec<i64>` holding each lane of `v` converted by `cvtt_f64_to_i64`.
+    pub fn cvtt_f64_to_i64_vec(v: &[f64]) -> Vec<i64> {
+        v.iter().map(|x| Self::cvtt_f64_to_i64(*x)).collect()
+    }
+
+    /// Converts a slice of `f32` to `i32` lanes under the given rounding mode, as `CVTPS2DQ` does.
+    ///
+    /// # Arguments
+    /// * `v` - The source lanes.
+    /// * `mode` - The rounding to apply, standing in for `MXCSR.RC`.
+    ///
+    /// # Returns
+    /// A `Vec<i32>` holding each lane of `v` converted by `cvt_f32_to_i32_rounded`.
+    pub fn cvt_f32_to_i32_rounded_vec(v: &[f32], mode: RoundingMode) -> Vec<i32> {
+        v.iter().map(|x| Self::cvt_f32_to_i32_rounded(*x, mode)).collect()
+    }
+
+    /// Converts a slice of `f64` to `i64` lanes under the given rounding mode, as `VCVTPD2QQ` does.
+    ///
+    /// # Arguments
+    /// * `v` - The source lanes.
+    /// * `mode` - The rounding to apply, standing in for `MXCSR.RC`.
+    ///
+    /// # Returns
+    /// A `Vec<i64>` holding each lane of `v` converted by `cvt_f64_to_i64_rounded`.
+    pub fn cvt_f64_to_i64_rounded_vec(v: &[f64], mode: RoundingMode) -> Vec<i64> {
+        v.iter().map(|x| Self::cvt_f64_to_i64_rounded(*x, mode)).collect()
+    }
+
+    /// Widens a `f64` to the x87 80-bit extended-precision format.
+    ///
+    /// The conversion is exact. Subnormal doubles become normal extended values, and NaN payloads
+    /// are kept in the high bits of the significand.
+    ///
+    /// # Arguments
+    /// * `f` - The value to convert.
+    ///
+    /// # Returns
+    /// The 64-bit significand, with its explicit integer bit, and the 16-bit sign and exponent
+    /// word, as they are stored in memory at offsets 0 and 8.
+    pub fn f64_to_f80_bits(f: f64) -> (u64, u16) {
+        let bits = f.to_bits();
+        let sign = ((bits >> 48) as u16) & 0x8000;
+        let exponent = (bits >> 52 & 0x7FF) as u16;
+        let fraction = bits & 0x000F_FFFF_FFFF_FFFF;
+        match exponent {
+            0x7FF => (1 << 63 | fraction << 11, sign | 0x7FFF),
+            0 if fraction == 0 => (0, sign),
+            0 => {
+                let shift = fraction.leading_zeros();
+                (fraction << shift, sign | (F80_BIAS - 1023 + 12 - shift as u16))
+            }
+            _ => (1 << 63 | fraction << 11, sign | (exponent + F80_BIAS - 1023)),
+        }
+    }
+
+    /// Narrows an x87 80-bit extended-precision value to a `f64`, rounding to nearest, ties to even.
+    ///
+    /// Values too large for a `f64` become infinities and values too small become zeros or
+    /// subnormals. NaNs keep the high bits of their payload and stay NaNs.
+    ///
+    /// Encodings the x87 rejects as invalid operands are given a value rather than an error, as a
+    /// documented simplification: unnormals (integer bit clear with a nonzero exponent) are read as
+    /// `significand * 2^(exponent - 16446)`, pseudo-denormals like denormals, and pseudo-infinities
+    /// and pseudo-NaNs (integer bit clear with an all-ones exponent) like their proper counterparts.
+    ///
+    /// # Arguments
+    /// * `mantissa` - The 64-bit significand, with its explicit integer bit.
+    /// * `sign_exponent` - The sign bit and 15-bit biased exponent.
+    ///
+    /// # Returns
+    /// The nearest `f64`.
+    pub fn f80_bits_to_f64(mantissa: u64, sign_exponent: u16) -> f64 {
+        let sign = ((sign_exponent & 0x8000) as u64) << 48;
+        let exponent = (sign_exponent & 0x7FFF) as i32;
+        if exponent == 0x7FFF {
+            let fraction = mantissa << 1 >> 12;
+            if mantissa << 1 == 0 {
+                return f64::from_bits(sign | 0x7FF0_0000_0000_0000);
+            }
+            // a payload held entirely in the low bits would otherwise read back as infinity
+            let fraction = if fraction == 0 { 1 << 51 } else { fraction };
+            return f64::from_bits(sign | 0x7FF0_0000_0000_0000 | fraction);
+        }
+        if mantissa == 0 {
+            return f64::from_bits(sign);
+        }
+        // normalize so that the integer bit is set, then rebias for a double
+        let shift = mantissa.leading_zeros();
+        let biased = exponent.max(1) - shift as i32 - F80_BIAS as i32 + 1023;
+        let mantissa = (mantissa << shift) as u128;
+        let bits = if biased >= 0x7FF {
+            0x7FF0_0000_0000_0000
+        } else if biased >= 1 {
+            ((biased as u64 - 1) << 52) + round_shift_right(mantissa, 11)
+        } else {
+            round_shift_right(mantissa, (12 - biased).min(127) as u32)
+        };
+        f64::from_bits(sign | bits.min(0x7FF0_0000_0000_0000))
+    }
+
+    /// Interleaves the low halves of two slices, matching the `PUNPCKL*` family.
+    ///
+    /// # Type Parameters
+    /// * `T` - The lane type.
+    ///
+    /// # Arguments
+    /// * `a` - The first source, whose lanes land in even positions.
+    /// * `b` - The second source, whose lanes land in odd positions.
+    ///
+    /// # Returns
+    /// A `Vec<T>` of the form `[a[0], b[0], a[1], b[1], ...]` built from the first `a.len() / 2` lanes of `a`
+    /// and the first `b.len() / 2` lanes of `b`.
+    pub fn interleave_low<T: Clone>(a: &[T], b: &[T]) -> Vec<T> {
+        Self::interleave(&a[..a.len() / 2], &b[..b.len() / 2])
+    }
+
+    /// Interleaves the high halves of two slices, matching the `PUNPCKH*` family.
+    ///
+    /// # Type Parameters
+    /// * `T` - The lane type.
+    ///
+    /// # Arguments
+    /// * `a` - The first source, whose lanes land in even positions.
+    /// * `b` - The second source, whose lanes land in odd positions.
+    ///
+    /// # Returns
+    /// A `Vec<T>` of the form `[a[n], b[n], a[n + 1], b[n + 1], ...]` built from the upper halves of `a` and `b`.
+    pub fn interleave_high<T: Clone>(a: &[T], b: &[T]) -> Vec<T> {
+        Self::interleave(&a[a.len() / 2..], &b[b.len() / 2..])
+    }
+
+    /// Splits an interleaved slice into its even and odd lanes.
+    ///
+    /// # Type Parameters
+    /// * `T` - The lane type.
+    ///
+    /// # Arguments
+    /// * `v` - The interleaved lanes.
+    ///
+    /// # Returns
+    /// A tuple of the lanes at even positions and the lanes at odd positions, undoing `interleave_low` and `interleave_high`.
+    pub fn deinterleave<T: Clone>(v: &[T]) -> (Vec<T>, Vec<T>) {
+        let even = v.iter().step_by(2).cloned().collect();
+        let odd = v.iter().skip(1).step_by(2).cloned().collect();
+        (even, odd)
+    }
+
+    /// Interleaves two slices lane by lane, appending the leftover lanes of the longer one.
+    fn interleave<T: Clone>(a: &[T], b: &[T]) -> Vec<T> {
+        let mut result = Vec::with_capacity(a.len() + b.len());
+        for i in 0..a.len().max(b.len()) {
+            if let Some(x) = a.get(i) {
+                result.push(x.clone());
+            }
+            if let Some(y) = b.get(i) {
+                result.push(y.clone());
+            }
+        }
+        result
+    }
+
+    /// Adds adjacent pairs of `u16` lanes horizontally, matching `PHADDW`.
+    ///
+    /// # Arguments
+    /// * `a` - The first source, whose pairwise sums fill the lower half of the result.
+    /// * `b` - The second source, whose pairwise sums fill the upper half of the result.
+    ///
+    /// # Returns
+    /// A `Vec<u16>` of `a.len() / 2 + b.len() / 2` lanes, each being `v[2i] + v[2i + 1]` of its source, wrapping on overflow.
+    ///
+    /// # Panics
+    /// Panics if either input has an odd length.
+    pub fn hadd_u16_vec(a: &[u16], b: &[u16]) -> Vec<u16> {
+        if !a.len().is_multiple_of(2) || !b.len().is_multiple_of(2) {
+            panic!("Horizontal operation requires even-length inputs");
+        }
+        a.chunks(2).chain(b.chunks(2)).map(|pair| {
+            let (x, y) = (&pair[0], &pair[1]);
+            x.wrapping_add(*y)
+        }).collect()
+    }
+
+    /// Adds adjacent pairs of `u32` lanes horizontally, matching `PHADDD`.
+    ///
+    /// # Arguments
+    /// * `a` - The first source, whose pairwise sums fill the lower half of the result.
+    /// * `b` - The second source, whose pairwise sums fill the upper half of the result.
+    ///
+    /// # Returns
+    /// A `Vec<u32>` of `a.len() / 2 + b.len() / 2` lanes, each being `v[2i] + v[2i + 1]` of its source, wrapping on overflow.
+    ///
+    /// # Panics
+    /// Panics if either input has an odd length.
+    pub fn hadd_u32_vec(a: &[u32], b: &[u32]) -> Vec<u32> {
+        if !a.len().is_multiple_of(2) || !b.len().is_multiple_of(2) {
+            panic!("Horizontal operation requires even-length inputs");
+        }
+        a.chunks(2).chain(b.chunks(2)).map(|pair| {
+            let (x, y) = (&pair[0], &pair[1]);
+            x.wrapping_add(*y)
+        }).collect()
+    }
+
+    /// Adds adjacent pairs of `f32` lanes horizontally, matching `HADDPS`.
+    ///
+    /// # Arguments
+    /// * `a` - The first source, whose pairwise sums fill the lower half of the result.
+    /// * `b` - The second source, whose pairwise sums fill the upper half of the result.
+    ///
+    /// # Returns
+    /// A `Vec<f32>` of `a.len() / 2 + b.len() / 2` lanes, each being `v[2i] + v[2i + 1]` of its source.
+    ///
+    /// # Panics
+    /// Panics if either input has an odd length.
+    pub fn hadd_f32_vec(a: &[f32], b: &[f32]) -> Vec<f32> {
+        if !a.len().is_multiple_of(2) || !b.len().is_multiple_of(2) {
+            panic!("Horizontal operation requires even-length inputs");
+        }
+        a.chunks(2).chain(b.chunks(2)).map(|pair| {
+            let (x, y) = (&pair[0], &pair[1]);
+            x + y
+        }).collect()
+    }
+
+    /// Subtracts adjacent pairs of `u16` lanes horizontally, matching `PHSUBW`.
+    ///
+    /// # Arguments
+    /// * `a` - The first source, whose pairwise differences fill the lower half of the result.
+    /// * `b` - The second source, whose pairwise differences fill the upper half of the result.
+    ///
+    /// # Returns
+    /// A `Vec<u16>` of `a.len() / 2 + b.len() / 2` lanes, each being `v[2i] - v[2i + 1]` of its source, wrapping on overflow.
+    ///
+    /// # Panics
+    /// Panics if either input has an odd length.
+    pub fn hsub_u16_vec(a: &[u16], b: &[u16]) -> Vec<u16> {
+        if !a.len().is_multiple_of(2) || !b.len().is_multiple_of(2) {
+            panic!("Horizontal operation requires even-length inputs");
+        }
+        a.chunks(2).chain(b.chunks(2)).map(|pair| {
+            let (x, y) = (&pair[0], &pair[1]);
+            x.wrapping_sub(*y)
+        }).collect()
+    }
+
+    /// Subtracts adjacent pairs of `u32` lanes horizontally, matching `PHSUBD`.
+    ///
+    /// # Arguments
+    /// * `a` - The first source, whose pairwise differences fill the lower half of the result.
+    /// * `b` - The second source, whose pairwise differences fill the upper half of the result.
+    ///
+    /// # Returns
+    /// A `Vec<u32>` of `a.len() / 2 + b.len() / 2` lanes, each being `v[2i] - v[2i + 1]` of its source, wrapping on overflow.
+    ///
+    /// # Panics
+    /// Panics if either input has an odd length.
+    pub fn hsub_u32_vec(a: &[u32], b: &[u32]) -> Vec<u32> {
+        if !a.len().is_multiple_of(2) || !b.len().is_multiple_of(2) {
+            panic!("Horizontal operation requires even-length inputs");
+        }
+        a.chunks(2).chain(b.chunks(2)).map(|pair| {
+            let (x, y) = (&pair[0], &pair[1]);
+            x.wrapping_sub(*y)
+        }).collect()
+    }
+
+    /// Subtracts adjacent pairs of `f32` lanes horizontally, matching `HSUBPS`.
+    ///
+    /// # Arguments
+    /// * `a` - The first source, whose pairwise differences fill the lower half of the result.
+    /// * `b` - The second source, whose pairwise differences fill the upper half of the result.
+    ///
+    /// # Returns
+    /// A `Vec<f32>` of `a.len() / 2 + b.len() / 2` lanes, each being `v[2i] - v[2i + 1]` of its source.
+    ///
+    /// # Panics
+    /// Panics if either input has an odd length.
+    pub fn hsub_f32_vec(a: &[f32], b: &[f32]) -> Vec<f32> {
+        if !a.len().is_multiple_of(2) || !b.len().is_multiple_of(2) {
+            panic!("Horizontal operation requires even-length inputs");
+        }
+        a.chunks(2).chain(b.chunks(2)).map(|pair| {
+            let (x, y) = (&pair[0], &pair[1]);
+            x - y
+        }).collect()
+    }
+
+    /// Gathers lanes of a slice by index, matching the `PSHUFB`/`VPERMD` family.
+    ///
+    /// # Type Parameters
+    /// * `T` - The lane type.
+    ///
+    /// # Arguments
+    /// * `src` - The source lanes.
+    /// * `indices` - The source index of each destination lane.
+    ///
+    /// # Returns
+    /// A `Vec<T>` of `indices.len()` lanes where lane `i` is `src[indices[i]]`,
+    /// or `T::default()` (zero for numeric types) when the index is out of range.
+    pub fn shuffle<T: Clone + Default>(src: &[T], indices: &[usize]) -> Vec<T> {
+        indices.iter().map(|&i| src.get(i).cloned().unwrap_or_default()).collect()
+    }
+
+    /// Permutes quadword lanes by an immediate, matching `VPERMQ` with an `imm8` operand.
+    ///
+    /// Each group of four lanes (one 256-bit half) is permuted independently: destination lane `i`
+    /// of a group takes source lane `(imm8 >> (2 * i)) & 3` of the same group.
+    ///
+    /// # Type Parameters
+    /// * `T` - The lane type.
+    ///
+    /// # Arguments
+    /// * `src` - The source lanes.
+    /// * `imm8` - The immediate selecting the source lane of each destination lane.
+    ///
+    /// # Returns
+    /// A `Vec<T>` with the same length as `src`.
+    ///
+    /// # Panics
+    /// Panics if the length of `src` is not a multiple of 4.
+    pub fn permute64<T: Clone>(src: &[T], imm8: u8) -> Vec<T> {
+        if !src.len().is_multiple_of(4) {
+            panic!("Invalid lane count {} for a quadword permutation", src.len());
+        }
+        src.chunks(4)
+            .flat_map(|group| (0..4).map(move |i| group[((imm8 >> (2 * i)) & 3) as usize].clone()))
+            .collect()
+    }
+
+    /// Compares two slices of `u8` lanes for equality, matching `PCMPEQB`.
+    ///
+    /// # Arguments
+    /// * `a` - The first operand lanes.
+    /// * `b` - The second operand lanes.
+    ///
+    /// # Returns
+    /// A `Vec<u8>` mask holding `0xFF` where the lanes are equal and `0` elsewhere,
+    /// or `LengthMismatch` if the slices differ in length.
+    pub fn compare_eq_u8_vec(a: &[u8], b: &[u8]) -> Result<Vec<u8>, LengthMismatch> {
+        Self::check_lengths(a.len(), b.len())?;
+        Ok(a.iter().zip(b).map(|(x, y)| if x == y { u8::MAX } else { 0 }).collect())
+    }
+
+    /// Compares two slices of `u16` lanes for equality, matching `PCMPEQW`.
+    ///
+    /// # Arguments
+    /// * `a` - The first operand lanes.
+    /// * `b` - The second operand lanes.
+    ///
+    /// # Returns
+    /// A `Vec<u16>` mask holding `0xFFFF` where the lanes are equal and `0` elsewhere,
+    /// or `LengthMismatch` if the slices differ in length.
+    pub fn compare_eq_u16_vec(a: &[u16], b: &[u16]) -> Result<Vec<u16>, LengthMismatch> {
+        Self::check_lengths(a.len(), b.len())?;
+        Ok(a.iter().zip(b).map(|(x, y)| if x == y { u16::MAX } else { 0 }).collect())
+    }
+
+    /// Compares two slices of `u32` lanes for equality, matching `PCMPEQD`.
+    ///
+    /// # Arguments
+    /// * `a` - The first operand lanes.
+    /// * `b` - The second operand lanes.
+    ///
+    /// # Returns
+    /// A `Vec<u32>` mask holding `0xFFFF_FFFF` where the lanes are equal and `0` elsewhere,
+    /// or `LengthMismatch` if the slices differ in length.
+    pub fn compare_eq_u32_vec(a: &[u32], b: &[u32]) -> Result<Vec<u32>, LengthMismatch> {
+        Self::check_lengths(a.len(), b.len())?;
+        Ok(a.iter().zip(b).map(|(x, y)| if x == y { u32::MAX } else { 0 }).collect())
+    }
+
+    /// Compares two slices of `i32` lanes for signed greater-than, matching `PCMPGTD`.
+    ///
+    /// # Arguments
+    /// * `a` - The first operand lanes.
+    /// * `b` - The second operand lanes.
+    ///
+    /// # Returns
+    /// A `Vec<u32>` mask holding `0xFFFF_FFFF` where the lanes of `a` is greater than the lane of `b` and `0` elsewhere,
+    /// or `LengthMismatch` if the slices differ in length.
+    pub fn compare_gt_i32_vec(a: &[i32], b: &[i32]) -> Result<Vec<u32>, LengthMismatch> {
+        Self::check_lengths(a.len(), b.len())?;
+        Ok(a.iter().zip(b).map(|(x, y)| if x > y { u32::MAX } else { 0 }).collect())
+    }
+
+    /// Computes the element-wise minimum of two slices of `u8` lanes, matching `PMINUB`.
+    ///
+    /// # Arguments
+    /// * `a` - The first operand lanes.
+    /// * `b` - The second operand lanes.
+    ///
+    /// # Returns
+    /// A `Vec<u8>` holding the minimum of each lane pair, or `LengthMismatch` if the slices differ in length.
+    pub fn min_u8_vec(a: &[u8], b: &[u8]) -> Result<Vec<u8>, LengthMismatch> {
+        Self::check_lengths(a.len(), b.len())?;
+        Ok(a.iter().zip(b).map(|(x, y)| *x.min(y)).collect())
+    }
+
+    /// Computes the element-wise minimum of two slices of `i8` lanes, matching `PMINSB`.
+    ///
+    /// # Arguments
+    /// * `a` - The first operand lanes.
+    /// * `b` - The second operand lanes.
+    ///
+    /// # Returns
+    /// A `Vec<i8>` holding the minimum of each lane pair, or `LengthMismatch` if the slices differ in length.
+    pub fn min_i8_vec(a: &[i8], b: &[i8]) -> Result<Vec<i8>, LengthMismatch> {
+        Self::check_lengths(a.len(), b.len())?;
+        Ok(a.iter().zip(b).map(|(x, y)| *x.min(y)).collect())
+    }
+
+    /// Computes the element-wise minimum of two slices of `u16` lanes, matching `PMINUW`.
+    ///
+    /// # Arguments
+    /// * `a` - The first operand lanes.
+    /// * `b` - The second operand lanes.
+    ///
+    /// # Returns
+    /// A `Vec<u16>` holding the minimum of each lane pair, or `LengthMismatch` if the slices differ in length.
+    pub fn min_u16_vec(a: &[u16], b: &[u16]) -> Result<Vec<u16>, LengthMismatch> {
+        Self::check_lengths(a.len(), b.len())?;
+        Ok(a.iter().zip(b).map(|(x, y)| *x.min(y)).collect())
+    }
+
+    /// Computes the element-wise minimum of two slices of `i16` lanes, matching `PMINSW`.
+    ///
+    /// # Arguments
+    /// * `a` - The first operand lanes.
+    /// * `b` - The second operand lanes.
+    ///
+    /// # Returns
+    /// A `Vec<i16>` holding the minimum of each lane pair, or `LengthMismatch` if the slices differ in length.
+    pub fn min_i16_vec(a: &[i16], b: &[i16]) -> Result<Vec<i16>, LengthMismatch> {
+        Self::check_lengths(a.len(), b.len())?;
+        Ok(a.iter().zip(b).map(|(x, y)| *x.min(y)).collect())
+    }
+
+    /// Computes the element-wise minimum of two slices of `u32` lanes, matching `PMINUD`.
+    ///
+    /// # Arguments
+    /// * `a` - The first operand lanes.
+    /// * `b` - The second operand lanes.
+    ///
+    /// # Returns
+    /// A `Vec<u32>` holding the minimum of each lane pair, or `LengthMismatch` if the slices differ in length.
+    pub fn min_u32_vec(a: &[u32], b: &[u32]) -> Result<Vec<u32>, LengthMismatch> {
+        Self::check_lengths(a.len(), b.len())?;
+        Ok(a.iter().zip(b).map(|(x, y)| *x.min(y)).collect())
+    }
+
+    /// Computes the element-wise minimum of two slices of `i32` lanes, matching `PMINSD`.
+    ///
+    /// # Arguments
+    /// * `a` - The first operand lanes.
+    /// * `b` - The second operand lanes.
+    ///
+    /// # Returns
+    /// A `Vec<i32>` holding the minimum of each lane pair, or `LengthMismatch` if the slices differ in length.
+    pub fn min_i32_vec(a: &[i32], b: &[i32]) -> Result<Vec<i32>, LengthMismatch> {
+        Self::check_lengths(a.len(), b.len())?;
+        Ok(a.iter().zip(b).map(|(x, y)| *x.min(y)).collect())
+    }
+
+    /// Computes the element-wise maximum of two slices of `u8` lanes, matching `PMAXUB`.
+    ///
+    /// # Arguments
+    /// * `a` - The first operand lanes.
+    /// * `b` - The second operand lanes.
+    ///
+    /// # Returns
+    /// A `Vec<u8>` holding the maximum of each lane pair, or `LengthMismatch` if the slices differ in length.
+    pub fn max_u8_vec(a: &[u8], b: &[u8]) -> Result<Vec<u8>, LengthMismatch> {
+        Self::check_lengths(a.len(), b.len())?;
+        Ok(a.iter().zip(b).map(|(x, y)| *x.max(y)).collect())
+    }
+
+    /// Computes the element-wise maximum of two slices of `i8` lanes, matching `PMAXSB`.
+    ///
+    /// # Arguments
+    /// * `a` - The first operand lanes.
+    /// * `b` - The second operand lanes.
+    ///
+    /// # Returns
+    /// A `Vec<i8>` holding the maximum of each lane pair, or `LengthMismatch` if the slices differ in length.
+    pub fn max_i8_vec(a: &[i8], b: &[i8]) -> Result<Vec<i8>, LengthMismatch> {
+        Self::check_lengths(a.len(), b.len())?;
+        Ok(a.iter().zip(b).map(|(x, y)| *x.max(y)).collect())
+    }
+
+    /// Computes the element-wise maximum of two slices of `u16` lanes, matching `PMAXUW`.
+    ///
+    /// # Arguments
+    /// * `a` - The first operand lanes.
+    /// * `b` - The second operand lanes.
+    ///
+    /// # Returns
+    /// A `Vec<u16>` holding the maximum of each lane pair, or `LengthMismatch` if the slices differ in length.
+    pub fn max_u16_vec(a: &[u16], b: &[u16]) -> Result<Vec<u16>, LengthMismatch> {
+        Self::check_lengths(a.len(), b.len())?;
+        Ok(a.iter().zip(b).map(|(x, y)| *x.max(y)).collect())
+    }
+
+    /// Computes the element-wise maximum of two slices of `i16` lanes, matching `PMAXSW`.
+    ///
+    /// # Arguments
+    /// * `a` - The first operand lanes.
+    /// * `b` - The second operand lanes.
+    ///
+    /// # Returns
+    /// A `Vec<i16>` holding the maximum of each lane pair, or `LengthMismatch` if the slices differ in length.
+    pub fn max_i16_vec(a: &[i16], b: &[i16]) -> Result<Vec<i16>, LengthMismatch> {
+        Self::check_lengths(a.len(), b.len())?;
+        Ok(a.iter().zip(b).map(|(x, y)| *x.max(y)).collect())
+    }
+
+    /// Computes the element-wise maximum of two slices of `u32` lanes, matching `PMAXUD`.
+    ///
+    /// # Arguments
+    /// * `a` - The first operand lanes.
+    /// * `b` - The second operand lanes.
+    ///
+    /// # Returns
+    /// A `Vec<u32>` holding the maximum of each lane pair, or `LengthMismatch` if the slices differ in length.
+    pub fn max_u32_vec(a: &[u32], b: &[u32]) -> Result<Vec<u32>, LengthMismatch> {
+        Self::check_lengths(a.len(), b.len())?;
+        Ok(a.iter().zip(b).map(|(x, y)| *x.max(y)).collect())
+    }
+
+    /// Computes the element-wise maximum of two slices of `i32` lanes, matching `PMAXSD`.
+    ///
+    /// # Arguments
+    /// * `a` - The first operand lanes.
+    /// * `b` - The second operand lanes.
+    ///
+    /// # Returns
+    /// A `Vec<i32>` holding the maximum of each lane pair, or `LengthMismatch` if the slices differ in length.
+    pub fn max_i32_vec(a: &[i32], b: &[i32]) -> Result<Vec<i32>, LengthMismatch> {
+        Self::check_lengths(a.len(), b.len())?;
+        Ok(a.iter().zip(b).map(|(x, y)| *x.max(y)).collect())
+    }
+
+    /// Applies denormals-are-zero to a `f32` operand.
+    ///
+    /// # Arguments
+    /// * `v` - The operand.
+    /// * `daz` - Whether DAZ is in effect.
+    ///
+    /// # Returns
+    /// A zero of the same sign if `daz` is set and `v` is subnormal, otherwise `v`.
+    pub fn apply_daz_f32(v: f32, daz: bool) -> f32 {
+        if daz && v.is_subnormal() { 0.0f32.copysign(v) } else { v }
+    }
+
+    /// Applies flush-to-zero to a `f32` result.
+    ///
+    /// # Arguments
+    /// * `v` - The result.
+    /// * `ftz` - Whether FTZ is in effect.
+    ///
+    /// # Returns
+    /// A zero of the same sign if `ftz` is set and `v` is subnormal, otherwise `v`.
+    pub fn apply_ftz_f32(v: f32, ftz: bool) -> f32 {
+        if ftz && v.is_subnormal() { 0.0f32.copysign(v) } else { v }
+    }
+
+    /// Prepares a `f32` source operand the way an SSE instruction would under the given MXCSR.
+    ///
+    /// Subnormal operands become zeros of the same sign when MXCSR.DAZ (bit 6) is set.
+    ///
+    /// # Arguments
+    /// * `v` - The operand.
+    /// * `mxcsr` - The current MXCSR value.
+    ///
+    /// # Returns
+    /// The operand the instruction actually computes with.
+    pub fn canonicalize_operand_f32(v: f32, mxcsr: u32) -> f32 {
+        Self::apply_daz_f32(v, mxcsr & MXCSR_DAZ != 0)
+    }
+
+    /// Adjusts a `f32` result the way an SSE instruction would under the given MXCSR.
+    ///
+    /// Subnormal results become zeros of the same sign when MXCSR.FTZ (bit 15) is set and the
+    /// underflow exception is masked (MXCSR.UM, bit 11), as the hardware requires both. The check
+    /// is made on the rounded result, while the hardware checks before rounding, so results that
+    /// round up to the smallest normal value are kept.
+    ///
+    /// # Arguments
+    /// * `v` - The result.
+    /// * `mxcsr` - The current MXCSR value.
+    ///
+    /// # Returns
+    /// The result the instruction actually writes.
+    pub fn canonicalize_result_f32(v: f32, mxcsr: u32) -> f32 {
+        Self::apply_ftz_f32(v, mxcsr & (MXCSR_FTZ | MXCSR_UM) == MXCSR_FTZ | MXCSR_UM)
+    }
+
+    /// Applies denormals-are-zero to a `f64` operand.
+    ///
+    /// # Arguments
+    /// * `v` - The operand.
+    /// * `daz` - Whether DAZ is in effect.
+    ///
+    /// # Returns
+    /// A zero of the same sign if `daz` is set and `v` is subnormal, otherwise `v`.
+    pub fn apply_daz_f64(v: f64, daz: bool) -> f64 {
+        if daz && v.is_subnormal() { 0.0f64.copysign(v) } else { v }
+    }
+
+    /// Applies flush-to-zero to a `f64` result.
+    ///
+    /// # Arguments
+    /// * `v` - The result.
+    /// * `ftz` - Whether FTZ is in effect.
+    ///
+    /// # Returns
+    /// A zero of the same sign if `ftz` is set and `v` is subnormal, otherwise `v`.
+    pub fn apply_ftz_f64(v: f64, ftz: bool) -> f64 {
+        if ftz && v.is_subnormal() { 0.0f64.copysign(v) } else { v }
+    }
+
+    /// Prepares a `f64` source operand the way an SSE instruction would under the given MXCSR.
+    ///
+    /// Subnormal operands become zeros of the same sign when MXCSR.DAZ (bit 6) is set.
+    ///
+    /// # Arguments
+    /// * `v` - The operand.
+    /// * `mxcsr` - The current MXCSR value.
+    ///
+    /// # Returns
+    /// The operand the instruction actually computes with.
+    pub fn canonicalize_operand_f64(v: f64, mxcsr: u32) -> f64 {
+        Self::apply_daz_f64(v, mxcsr & MXCSR_DAZ != 0)
+    }
+
+    /// Adjusts a `f64` result the way an SSE instruction would under the given MXCSR.
+    ///
+    /// Subnormal results become zeros of the same sign when MXCSR.FTZ (bit 15) is set and the
+    /// underflow exception is masked (MXCSR.UM, bit 11), as the hardware requires both. The check
+    /// is made on the rounded result, while the hardware checks before rounding, so results that
+    /// round up to the smallest normal value are kept.
+    ///
+    /// # Arguments
+    /// * `v` - The result.
+    /// * `mxcsr` - The current MXCSR value.
+    ///
+    /// # Returns
+    /// The result the instruction actually writes.
+    pub fn canonicalize_result_f64(v: f64, mxcsr: u32) -> f64 {
+        Self::apply_ftz_f64(v, mxcsr & (MXCSR_FTZ | MXCSR_UM) == MXCSR_FTZ | MXCSR_UM)
+    }
+
+    /// Returns the minimum of two `f32` values under the `MINPS` rule.
+    ///
+    /// The instruction returns `a` only if it is strictly smaller than `b` and returns `b` otherwise,
+    /// so a NaN in either operand, or zeros of either sign, yield `b`. This differs from
+    /// `f32::min`, which ignores NaNs.
+    ///
+    /// # Arguments
+    /// * `a` - The first source operand.
+    /// * `b` - The second source operand.
+    ///
+    /// # Returns
+    /// `a` if `a < b`, otherwise `b`.
+    pub fn minps_scalar(a: f32, b: f32) -> f32 {
+        if a < b { a } else { b }
+    }
+
+    /// Returns the maximum of two `f32` values under the `MAXPS` rule.
+    ///
+    /// The instruction returns `a` only if it is strictly greater than `b` and returns `b` otherwise,
+    /// so a NaN in either operand, or zeros of either sign, yield `b`. This differs from
+    /// `f32::max`, which ignores NaNs.
+    ///
+    /// # Arguments
+    /// * `a` - The first source operand.
+    /// * `b` - The second source operand.
+    ///
+    /// # Returns
+    /// `a` if `a > b`, otherwise `b`.
+    pub fn maxps_scalar(a: f32, b: f32) -> f32 {
+        if a > b { a } else { b }
+    }
+
+    /// Returns the minimum of two `f64` values under the `MINPD` rule.
+    ///
+    /// The instruction returns `a` only if it is strictly smaller than `b` and returns `b` otherwise,
+    /// so a NaN in either operand, or zeros of either sign, yield `b`. This differs from
+    /// `f64::min`, which ignores NaNs.
+    ///
+    /// # Arguments
+    /// * `a` - The first source operand.
+    /// * `b` - The second source operand.
+    ///
+    /// # Returns
+    /// `a` if `a < b`, otherwise `b`.
+    pub fn minpd_scalar(a: f64, b: f64) -> f64 {
+        if a < b { a } else { b }
+    }
+
+    /// Returns the maximum of two `f64` values under the `MAXPD` rule.
+    ///
+    /// The instruction returns `a` only if it is strictly greater than `b` and returns `b` otherwise,
+    /// so a NaN in either operand, or zeros of either sign, yield `b`. This differs from
+    /// `f64::max`, which ignores NaNs.
+    ///
+    /// # Arguments
+    /// * `a` - The first source operand.
+    /// * `b` - The second source operand.
+    ///
+    /// # Returns
+    /// `a` if `a > b`, otherwise `b`.
+    pub fn maxpd_scalar(a: f64, b: f64) -> f64 {
+        if a > b { a } else { b }
+    }
+
+    /// Computes the element-wise minimum of two slices of `f32` lanes, matching `MINPS`.
+    ///
+    /// # Arguments
+    /// * `a` - The first source operand lanes.
+    /// * `b` - The second source operand lanes.
+    ///
+    /// # Returns
+    /// A `Vec<f32>` holding `minps_scalar` of each lane pair, or `LengthMismatch` if the slices differ in length.
+    pub fn minps_vec(a: &[f32], b: &[f32]) -> Result<Vec<f32>, LengthMismatch> {
+        Self::check_lengths(a.len(), b.len())?;
+        Ok(a.iter().zip(b).map(|(x, y)| Self::minps_scalar(*x, *y)).collect())
+    }
+
+    /// Computes the element-wise maximum of two slices of `f32` lanes, matching `MAXPS`.
+    ///
+    /// # Arguments
+    /// * `a` - The first source operand lanes.
+    /// * `b` - The second source operand lanes.
+    ///
+    /// # Returns
+    /// A `Vec<f32>` holding `maxps_scalar` of each lane pair, or `LengthMismatch` if the slices differ in length.
+    pub fn maxps_vec(a: &[f32], b: &[f32]) -> Result<Vec<f32>, LengthMismatch> {
+        Self::check_lengths(a.len(), b.len())?;
+        Ok(a.iter().zip(b).map(|(x, y)| Self::maxps_scalar(*x, *y)).collect())
+    }
+
+    /// Computes the element-wise minimum of two slices of `f64` lanes, matching `MINPD`.
+    ///
+    /// # Arguments
+    /// * `a` - The first source operand lanes.
+    /// * `b` - The second source operand lanes.
+    ///
+    /// # Returns
+    /// A `Vec<f64>` holding `minpd_scalar` of each lane pair, or `LengthMismatch` if the slices differ in length.
+    pub fn minpd_vec(a: &[f64], b: &[f64]) -> Result<Vec<f64>, LengthMismatch> {
+        Self::check_lengths(a.len(), b.len())?;
+        Ok(a.iter().zip(b).map(|(x, y)| Self::minpd_scalar(*x, *y)).collect())
+    }
+
+    /// Computes the element-wise maximum of two slices of `f64` lanes, matching `MAXPD`.
+    ///
+    /// # Arguments
+    /// * `a` - The first source operand lanes.
+    /// * `b` - The second source operand lanes.
+    ///
+    /// # Returns
+    /// A `Vec<f64>` holding `maxpd_scalar` of each lane pair, or `LengthMismatch` if the slices differ in length.
+    pub fn maxpd_vec(a: &[f64], b: &[f64]) -> Result<Vec<f64>, LengthMismatch> {
+        Self::check_lengths(a.len(), b.len())?;
+        Ok(a.iter().zip(b).map(|(x, y)| Self::maxpd_scalar(*x, *y)).collect())
+    }
+
+    /// Packs the most significant bit of each byte lane into a mask, matching `PMOVMSKB`.
+    ///
+    /// # Arguments
+    /// * `lanes` - The byte lanes; lanes past the 64th are ignored.
+    ///
+    /// # Returns
+    /// A mask whose bit `i` is bit 7 of `lanes[i]`.
+    pub fn movemask_u8(lanes: &[u8]) -> u64 {
+        lanes.iter().take(64).enumerate().fold(0, |mask, (i, lane)| mask | ((lane >> 7) as u64) << i)
+    }
+
+    /// Packs the sign bit of each `f32` lane into a mask, matching `MOVMSKPS`.
+    ///
+    /// The sign bit is taken as is, so negative zeros and NaNs with the sign bit set count as negative.
+    ///
+    /// # Arguments
+    /// * `lanes` - The `f32` lanes; lanes past the 32nd are ignored.
+    ///
+    /// # Returns
+    /// A mask whose bit `i` is the sign bit of `lanes[i]`.
+    pub fn movemask_f32(lanes: &[f32]) -> u32 {
+        lanes.iter().take(32).enumerate().fold(0, |mask, (i, lane)| mask | (lane.to_bits() >> 31) << i)
+    }
+
+    /// Packs the sign bit of each `f64` lane into a mask, matching `MOVMSKPD`.
+    ///
+    /// The sign bit is taken as is, so negative zeros and NaNs with the sign bit set count as negative.
+    ///
+    /// # Arguments
+    /// * `lanes` - The `f64` lanes; lanes past the 32nd are ignored.
+    ///
+    /// # Returns
+    /// A mask whose bit `i` is the sign bit of `lanes[i]`.
+    pub fn movemask_f64(lanes: &[f64]) -> u32 {
+        lanes.iter().take(32).enumerate().fold(0, |mask, (i, lane)| mask | ((lane.to_bits() >> 63) as u32) << i)
+    }
+
+    /// Gathers the selected lanes of a slice into a contiguous vector, matching `VCOMPRESSD`.
+    ///
+    /// # Type Parameters
+    /// * `T` - The lane type.
+    ///
+    /// # Arguments
+    /// * `src` - The source lanes.
+    /// * `mask` - Selects the lanes to keep; lanes beyond the shorter of the two slices are ignored.
+    ///
+    /// # Returns
+    /// The lanes of `src` whose `mask` entry is `true`, in their original order.
+    pub fn compress_vec<T: Clone + Default>(src: &[T], mask: &[bool]) -> Vec<T> {
+        src.iter().zip(mask).filter(|(_, selected)| **selected).map(|(lane, _)| lane.clone()).collect()
+    }
+
+    /// Scatters consecutive lanes of a slice to the selected positions, matching `VEXPANDD` with zero masking.
+    ///
+    /// # Type Parameters
+    /// * `T` - The lane type.
+    ///
+    /// # Arguments
+    /// * `src` - The source lanes, consumed from the start.
+    /// * `mask` - Selects the destination positions; its length decides the length of the result.
+    ///
+    /// # Returns
+    /// A `Vec<T>` of `mask.len()` lanes holding the next lane of `src` at each position whose `mask`
+    /// entry is `true` and `T::default()` elsewhere, including selected positions left once `src` runs out.
+    pub fn expand_vec<T: Clone + Default>(src: &[T], mask: &[bool]) -> Vec<T> {
+        let mut lanes = src.iter();
+        mask.iter()
+            .map(|selected| if *selected { lanes.next().cloned().unwrap_or_default() } else { T::default() })
+            .collect()
+    }
+
+    /// Checks that two operand slices have the same number of lanes.
+    fn check_lengths(left: usize, right: usize) -> Result<(), LengthMismatch> {
+        if left != right {
+            return Err(LengthMismatch { left, right });
+        }
+        Ok(())
+    }
+
+    /// Computes the element-wise sum of two slices of `f32` lanes, matching `ADDPS`.
+    ///
+    /// Results follow IEEE 754, including NaN propagation, infinities and signed zeros.
+    ///
+    /// # Arguments
+    /// * `a` - The first operand lanes.
+    /// * `b` - The second operand lanes.
+    ///
+    /// # Returns
+    /// A `Vec<f32>` holding `a[i] + b[i]` for each lane.
+    ///
+    /// # Panics
+    /// Panics if the slices differ in length.
+    pub fn f32vec_add(a: &[f32], b: &[f32]) -> Vec<f32> {
+        if let Err(e) = Self::check_lengths(a.len(), b.len()) {
+            panic!("{}", e);
+        }
+        a.iter().zip(b).map(|(x, y)| x + y).collect()
+    }
+
+    /// Computes the element-wise difference of two slices of `f32` lanes, matching `SUBPS`.
+    ///
+    /// Results follow IEEE 754, including NaN propagation, infinities and signed zeros.
+    ///
+    /// # Arguments
+    /// * `a` - The first operand lanes.
+    /// * `b` - The second operand lanes.
+    ///
+    /// # Returns
+    /// A `Vec<f32>` holding `a[i] - b[i]` for each lane.
+    ///
+    /// # Panics
+    /// Panics if the slices differ in length.
+    pub fn f32vec_sub(a: &[f32], b: &[f32]) -> Vec<f32> {
+        if let Err(e) = Self::check_lengths(a.len(), b.len()) {
+            panic!("{}", e);
+        }
+        a.iter().zip(b).map(|(x, y)| x - y).collect()
+    }
+
+    /// Computes the element-wise product of two slices of `f32` lanes, matching `MULPS`.
+    ///
+    /// Results follow IEEE 754, including NaN propagation, infinities and signed zeros.
+    ///
+    /// # Arguments
+    /// * `a` - The first operand lanes.
+    /// * `b` - The second operand lanes.
+    ///
+    /// # Returns
+    /// A `Vec<f32>` holding `a[i] * b[i]` for each lane.
+    ///
+    /// # Panics
+    /// Panics if the slices differ in length.
+    pub fn f32vec_mul(a: &[f32], b: &[f32]) -> Vec<f32> {
+        if let Err(e) = Self::check_lengths(a.len(), b.len()) {
+            panic!("{}", e);
+        }
+        a.iter().zip(b).map(|(x, y)| x * y).collect()
+    }
+
+    /// Computes the element-wise quotient of two slices of `f32` lanes, matching `DIVPS`.
+    ///
+    /// Results follow IEEE 754, including NaN propagation, infinities and signed zeros.
+    ///
+    /// # Arguments
+    /// * `a` - The first operand lanes.
+    /// * `b` - The second operand lanes.
+    ///
+    /// # Returns
+    /// A `Vec<f32>` holding `a[i] / b[i]` for each lane.
+    ///
+    /// # Panics
+    /// Panics if the slices differ in length.
+    pub fn f32vec_div(a: &[f32], b: &[f32]) -> Vec<f32> {
+        if let Err(e) = Self::check_lengths(a.len(), b.len()) {
+            panic!("{}", e);
+        }
+        a.iter().zip(b).map(|(x, y)| x / y).collect()
+    }
+
+    /// Computes the element-wise square root of a slice of `f32` lanes, matching `SQRTPS`.
+    ///
+    /// Negative inputs other than `-0.0` produce NaN, as required by IEEE 754.
+    ///
+    /// # Arguments
+    /// * `a` - The operand lanes.
+    ///
+    /// # Returns
+    /// A `Vec<f32>` holding the square root of each lane.
+    pub fn f32vec_sqrt(a: &[f32]) -> Vec<f32> {
+        a.iter().map(|x| x.sqrt()).collect()
+    }
+
+    /// Computes the element-wise sum of two slices of `f64` lanes, matching `ADDPD`.
+    ///
+    /// Results follow IEEE 754, including NaN propagation, infinities and signed zeros.
+    ///
+    /// # Arguments
+    /// * `a` - The first operand lanes.
+    /// * `b` - The second operand lanes.
+    ///
+    /// # Returns
+    /// A `Vec<f64>` holding `a[i] + b[i]` for each lane.
+    ///
+    /// # Panics
+    /// Panics if the slices differ in length.
+    pub fn f64vec_add(a: &[f64], b: &[f64]) -> Vec<f64> {
+        if let Err(e) = Self::check_lengths(a.len(), b.len()) {
+            panic!("{}", e);
+        }
+        a.iter().zip(b).map(|(x, y)| x + y).collect()
+    }
+
+    /// Computes the element-wise difference of two slices of `f64` lanes, matching `SUBPD`.
+    ///
+    /// Results follow IEEE 754, including NaN propagation, infinities and signed zeros.
+    ///
+    /// # Arguments
+    /// * `a` - The first operand lanes.
+    /// * `b` - The second operand lanes.
+    ///
+    /// # Returns
+    /// A `Vec<f64>` holding `a[i] - b[i]` for each lane.
+    ///
+    /// # Panics
+    /// Panics if the slices differ in length.
+    pub fn f64vec_sub(a: &[f64], b: &[f64]) -> Vec<f64> {
+        if let Err(e) = Self::check_lengths(a.len(), b.len()) {
+            panic!("{}", e);
+        }
+        a.iter().zip(b).map(|(x, y)| x - y).collect()
+    }
+
+    /// Computes the element-wise product of two slices of `f64` lanes, matching `MULPD`.
+    ///
+    /// Results follow IEEE 754, including NaN propagation, infinities and signed zeros.
+    ///
+    /// # Arguments
+    /// * `a` - The first operand lanes.
+    /// * `b` - The second operand lanes.
+    ///
+    /// # Returns
+    /// A `Vec<f64>` holding `a[i] * b[i]` for each lane.
+    ///
+    /// # Panics
+    /// Panics if the slices differ in length.
+    pub fn f64vec_mul(a: &[f64], b: &[f64]) -> Vec<f64> {
+        if let Err(e) = Self::check_lengths(a.len(), b.len()) {
+            panic!("{}", e);
+        }
+        a.iter().zip(b).map(|(x, y)| x * y).collect()
+    }
+
+    /// Computes the element-wise quotient of two slices of `f64` lanes, matching `DIVPD`.
+    ///
+    /// Results follow IEEE 754, including NaN propagation, infinities and signed zeros.
+    ///
+    /// # Arguments
+    /// * `a` - The first operand lanes.
+    /// * `b` - The second operand lanes.
+    ///
+    /// # Returns
+    /// A `Vec<f64>` holding `a[i] / b[i]` for each lane.
+    ///
+    /// # Panics
+    /// Panics if the slices differ in length.
+    pub fn f64vec_div(a: &[f64], b: &[f64]) -> Vec<f64> {
+        if let Err(e) = Self::check_lengths(a.len(), b.len()) {
+            panic!("{}", e);
+        }
+        a.iter().zip(b).map(|(x, y)| x / y).collect()
+    }
+
+    /// Computes the element-wise square root of a slice of `f64` lanes, matching `SQRTPD`.
+    ///
+    /// Negative inputs other than `-0.0` produce NaN, as required by IEEE 754.
+    ///
+    /// # Arguments
+    /// * `a` - The operand lanes.
+    ///
+    /// # Returns
+    /// A `Vec<f64>` holding the square root of each lane.
+    pub fn f64vec_sqrt(a: &[f64]) -> Vec<f64> {
+        a.iter().map(|x| x.sqrt()).collect()
+    }
+
+    /// Computes the fused multiply-add `a[i] * b[i] + c[i]` of `f32` lanes, matching `VFMADD231PS`.
+    ///
+    /// The product is not rounded before the addition; only the final result is rounded once,
+    /// so the output may differ from computing `a[i] * b[i] + c[i]` in two steps.
+    ///
+    /// # Arguments
+    /// * `a` - The multiplicand lanes.
+    /// * `b` - The multiplier lanes.
+    /// * `c` - The lanes added to the product.
+    ///
+    /// # Returns
+    /// A `Vec<f32>` holding the fused result of each lane.
+    ///
+    /// # Panics
+    /// Panics if the slices differ in length.
+    pub fn f32vec_fmadd(a: &[f32], b: &[f32], c: &[f32]) -> Vec<f32> {
+        if a.len() != b.len() || a.len() != c.len() {
+            panic!("Invalid operand lengths {}, {} and {}", a.len(), b.len(), c.len());
+        }
+        (0..a.len()).map(|i| a[i].mul_add(b[i], c[i])).collect()
+    }
+
+    /// Computes the fused multiply-subtract `a[i] * b[i] - c[i]` of `f32` lanes, matching `VFMSUB231PS`.
+    ///
+    /// The product is not rounded before the subtraction; only the final result is rounded once,
+    /// so the output may differ from computing `a[i] * b[i] - c[i]` in two steps.
+    ///
+    /// # Arguments
+    /// * `a` - The multiplicand lanes.
+    /// * `b` - The multiplier lanes.
+    /// * `c` - The lanes subtracted from the product.
+    ///
+    /// # Returns
+    /// A `Vec<f32>` holding the fused result of each lane.
+    ///
+    /// # Panics
+    /// Panics if the slices differ in length.
+    pub fn f32vec_fmsub(a: &[f32], b: &[f32], c: &[f32]) -> Vec<f32> {
+        if a.len() != b.len() || a.len() != c.len() {
+            panic!("Invalid operand lengths {}, {} and {}", a.len(), b.len(), c.len());
+        }
+        (0..a.len()).map(|i| a[i].mul_add(b[i], -c[i])).collect()
+    }
+
+    /// Computes the fused multiply-add `a[i] * b[i] + c[i]` of `f64` lanes, matching `VFMADD231PD`.
+    ///
+    /// The product is not rounded before the addition; only the final result is rounded once,
+    /// so the output may differ from computing `a[i] * b[i] + c[i]` in two steps.
+    ///
+    /// # Arguments
+    /// * `a` - The multiplicand lanes.
+    /// * `b` - The multiplier lanes.
+    /// * `c` - The lanes added to the product.
+    ///
+    /// # Returns
+    /// A `Vec<f64>` holding the fused result of each lane.
+    ///
+    /// # Panics
+    /// Panics if the slices differ in length.
+    pub fn f64vec_fmadd(a: &[f64], b: &[f64], c: &[f64]) -> Vec<f64> {
+        if a.len() != b.len() || a.len() != c.len() {
+            panic!("Invalid operand lengths {}, {} and {}", a.len(), b.len(), c.len());
+        }
+        (0..a.len()).map(|i| a[i].mul_add(b[i], c[i])).collect()
+    }
+
+    /// Computes the fused multiply-subtract `a[i] * b[i] - c[i]` of `f64` lanes, matching `VFMSUB231PD`.
+    ///
+    /// The product is not rounded before the subtraction; only the final result is rounded once,
+    /// so the output may differ from computing `a[i] * b[i] - c[i]` in two steps.
+    ///
+    /// # Arguments
+    /// * `a` - The multiplicand lanes.
+    /// * `b` - The multiplier lanes.
+    /// * `c` - The lanes subtracted from the product.
+    ///
+    /// # Returns
+    /// A `Vec<f64>` holding the fused result of each lane.
+    ///
+    /// # Panics
+    /// Panics if the slices differ in length.
+    pub fn f64vec_fmsub(a: &[f64], b: &[f64], c: &[f64]) -> Vec<f64> {
+        if a.len() != b.len() || a.len() != c.len() {
+            panic!("Invalid operand lengths {}, {} and {}", a.len(), b.len(), c.len());
+        }
+        (0..a.len()).map(|i| a[i].mul_add(b[i], -c[i])).collect()
+    }
+
+    /// Computes the element-wise reciprocal `1.0 / a[i]` of `f32` lanes, standing in for `RCPPS`.
+    ///
+    /// Note that real `RCPPS` only returns an approximation with a relative error of up to 1.5 * 2^-12;
+    /// this helper uses a full-precision division instead, so results may differ from hardware in the low bits.
+    ///
+    /// # Arguments
+    /// * `a` - The operand lanes.
+    ///
+    /// # Returns
+    /// A `Vec<f32>` holding the reciprocal of each lane.
+    pub fn f32vec_rcp(a: &[f32]) -> Vec<f32> {
+        a.iter().map(|x| 1.0 / x).collect()
+    }
+
+    /// Computes the element-wise reciprocal square root `1.0 / sqrt(a[i])` of `f32` lanes, standing in for `RSQRTPS`.
+    ///
+    /// Note that real `RSQRTPS` only returns an approximation with a relative error of up to 1.5 * 2^-12;
+    /// this helper uses a full-precision computation instead, so results may differ from hardware in the low bits.
+    ///
+    /// # Arguments
+    /// * `a` - The operand lanes.
+    ///
+    /// # Returns
+    /// A `Vec<f32>` holding the reciprocal square root of each lane.
+    pub fn f32vec_rsqrt(a: &[f32]) -> Vec<f32> {
+        a.iter().map(|x| 1.0 / x.sqrt()).collect()
+    }
+
+    /// Adds adjacent pairs of `f32` lanes horizontally, matching `HADDPS`.
+    ///
+    /// This is an alias of `hadd_f32_vec`.
+    ///
+    /// # Arguments
+    /// * `a` - The first source, whose pairwise sums fill the lower half of the result.
+    /// * `b` - The second source, whose pairwise sums fill the upper half of the result.
+    ///
+    /// # Returns
+    /// A `Vec<f32>` of `a.len() / 2 + b.len() / 2` lanes.
+    ///
+    /// # Panics
+    /// Panics if either input has an odd length.
+    pub fn f32_hadd_vec(a: &[f32], b: &[f32]) -> Vec<f32> {
+        Self::hadd_f32_vec(a, b)
+    }
+
+    /// Converts an IEEE 754 half-precision value to a 32-bit floating point number (`f32`).
+    ///
+    /// The conversion is exact: subnormal halves become normal `f32` values, and infinities and NaN payloads are preserved.
+    ///
+    /// # Arguments
+    /// * `bits` - The bit pattern of the half-precision value.
+    ///
+    /// # Returns
+    /// The `f32` value equal to the half-precision input.
+    pub fn f16_to_f32(bits: u16) -> f32 {
+        let sign = ((bits & 0x8000) as u32) << 16;
+        let exponent = ((bits >> 10) & 0x1F) as u32;
+        let mantissa = (bits & 0x03FF) as u32;
+        let result = match exponent {
+            0 if mantissa == 0 => sign,
+            0 => {
+                // subnormal: normalize the mantissa
+                let mut exponent = 127 - 14;
+                let mut mantissa = mantissa;
+                while mantissa & 0x0400 == 0 {
+                    mantissa <<= 1;
+                    exponent -= 1;
+                }
+                sign | (exponent << 23) | ((mantissa & 0x03FF) << 13)
+            }
+            0x1F => sign | 0x7F80_0000 | (mantissa << 13),
+            _ => sign | ((exponent + 127 - 15) << 23) | (mantissa << 13),
+        };
+        f32::from_bits(result)
+    }
+
+    /// Converts a 32-bit floating point number (`f32`) to IEEE 754 half precision.
+    ///
+    /// Values are rounded to nearest with ties to even. Results too large for half precision become
+    /// infinities, results too small become subnormals or signed zeros, and NaNs stay quiet NaNs.
+    ///
+    /// # Arguments
+    /// * `f` - The `f32` value to be converted.
+    ///
+    /// # Returns
+    /// The bit pattern of the nearest half-precision value.
+    pub fn f32_to_f16(f: f32) -> u16 {
+        let bits = f.to_bits();
+        let sign = ((bits >> 16) & 0x8000) as u16;
+        let exponent = ((bits >> 23) & 0xFF) as i32;
+        let mantissa = bits & 0x007F_FFFF;
+        if exponent == 0xFF {
+            return if mantissa == 0 {
+                sign | 0x7C00
+            } else {
+                sign | 0x7E00 | (mantissa >> 13) as u16
+            };
+        }
+        let half_exponent = exponent - 127 + 15;
+        if half_exponent >= 0x1F {
+            return sign | 0x7C00;
+        }
+        if half_exponent <= 0 {
+            if half_exponent < -10 {
+                return sign;
+            }
+            // subnormal: shift the mantissa with its implicit bit into place and round
+            let mantissa = mantissa | 0x0080_0000;
+            let shift = (14 - half_exponent) as u32;
+            let mut half = mantissa >> shift;
+            let remainder = mantissa & ((1 << shift) - 1);
+            let halfway = 1 << (shift - 1);
+            if remainder > halfway || (remainder == halfway && half & 1 == 1) {
+                half += 1;
+            }
+            return sign | half as u16;
+        }
+        let mut half = ((half_exponent as u32) << 10) | (mantissa >> 13);
+        let remainder = mantissa & 0x1FFF;
+        if remainder > 0x1000 || (remainder == 0x1000 && half & 1 == 1) {
+            // a carry out of the mantissa correctly bumps the exponent, up to infinity
+            half += 1;
+        }
+        sign | half as u16
+    }
+
+    /// Converts a slice of `f32` values to half-precision bit patterns.
+    ///
+    /// # Arguments
+    /// * `v` - The `f32` values to be converted.
+    ///
+    /// # Returns
+    /// A `Vec<u16>` where each element is the half-precision encoding of the corresponding input.
+    pub fn f32vec_to_f16vec(v: &[f32]) -> Vec<u16> {
+        v.iter().map(|x| Self::f32_to_f16(*x)).collect()
+    }
+
+    /// Converts a slice of half-precision bit patterns to `f32` values.
+    ///
+    /// # Arguments
+    /// * `v` - The half-precision bit patterns to be converted.
+    ///
+    /// # Returns
+    /// A `Vec<f32>` where each element is the value of the corresponding half-precision input.
+    pub fn f16vec_to_f32vec(v: &[u16]) -> Vec<f32> {
+        v.iter().map(|x| Self::f16_to_f32(*x)).collect()
+    }
+
+    /// Converts a 32-bit floating point number (`f32`) to bfloat16.
+    ///
+    /// The low 16 bits of the `f32` encoding are dropped, rounding to nearest with ties to even.
+    /// NaNs are kept quiet NaNs rather than being rounded into infinities.
+    ///
+    /// # Arguments
+    /// * `f` - The `f32` value to be converted.
+    ///
+    /// # Returns
+    /// The bit pattern of the nearest bfloat16 value.
+    pub fn f32_to_bf16(f: f32) -> u16 {
+        let bits = f.to_bits();
+        if f.is_nan() {
+            return ((bits >> 16) as u16) | 0x0040;
+        }
+        let rounding = 0x7FFF + ((bits >> 16) & 1);
+        (bits.wrapping_add(rounding) >> 16) as u16
+    }
+
+    /// Converts a bfloat16 value to a 32-bit floating point number (`f32`).
+    ///
+    /// # Arguments
+    /// * `bits` - The bit pattern of the bfloat16 value.
+    ///
+    /// # Returns
+    /// The `f32` value whose upper half is `bits` and whose lower half is zero.
+    pub fn bf16_to_f32(bits: u16) -> f32 {
+        f32::from_bits((bits as u32) << 16)
+    }
+
+    /// Converts a slice of `f32` values to bfloat16 bit patterns.
+    ///
+    /// # Arguments
+    /// * `v` - The `f32` values to be converted.
+    ///
+    /// # Returns
+    /// A `Vec<u16>` where each element is the bfloat16 encoding of the corresponding input.
+    pub fn f32vec_to_bf16vec(v: &[f32]) -> Vec<u16> {
+        v.iter().map(|x| Self::f32_to_bf16(*x)).collect()
+    }
+
+    /// Converts a slice of bfloat16 bit patterns to `f32` values.
+    ///
+    /// # Arguments
+    /// * `v` - The bfloat16 bit patterns to be converted.
+    ///
+    /// # Returns
+    /// A `Vec<f32>` where each element is the value of the corresponding bfloat16 input.
+    pub fn bf16vec_to_f32vec(v: &[u16]) -> Vec<f32> {
+        v.iter().map(|x| Self::bf16_to_f32(*x)).collect()
+    }
+
+    /// Computes the carry flag (CF) of an unsigned addition of `width` bits.
+    ///
+    /// Only the low `width` bits of each operand take part in the addition. This is the CF reported
+    /// by `flags_after_add` without a carry in.
+    ///
+    /// # Arguments
+    /// * `lhs` - The first operand.
+    /// * `rhs` - The second operand.
+    /// * `width` - The operand width in bits: 8, 16, 32 or 64.
+    ///
+    /// # Returns
+    /// `true` if the addition carries out of the most significant bit.
+    ///
+    /// # Panics
+    /// Panics if `width` is not 8, 16, 32 or 64.
+    pub fn carry_flag_add(lhs: u64, rhs: u64, width: usize) -> bool {
+        Self::flags_after_add(lhs, rhs, false, width).cf
+    }
+
+    /// Computes the overflow flag (OF) of a signed addition of `width` bits.
+    ///
+    /// Only the low `width` bits of each operand take part in the addition. This is the OF reported
+    /// by `flags_after_add` without a carry in.
+    ///
+    /// # Arguments
+    /// * `lhs` - The first operand.
+    /// * `rhs` - The second operand.
+    /// * `width` - The operand width in bits: 8, 16, 32 or 64.
+    ///
+    /// # Returns
+    /// `true` if both operands have the same sign and the sign of the result differs from it.
+    ///
+    /// # Panics
+    /// Panics if `width` is not 8, 16, 32 or 64.
+    pub fn overflow_flag_add(lhs: u64, rhs: u64, width: usize) -> bool {
+        Self::flags_after_add(lhs, rhs, false, width).of
+    }
+
+    /// Computes the carry flag (CF) of an unsigned subtraction of `width` bits.
+    ///
+    /// Only the low `width` bits of each operand take part in the subtraction. This is the CF reported
+    /// by `flags_after_sub` without a borrow in.
+    ///
+    /// # Arguments
+    /// * `lhs` - The minuend.
+    /// * `rhs` - The subtrahend.
+    /// * `width` - The operand width in bits: 8, 16, 32 or 64.
+    ///
+    /// # Returns
+    /// `true` if the subtraction borrows, i.e. `rhs` is greater than `lhs`.
+    ///
+    /// # Panics
+    /// Panics if `width` is not 8, 16, 32 or 64.
+    pub fn borrow_flag_sub(lhs: u64, rhs: u64, width: usize) -> bool {
+        Self::flags_after_sub(lhs, rhs, false, width).cf
+    }
+
+    /// Returns the mask selecting the low `width` bits of an operand.
+    ///
+    /// # Panics
+    /// Panics if `width` is not 8, 16, 32 or 64.
+    fn flag_width_mask(width: usize) -> u64 {
+        match width {
+            8 | 16 | 32 => (1u64 << width) - 1,
+            64 => u64::MAX,
+            _ => panic!("Invalid operand width {}", width),
+        }
+    }
+
+    /// Computes the parity flag (PF) of a result.
+    ///
+    /// # Arguments
+    /// * `result` - The result of an operation; only its low 8 bits are considered.
+    ///
+    /// # Returns
+    /// `true` if the low byte of `result` has an even number of set bits.
+    pub fn parity_byte(result: u64) -> bool {
+        (result as u8).count_ones().is_multiple_of(2)
+    }
+
+    /// Computes the auxiliary carry flag (AF) of an 8-bit addition.
+    ///
+    /// # Arguments
+    /// * `lhs` - The first operand.
+    /// * `rhs` - The second operand.
+    ///
+    /// # Returns
+    /// `true` if the addition carries from bit 3 into bit 4.
+    pub fn adjust_flag_add(lhs: u8, rhs: u8) -> bool {
+        (lhs & 0x0F) + (rhs & 0x0F) > 0x0F
+    }
 }